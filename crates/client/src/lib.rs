@@ -46,3 +46,70 @@ impl Into<proto::PropertyValue> for Vec<u8> {
         }
     }
 }
+
+impl Into<proto::PropertyValue> for f64 {
+    fn into(self) -> proto::PropertyValue {
+        proto::PropertyValue {
+            value: Some(proto::property_value::Value::Float64(self)),
+        }
+    }
+}
+
+impl Into<proto::PropertyValue> for i64 {
+    fn into(self) -> proto::PropertyValue {
+        proto::PropertyValue {
+            value: Some(proto::property_value::Value::Int64(self)),
+        }
+    }
+}
+
+impl Into<proto::PropertyValue> for bool {
+    fn into(self) -> proto::PropertyValue {
+        proto::PropertyValue {
+            value: Some(proto::property_value::Value::Bool(self)),
+        }
+    }
+}
+
+/// Null
+impl Into<proto::PropertyValue> for () {
+    fn into(self) -> proto::PropertyValue {
+        proto::PropertyValue {
+            value: Some(proto::property_value::Value::Null(prost_types::NullValue::NullValue as i32)),
+        }
+    }
+}
+
+/// Negative durations may be given as a `prost_types::Duration`
+impl Into<proto::PropertyValue> for std::time::Duration {
+    fn into(self) -> proto::PropertyValue {
+        proto::PropertyValue {
+            value: Some(proto::property_value::Value::Duration(prost_types::Duration {
+                seconds: self.as_secs() as i64,
+                nanos: self.subsec_nanos() as i32,
+            })),
+        }
+    }
+}
+
+impl Into<proto::PropertyValue> for prost_types::Duration {
+    fn into(self) -> proto::PropertyValue {
+        proto::PropertyValue {
+            value: Some(proto::property_value::Value::Duration(self)),
+        }
+    }
+}
+
+/// A decimal number, in its canonical string form such as "-12.50"
+pub fn decimal<S: Into<String>>(decimal: S) -> proto::PropertyValue {
+    proto::PropertyValue {
+        value: Some(proto::property_value::Value::Decimal(decimal.into())),
+    }
+}
+
+/// A reference to another entity, by its full id
+pub fn entity_ref<S: Into<String>>(entity_id: S) -> proto::PropertyValue {
+    proto::PropertyValue {
+        value: Some(proto::property_value::Value::EntityRef(entity_id.into())),
+    }
+}
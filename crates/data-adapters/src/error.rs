@@ -16,7 +16,7 @@ pub enum Error<Sym: TSymbol, Val: TValue> {
     MaterializationDeclined { entity: Entity<Sym, Val>, reason: &'static str },
 }
 
-impl<'a, Sym: TSymbol, Val: TValue> std::convert::From<Error<Sym, Val>> for std::io::Error {
+impl<'a, Sym: TSymbol + std::fmt::Debug, Val: TValue + std::fmt::Debug> std::convert::From<Error<Sym, Val>> for std::io::Error {
     fn from(error: Error<Sym, Val>) -> Self {
         use std::io::ErrorKind;
         std::io::Error::new(ErrorKind::Other, format!("{:?}", error))
@@ -1,12 +1,10 @@
 pub mod test;
 pub mod typemap;
 
-use std::marker::PhantomData;
-
 use mindbase_hypergraph::{
-    entity::{directed, vertex, Property},
-    traits::{GraphInterface, TSymbol},
-    Entity, EntityId,
+    entity::{directed, vertex, MemberRole, Property},
+    traits::{GraphInterface, TSymbol, TValue},
+    EntityId,
 };
 use mindbase_types::MBValue;
 use serde_json::Value;
@@ -28,7 +26,7 @@ impl<'a, G, JsonTypeSymbol> JsonAdapter<'a, G, JsonTypeSymbol>
 where
     G: GraphInterface<JsonTypeSymbol, MBValue>,
     // W: Weight<Symbol = S> + From<DataNode<S>> + From<SubGraph<S>> + From<Type<S>>,
-    JsonTypeSymbol: TSymbol + Clone + std::fmt::Debug,
+    JsonTypeSymbol: TSymbol + Clone + PartialEq + std::fmt::Debug,
 {
     pub fn new(graph: &'a G, typemap: JsonTypeMap<JsonTypeSymbol>) -> Self {
         Self {
@@ -46,16 +44,10 @@ where
 
         let root_element = self.input_recurse(jv)?;
 
-        let json_document = self.graph.insert(vertex(Property {
-            key: self.typemap.to_sym(JsonType::Document),
-            value: MBValue::String(filename),
-        }))?;
+        let json_document = self.graph.insert(vertex(self.typed(JsonType::Document, MBValue::String(filename))))?;
 
-        self.graph.insert(directed(
-            Type(self.typemap.to_sym(JsonType::RootElement)),
-            [json_document],
-            [root_element],
-        ))?;
+        self.graph
+            .insert(directed(self.marker(JsonType::RootElement), [json_document], [root_element]))?;
 
         Ok(json_document)
     }
@@ -65,91 +57,98 @@ where
         // might be a document, or a root node
         let entity = self.graph.get(&entity_id)?;
 
-        match &entity.weight {
-            Artifact::Node(DataNode { ref data_type, data }) => {
-                match self.typemap.from_sym(data_type, self.graph)? {
-                    JsonType::Document => {
-                        // This is dumb. it should not be a fulter function
-                        let roots = self.graph.get_adjacencies_matching(entity_id, |a| {
-                            if let Artifact::Type(Type(ty)) = a {
-                                let score = ty.compare(&self.typemap.RootElement, self.graph)?;
-
-                                if score > 0.7 {
-                                    return Ok(true);
-                                }
-                            }
-                            Ok(false)
-                        })?;
-                        if roots.len() == 0 {
-                            return Err(Error::InvariantViolation("No RootElement found for Document"));
-                        }
-                        if roots.len() > 1 {
-                            return Err(Error::InvariantViolation("Multiple RootElements found for Document"));
-                        }
-                        let root_id = roots[0];
-                        Ok((data.as_ref().map(|b| String::from_utf8_lossy(&b).to_string()), root_id))
-                    },
-                    JsonType::Null | JsonType::Bool | JsonType::Number | JsonType::Array | JsonType::Object => {
-                        // These are legal node types to start rendering
-                        Ok((None, *entity_id))
-                    },
-                    _ => Err(Error::MaterializationDeclined {
-                        entity,
-                        reason: "Invalid root JSON entity",
-                    }),
+        let json_type = entity.properties.iter().find_map(|p| self.typemap.from_sym(&p.key));
+
+        match json_type {
+            Some(JsonType::Document) if entity.is_vertex() => {
+                let filename = entity.properties.iter().find_map(|p| match &p.value {
+                    MBValue::String(filename) if self.typemap.is(&p.key, JsonType::Document) => Some(filename.clone()),
+                    _ => None,
+                });
+
+                let roots = self
+                    .graph
+                    .get_adjacencies_matching(entity_id, |key, _| Ok(self.typemap.is(key, JsonType::RootElement)))?;
+                if roots.len() == 0 {
+                    return Err(Error::InvariantViolation("No RootElement found for Document"));
+                }
+                if roots.len() > 1 {
+                    return Err(Error::InvariantViolation("Multiple RootElements found for Document"));
                 }
+
+                // The RootElement hyperedge points from the document to its root
+                let root_id = self
+                    .graph
+                    .get(&roots[0])?
+                    .members()
+                    .into_iter()
+                    .find(|(_, role)| *role == MemberRole::To)
+                    .map(|(member, _)| member.entity_id)
+                    .ok_or(Error::InvariantViolation("RootElement has no root"))?;
+
+                Ok((filename, root_id))
             },
-            Artifact::Agent(_) => Err(Error::MaterializationDeclined {
-                entity,
-                reason: "Entity should be a node, but is an Agent",
-            }),
-            Artifact::Url(_) => Err(Error::MaterializationDeclined {
-                entity,
-                reason: "Entity should be a node, but is a Url",
-            }),
-            Artifact::FlatText(_) => Err(Error::MaterializationDeclined {
+            Some(JsonType::Null)
+            | Some(JsonType::Bool)
+            | Some(JsonType::Number)
+            | Some(JsonType::String)
+            | Some(JsonType::Array)
+            | Some(JsonType::Object)
+                if entity.is_vertex() =>
+            {
+                // These are legal node types to start rendering
+                Ok((None, *entity_id))
+            },
+            Some(_) => Err(Error::MaterializationDeclined {
                 entity,
-                reason: "Entity should be a node, but is a FlatText",
+                reason: "Invalid root JSON entity",
             }),
-            Artifact::Type(v) => Err(Error::MaterializationDeclined {
+            None => Err(Error::MaterializationDeclined {
                 entity,
-                reason: "Entity should be a node, but is a Type",
+                reason: "Entity has no JSON type property",
             }),
         }
     }
-    pub fn write<'b, R: std::io::Write>(&self, writer: R, entity_id: EntityId) -> Result<(), Error<Artifact<JsonTypeSymbol>>> {
-        let mut cycleguard = CycleGuard::default();
+    pub fn write<'b, R: std::io::Write>(&self, _writer: R, entity_id: EntityId) -> Result<(), Error<JsonTypeSymbol, MBValue>> {
+        let _cycleguard = CycleGuard::default();
 
         // skip over the document vertex, if applicable
-        let (_filename, root_id) = self.get_filename_and_root(&entity_id)?;
+        let (_filename, _root_id) = self.get_filename_and_root(&entity_id)?;
 
         // self.output_recurse(&mut cycleguard, &root_id, &writer)?;
 
         Ok(())
     }
 
-    fn input_recurse<'b, W: Value>(&self, v: Value) -> Result<EntityId, Error<W>> {
-        let tm = &self.typemap;
+    /// A single property identifying the JSON type of an entity, along with its data
+    fn typed(&self, jt: JsonType, value: MBValue) -> Vec<Property<JsonTypeSymbol, MBValue>> {
+        vec![Property {
+            key: self.typemap.to_sym(jt),
+            value,
+        }]
+    }
+    /// A property identifying the JSON type of an entity which carries no data of its own
+    fn marker(&self, jt: JsonType) -> Vec<Property<JsonTypeSymbol, MBValue>> {
+        self.typed(jt, MBValue::Bytes(vec![]))
+    }
+
+    fn input_recurse(&self, v: Value) -> Result<EntityId, Error<JsonTypeSymbol, MBValue>> {
         Ok(match v {
-            Value::Null => self.graph.insert(vertex(DataNode {
-                data_type: tm.to_sym(JsonType::Null),
-                data: None,
-            }))?,
-            Value::Bool(b) => self.graph.insert(vertex(DataNode {
-                data_type: tm.to_sym(JsonType::Bool),
-                data: Some(vec![b as u8]),
-            }))?,
-            Value::Number(n) => self.graph.insert(vertex(DataNode {
-                data_type: tm.to_sym(JsonType::Number),
-                data: Some(n.as_i64().unwrap().to_ne_bytes().to_vec()),
-            }))?,
-            Value::String(s) => self.graph.insert(vertex(DataNode {
-                data_type: tm.to_sym(JsonType::String),
-                data: Some(s.as_bytes().to_vec()),
-            }))?,
+            Value::Null => self.graph.insert(vertex(self.typed(JsonType::Null, MBValue::Null)))?,
+            Value::Bool(b) => self.graph.insert(vertex(self.typed(JsonType::Bool, MBValue::Bool(b))))?,
+            Value::Number(n) => {
+                // Integers too large for an Int64 are kept in their JSON representation, rather than lose precision
+                let value = match (n.as_i64(), n.as_f64()) {
+                    (Some(v), _) => MBValue::Int64(v),
+                    (None, Some(v)) if n.is_f64() => MBValue::Float64(v),
+                    _ => MBValue::Json(n.to_string().into_bytes()),
+                };
+                self.graph.insert(vertex(self.typed(JsonType::Number, value)))?
+            },
+            Value::String(s) => self.graph.insert(vertex(self.typed(JsonType::String, MBValue::String(s))))?,
             Value::Array(values) => {
                 //First define the array node itself
-                let arr = self.graph.insert(vertex(Type(tm.to_sym(JsonType::Array))))?;
+                let arr = self.graph.insert(vertex(self.marker(JsonType::Array)))?;
 
                 // now recurse
                 let mut members: Vec<EntityId> = Vec::with_capacity(values.len());
@@ -157,43 +156,37 @@ where
                     let member = self.input_recurse(value)?;
 
                     self.graph.insert(directed(
-                        DataNode {
-                            data_type: self.typemap.to_sym(JsonType::ArrayOffset),
-                            data: Some(i.to_ne_bytes().to_vec()),
-                        },
+                        self.typed(JsonType::ArrayOffset, MBValue::Uint32(i as u32)),
                         [arr],
                         [member],
                     ))?;
 
                     if i == 0 {
                         self.graph
-                            .insert(directed(Type(tm.to_sym(JsonType::ArrHead)), [arr], [member]))?;
+                            .insert(directed(self.marker(JsonType::ArrHead), [arr], [member]))?;
                     } else {
                         let prev = *members.last().unwrap();
                         self.graph
-                            .insert(directed(Type(tm.to_sym(JsonType::ArrNextMember)), [prev], [member]))?;
+                            .insert(directed(self.marker(JsonType::ArrNextMember), [prev], [member]))?;
                         self.graph
-                            .insert(directed(Type(tm.to_sym(JsonType::ArrPrevMember)), [member], [prev]))?;
+                            .insert(directed(self.marker(JsonType::ArrPrevMember), [member], [prev]))?;
                     };
 
                     members.push(member);
                 }
                 if let Some(tail) = members.last() {
                     self.graph
-                        .insert(directed(Type(tm.to_sym(JsonType::ArrTail)), [arr], [*tail]))?;
+                        .insert(directed(self.marker(JsonType::ArrTail), [arr], [*tail]))?;
                 }
 
                 self.graph
-                    .insert(directed(Type(tm.to_sym(JsonType::ArrayMember)), [arr], members))?;
+                    .insert(directed(self.marker(JsonType::ArrayMember), [arr], members))?;
 
                 arr
             },
             Value::Object(values) => {
                 //First define the array node itself
-                let obj = self.graph.insert(vertex(DataNode {
-                    data_type: tm.to_sym(JsonType::Object),
-                    data: None,
-                }))?;
+                let obj = self.graph.insert(vertex(self.marker(JsonType::Object)))?;
                 let mut properties: Vec<EntityId> = Vec::with_capacity(values.len());
                 let mut members: Vec<EntityId> = Vec::with_capacity(values.len());
 
@@ -201,12 +194,8 @@ where
                 for (key, value) in values {
                     let member = self.input_recurse(value)?;
 
-                    // TODO1 - reconcile HyperedgeWeight with symbolic types
                     let prop = self.graph.insert(directed(
-                        DataNode {
-                            data_type: tm.to_sym(JsonType::ObjectProperty),
-                            data: Some(key.bytes().collect()),
-                        },
+                        self.typed(JsonType::ObjectProperty, MBValue::String(key)),
                         [obj],
                         [member],
                     ))?;
@@ -215,9 +204,9 @@ where
                     properties.push(prop);
                 }
                 self.graph
-                    .insert(directed(Type(tm.to_sym(JsonType::ObjectMembers)), [obj], members.clone()))?;
+                    .insert(directed(self.marker(JsonType::ObjectMembers), [obj], members))?;
                 self.graph
-                    .insert(directed(Type(tm.to_sym(JsonType::ObjectProperties)), [obj], members))?;
+                    .insert(directed(self.marker(JsonType::ObjectProperties), [obj], properties))?;
 
                 obj
             },
@@ -320,7 +309,8 @@ where
 struct CycleGuard(Vec<EntityId>);
 
 impl<'a> CycleGuard {
-    fn push<W: Value>(&mut self, entity: &EntityId) -> Result<(), Error<W>> {
+    #[allow(dead_code)]
+    fn push<Sym: TSymbol, Val: TValue>(&mut self, entity: &EntityId) -> Result<(), Error<Sym, Val>> {
         match self.0.binary_search(entity) {
             Ok(_) => Err(Error::CycleDetected),
             Err(i) => {
@@ -329,13 +319,14 @@ impl<'a> CycleGuard {
             },
         }
     }
-    fn pop<W: Value>(&mut self, entity_id: &EntityId) -> Result<(), Error<W>> {
+    #[allow(dead_code)]
+    fn pop<Sym: TSymbol, Val: TValue>(&mut self, entity_id: &EntityId) -> Result<(), Error<Sym, Val>> {
         match self.0.binary_search(&entity_id) {
             Ok(i) => {
                 self.0.remove(i);
                 Ok(())
             },
-            Err(i) => Err(Error::Sanity),
+            Err(_) => Err(Error::Sanity),
         }
     }
 }
@@ -1,9 +1,4 @@
-use std::cmp::Ordering;
-
-use mindbase_hypergraph::traits::{GraphInterface, TSymbol, TValue};
-use mindbase_types::MBValue;
-
-use crate::Error;
+use mindbase_hypergraph::traits::TSymbol;
 
 #[allow(non_snake_case)]
 pub struct JsonTypeMap<T>
@@ -29,7 +24,10 @@ where
     pub RootElement: T,
 }
 
-#[derive(Clone, Copy)]
+/// Symbols must be more similar than this to a type's symbol to stand for that type
+pub const TYPE_SIMILARITY_THRESHOLD: f64 = 0.7;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum JsonType {
     Document,
     Null,
@@ -50,9 +48,29 @@ pub enum JsonType {
     RootElement,
 }
 
+const JSON_TYPES: [JsonType; 17] = [
+    JsonType::Document,
+    JsonType::Null,
+    JsonType::Bool,
+    JsonType::Number,
+    JsonType::String,
+    JsonType::Array,
+    JsonType::ArrayMember,
+    JsonType::ArrayOffset,
+    JsonType::ArrNextMember,
+    JsonType::ArrPrevMember,
+    JsonType::ArrHead,
+    JsonType::ArrTail,
+    JsonType::Object,
+    JsonType::ObjectProperty,
+    JsonType::ObjectProperties,
+    JsonType::ObjectMembers,
+    JsonType::RootElement,
+];
+
 impl<TypeSymbol> JsonTypeMap<TypeSymbol>
 where
-    TypeSymbol: TSymbol + Clone + std::fmt::Debug,
+    TypeSymbol: TSymbol + Clone + PartialEq + std::fmt::Debug,
 {
     pub fn to_sym(&self, jt: JsonType) -> TypeSymbol {
         match jt {
@@ -75,42 +93,19 @@ where
             JsonType::RootElement => self.RootElement.clone(),
         }
     }
-    pub fn from_sym<'a, G, W: TValue>(&self, symbol: &TypeSymbol, graph: &G) -> Result<JsonType, Error<W>>
-    where
-        G: GraphInterface<TypeSymbol>,
-    {
-        // Who should decide this?
-        // How is it calculated for hyperedges?
-
-        // TODO - cache the comparison per symbol
-        let threshold = 0.7;
-
-        struct Comp(JsonType, f64);
-        let mut candidates = [
-            Comp(JsonType::Document, symbol.compare(&self.Document, graph)?),
-            Comp(JsonType::Null, symbol.compare(&self.Null, graph)?),
-            Comp(JsonType::Bool, symbol.compare(&self.Bool, graph)?),
-            Comp(JsonType::Number, symbol.compare(&self.Number, graph)?),
-            Comp(JsonType::String, symbol.compare(&self.String, graph)?),
-            Comp(JsonType::Array, symbol.compare(&self.Array, graph)?),
-            Comp(JsonType::ArrayMember, symbol.compare(&self.ArrayMember, graph)?),
-            Comp(JsonType::ArrayOffset, symbol.compare(&self.ArrayOffset, graph)?),
-            Comp(JsonType::ArrNextMember, symbol.compare(&self.ArrNextMember, graph)?),
-            Comp(JsonType::ArrPrevMember, symbol.compare(&self.ArrPrevMember, graph)?),
-            Comp(JsonType::ArrHead, symbol.compare(&self.ArrHead, graph)?),
-            Comp(JsonType::ArrTail, symbol.compare(&self.ArrTail, graph)?),
-            Comp(JsonType::Object, symbol.compare(&self.Object, graph)?),
-            Comp(JsonType::ObjectProperty, symbol.compare(&self.ObjectProperty, graph)?),
-            Comp(JsonType::ObjectProperties, symbol.compare(&self.ObjectProperties, graph)?),
-            Comp(JsonType::ObjectMembers, symbol.compare(&self.ObjectMembers, graph)?),
-            Comp(JsonType::RootElement, symbol.compare(&self.RootElement, graph)?),
-        ];
-        candidates.sort_by(|Comp(_, a), Comp(_, b)| b.partial_cmp(a).unwrap_or(Ordering::Greater));
-
-        if candidates[0].1 > threshold {
-            return Ok(candidates[0].0.clone());
-        } else {
-            Err(Error::SymbolResolution)
+    /// Whether the symbol stands for the given JSON type, by `TSymbol::similarity`
+    pub fn is(&self, symbol: &TypeSymbol, jt: JsonType) -> bool {
+        symbol.similarity(&self.to_sym(jt)) > TYPE_SIMILARITY_THRESHOLD
+    }
+    /// Identify the JSON type which a symbol stands for, if any. Where it resembles more than one, the most similar wins
+    pub fn from_sym(&self, symbol: &TypeSymbol) -> Option<JsonType> {
+        let mut best: Option<(JsonType, f64)> = None;
+        for jt in JSON_TYPES.iter().copied() {
+            let score = symbol.similarity(&self.to_sym(jt));
+            if score > TYPE_SIMILARITY_THRESHOLD && !matches!(best, Some((_, best_score)) if best_score >= score) {
+                best = Some((jt, score));
+            }
         }
+        best.map(|(jt, _)| jt)
     }
 }
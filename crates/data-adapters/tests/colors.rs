@@ -41,6 +41,9 @@ fn colors() -> Result<(), std::io::Error> {
     println!("Document name: {:?}", filename);
     println!("Document root {}", root_id);
 
+    assert_eq!(filename, Some("colors.json".to_string()));
+    assert_eq!(adapter.get_filename_and_root(&root_id)?, (None, root_id));
+
     adapter.write(&mut out, root_id)?;
 
     Ok(())
@@ -0,0 +1,27 @@
+use mindbase_data_adapters::json::{test::TestJSONSymbol, JsonAdapter};
+use mindbase_hypergraph::Hypergraph;
+use mindbase_types::MBValue;
+
+/// JSON scalars are stored as the MBValue of the same kind
+#[test]
+fn scalars() -> Result<(), std::io::Error> {
+    let v = r#"{"offset": -3, "reading": 21.5, "on": true, "unit": null, "big": 18446744073709551615}"#;
+    let graph: Hypergraph<_, TestJSONSymbol, MBValue> = Hypergraph::memory();
+
+    let adapter = JsonAdapter::new(&graph, TestJSONSymbol::typemap());
+    adapter.load(v.as_bytes(), "scalars.json".to_string())?;
+
+    for (symbol, value) in [
+        (TestJSONSymbol::Number, MBValue::Int64(-3)),
+        (TestJSONSymbol::Number, MBValue::Float64(21.5)),
+        (TestJSONSymbol::Bool, MBValue::Bool(true)),
+        (TestJSONSymbol::Null, MBValue::Null),
+        (TestJSONSymbol::Number, MBValue::Json(b"18446744073709551615".to_vec())),
+    ]
+    .iter()
+    {
+        assert_eq!(graph.find_by_property(symbol, value)?.len(), 1, "{:?}", value);
+    }
+
+    Ok(())
+}
@@ -37,7 +37,7 @@ where
 }
 
 // Fuzzy set where membership may be negative or positive
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct FuzzySet<M>(Vec<Item<M>>)
 where
     M: Member + Clone;
@@ -167,6 +167,30 @@ where
 
         sum_of_squares.sqrt()
     }
+    /// How alike two sets are, from 0.0 to 1.0: the sum of the lesser degree of each member over the sum of the greater.
+    /// Negative degrees count as no membership. Sets with no members in common are not alike at all, even if both are empty
+    pub fn similarity(&self, other: &Self) -> f64 {
+        use itertools::{EitherOrBoth, Itertools};
+        let iter = self.0.iter().merge_join_by(other.0.iter(), |a, b| a.member.cmp(&b.member));
+
+        let mut lesser: f64 = 0.0;
+        let mut greater: f64 = 0.0;
+        for either in iter {
+            match either {
+                EitherOrBoth::Both(l, r) => {
+                    lesser += l.degree.min(r.degree).max(0.0);
+                    greater += l.degree.max(r.degree).max(0.0);
+                }
+                EitherOrBoth::Left(item) | EitherOrBoth::Right(item) => greater += item.degree.max(0.0),
+            };
+        }
+
+        if greater > 0.0 {
+            lesser / greater
+        } else {
+            0.0
+        }
+    }
     pub fn intersect(&mut self, other: &Self) {
         // Don't keep searching the front of the list over and over
         // TODO
@@ -0,0 +1,16 @@
+use mindbase_fuzzyset::{fuzzyset::FuzzySet, test_util::SimpleMember};
+
+#[test]
+fn similarity() {
+    let full_name: FuzzySet<SimpleMember> = FuzzySet::from_list(&[("full", 1.0), ("name", 1.0)]);
+    let name: FuzzySet<SimpleMember> = FuzzySet::from_list(&[("name", 1.0)]);
+    let first_name: FuzzySet<SimpleMember> = FuzzySet::from_list(&[("first", 0.5), ("name", 1.0)]);
+    let not_name: FuzzySet<SimpleMember> = FuzzySet::from_list(&[("name", -1.0)]);
+
+    assert_eq!(full_name.similarity(&full_name), 1.0);
+    assert_eq!(full_name.similarity(&name), 0.5);
+    assert_eq!(name.similarity(&full_name), 0.5);
+    assert_eq!(full_name.similarity(&first_name), 1.0 / 2.5);
+    assert_eq!(name.similarity(&not_name), 0.0);
+    assert_eq!(FuzzySet::<SimpleMember>::new().similarity(&FuzzySet::new()), 0.0);
+}
@@ -1,13 +1,23 @@
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use sha2::{Digest, Sha512Trunc256};
+use serde::{Deserialize, Serialize};
+
+use std::{collections::BTreeMap, ops::Bound};
 
 use crate::{
-    entity::{EntityInner, EntityIx},
+    changes::{ChangeId, Filter, Subscription},
+    entity::{
+        Adjacency, Amendment, Attribution, EntityInner, EntityIx, Property, ProvenanceIx, SymbolIx, ValueHash, Version,
+        VALUE_HASH_LEN,
+    },
+    index,
+    text::{Hit, Postings, TextQuery},
     traits::{TProvenance, TSymbol, TValue},
     Entity, EntityId, Error,
 };
 
+pub mod kv;
+pub mod memory;
 pub mod sled;
+pub mod toboggan;
 
 pub trait StorageAdapter<Sym, Val, Prov = ()>
 where
@@ -15,20 +25,370 @@ where
     Val: TValue,
     Prov: TProvenance,
 {
-    fn insert(&self, entity: Entity<Sym, Val>) -> Result<(EntityIx, EntityId), Error>;
+    /// Insert an entity, attributing it and all of its properties to the provenance if given
+    fn insert(&self, entity: Entity<Sym, Val>, provenance: Option<&Prov>) -> Result<(EntityIx, EntityId), Error>;
+    /// Insert all of the given entities under their pre-assigned ids, atomically
+    fn insert_batch(&self, entities: Vec<(EntityId, Entity<Sym, Val>, Option<Prov>)>) -> Result<Vec<EntityIx>, Error>;
     fn get_by_ix(&self, entity_ix: &EntityIx) -> Result<Entity<Sym, Val>, Error>;
-    // fn put_symbol<T: Into<Sym>>(&mut self, into_sym: T) -> Result<(SymbolRef, SymbolId), Error>;
+    /// Read an entity by id, with its current properties
+    fn get(&self, entity_id: &EntityId) -> Result<Entity<Sym, Val>, Error>;
+    /// Read an entity by id, along with the provenance of it and of each of its current properties
+    fn get_attributed(&self, entity_id: &EntityId) -> Result<(Entity<Sym, Val>, Attribution<Prov>), Error>;
+    /// List up to `limit` entities of the given kind with ids inside the range, in `EntityId` (and so creation) order
+    fn scan(
+        &self, range: (Bound<EntityId>, Bound<EntityId>), kind: EntityKind, limit: usize,
+    ) -> Result<Entities<Sym, Val>, Error>;
+    /// List up to `limit` entities whose id starts with the given base64, in either the form of `EntityId::full` or of `EntityId::short`
+    fn find_by_id_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<EntityId>, Error>;
+    /// List the hyperedges which include the given entity, along with the role it plays in each
+    fn get_adjacencies(&self, entity_id: &EntityId) -> Result<Vec<Adjacency>, Error>;
+    /// List the hyperedges which include the given entity, and have at least one property matching the filter
+    fn get_adjacencies_matching<F>(&self, entity_id: &EntityId, filter: F) -> Result<Vec<Adjacency>, Error>
+    where
+        F: Fn(&Sym, &Val) -> Result<bool, Error>;
+    /// List the entities which have a property with exactly this key and value
+    fn find_by_property(&self, key: &Sym, value: &Val) -> Result<Vec<EntityId>, Error>;
+    /// List the entities which have a property with this value, and a key at least `threshold` similar to the given one.
+    /// Those with the most similar keys are listed first
+    fn find_by_similar_property(&self, key: &Sym, value: &Val, threshold: f64) -> Result<Vec<EntityId>, Error>;
+    /// List the entities which have a property with this key, and a value within the range, in value order
+    fn range_by_property(
+        &self, key: &Sym, range: (Bound<&Val>, Bound<&Val>), direction: ScanDirection, limit: Option<usize>,
+    ) -> Result<Vec<EntityId>, Error>;
+    /// Find entities by the words in the text of their property values, best ranked first
+    fn search(&self, query: &TextQuery<Sym>) -> Result<Vec<Hit>, Error>;
+    /// Remove an entity, tombstoning its id and removing it from all indexes.
+    /// Returns the ids of every entity removed, including any hyperedges removed by the cascade
+    fn remove(&self, entity_id: &EntityId, cascade: Cascade) -> Result<Vec<EntityId>, Error>;
+    /// Has this entity been removed?
+    fn is_removed(&self, entity_id: &EntityId) -> Result<bool, Error>;
+    /// List the members of a hyperedge which have been removed out from under it
+    fn get_dangling_members(&self, entity_id: &EntityId) -> Result<Vec<EntityId>, Error>;
+    /// Apply the amendments to the properties of an existing entity, all together as a single new version.
+    /// Appended and replacement properties are attributed to the provenance if given.
+    /// Prior versions are retained. Returns the new version
+    fn amend(&self, entity_id: &EntityId, amendments: Vec<Amendment<Sym, Val>>, provenance: Option<&Prov>)
+        -> Result<Version, Error>;
+    /// The current version of an entity's properties
+    fn get_version(&self, entity_id: &EntityId) -> Result<Version, Error>;
+    /// Get the properties of an entity as they are now, or as they were at the given version
+    fn get_properties(&self, entity_id: &EntityId, version: Option<Version>) -> Result<Vec<Property<Sym, Val>>, Error>;
+    /// Intern the symbol, returning its compact id. Interning a symbol which has been seen before returns the same id
+    fn put_symbol(&self, symbol: &Sym) -> Result<SymbolIx, Error>;
+    /// Look up the id of a symbol, if it has been interned
+    fn get_symbol_ix(&self, symbol: &Sym) -> Result<Option<SymbolIx>, Error>;
+    /// Every interned symbol which is at least `threshold` similar to the given one, and how similar, most similar first
+    fn find_similar_symbols(&self, symbol: &Sym, threshold: f64) -> Result<Vec<(SymbolIx, f64)>, Error>;
+    /// Look up an interned symbol by its id
+    fn get_symbol(&self, symbol_ix: SymbolIx) -> Result<Sym, Error>;
+    /// Fetch a value which was stored by reference. Values shorter than a `ValueHash` are stored inline,
+    /// and may not be fetched this way
+    fn get_value(&self, hash: &ValueHash) -> Result<Val, Error>;
+    /// Subscribe to changes matching the filter as they are committed, first catching up on those after the given change if any
+    fn subscribe(&self, filter: Filter<Sym>, after: Option<ChangeId>) -> Result<Subscription<Sym>, Error>;
+    /// Remove every change before the cut-off from the change log, returning how many were removed
+    fn trim_changes(&self, before: ChangeId) -> Result<usize, Error>;
+}
+
+/// Entities listed along with their ids, as by a scan
+pub type Entities<Sym, Val> = Vec<(EntityId, Entity<Sym, Val>)>;
+
+/// The kinds of entity to include in a scan
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntityKind {
+    Any,
+    Vertex,
+    /// Hyperedges, whether directed or not
+    Edge,
+}
+
+impl EntityKind {
+    fn includes(&self, inner: &EntityInner) -> bool {
+        match self {
+            EntityKind::Any => true,
+            EntityKind::Vertex => matches!(inner, EntityInner::Vertex),
+            EntityKind::Edge => !matches!(inner, EntityInner::Vertex),
+        }
+    }
+}
+
+/// The order in which to return the results of a range scan
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanDirection {
+    Forward,
+    Reverse,
+}
+
+/// What to do with the hyperedges which reference an entity being removed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cascade {
+    /// Remove the referencing hyperedges too, and any hyperedges which reference those, and so on
+    Remove,
+    /// Leave the referencing hyperedges in place, but flag the removed entity as a dangling member of each
+    FlagDangling,
+}
+
+/// The entities to be removed, in the order they were discovered,
+/// and the (hyperedge, missing member) pairs which will be left dangling
+struct RemovalPlan {
+    remove: Vec<EntityId>,
+    dangling: Vec<(EntityId, EntityId)>,
+}
+
+impl RemovalPlan {
+    fn new<F>(entity_id: &EntityId, cascade: Cascade, get_adjacencies: F) -> Result<Self, Error>
+    where
+        F: Fn(&EntityId) -> Result<Vec<Adjacency>, Error>,
+    {
+        let mut plan = RemovalPlan {
+            remove: vec![*entity_id],
+            dangling: Vec::new(),
+        };
+
+        let mut i = 0;
+        while i < plan.remove.len() {
+            let removing = plan.remove[i];
+            for adjacency in get_adjacencies(&removing)? {
+                match cascade {
+                    Cascade::Remove => {
+                        if !plan.remove.contains(&adjacency.hyperedge) {
+                            plan.remove.push(adjacency.hyperedge);
+                        }
+                    },
+                    Cascade::FlagDangling => plan.dangling.push((adjacency.hyperedge, removing)),
+                }
+            }
+            i += 1;
+        }
+
+        // A hyperedge which is itself being removed cannot dangle
+        let remove = &plan.remove;
+        plan.dangling.retain(|(hyperedge, _)| !remove.contains(hyperedge));
+        plan.dangling.dedup();
+
+        Ok(plan)
+    }
 }
 
-// #[derive(Serialize, Deserialize)]
-// struct StoredProperty(SymbolId, ValueRef);
-#[derive(Serialize, Deserialize)]
-struct StoredProperty(Vec<u8>, Vec<u8>);
+/// Stores symbols, large values and provenance ahead of the entity records which refer to them.
+/// All are write-once, so anything stored for a record which then fails to be written is merely unreferenced
+trait Intern {
+    fn put_symbol_bytes(&self, bytes: &[u8]) -> Result<SymbolIx, Error>;
+    fn put_value_bytes(&self, hash: &ValueHash, bytes: &[u8]) -> Result<(), Error>;
+    /// Store a provenance record under a freshly allocated id. Unlike symbols, these are not deduplicated
+    fn put_provenance_bytes(&self, bytes: &[u8]) -> Result<ProvenanceIx, Error>;
+}
+
+/// Resolves interned symbols, content-addressed values and provenance back to their serialized bytes
+trait Resolve {
+    fn get_symbol_bytes(&self, symbol_ix: SymbolIx) -> Result<Vec<u8>, Error>;
+    fn get_value_bytes(&self, hash: &ValueHash) -> Result<Vec<u8>, Error>;
+    fn get_provenance_bytes(&self, provenance_ix: ProvenanceIx) -> Result<Vec<u8>, Error>;
+}
 
-#[derive(Serialize, Deserialize)]
-pub(crate) struct StoredEntity(EntityId, Vec<StoredProperty>, EntityInner);
+/// Store the provenance of an insertion or amendment once, for every record it covers to refer to
+fn intern_provenance<Prov: TProvenance, I: Intern>(provenance: Option<&Prov>, intern: &I) -> Result<Option<ProvenanceIx>, Error> {
+    provenance
+        .map(|provenance| intern.put_provenance_bytes(&TProvenance::serialize(provenance)))
+        .transpose()
+}
+
+/// Copy a provenance record into another adapter, once per record however many properties share it
+fn translate_provenance<R: Resolve, I: Intern>(
+    provenance_ix: Option<ProvenanceIx>, from: &R, to: &I, translated: &mut BTreeMap<ProvenanceIx, ProvenanceIx>,
+) -> Result<Option<ProvenanceIx>, Error> {
+    let provenance_ix = match provenance_ix {
+        Some(provenance_ix) => provenance_ix,
+        None => return Ok(None),
+    };
+    if let Some(to_ix) = translated.get(&provenance_ix) {
+        return Ok(Some(*to_ix));
+    }
+    let to_ix = to.put_provenance_bytes(&from.get_provenance_bytes(provenance_ix)?)?;
+    translated.insert(provenance_ix, to_ix);
+    Ok(Some(to_ix))
+}
+
+/// A serialized value. Values shorter than a `ValueHash` are stored inline,
+/// and longer ones are stored once in the value storage, and referenced by their hash
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+enum StoredValue {
+    Inline(Vec<u8>),
+    Remote(ValueHash),
+}
+
+impl StoredValue {
+    /// Reference the serialized value without storing it, such as for use in a query
+    fn reference(bytes: Vec<u8>) -> Self {
+        if bytes.len() < VALUE_HASH_LEN {
+            StoredValue::Inline(bytes)
+        } else {
+            StoredValue::Remote(ValueHash::new(&bytes))
+        }
+    }
+    fn new<I: Intern>(bytes: Vec<u8>, intern: &I) -> Result<Self, Error> {
+        if bytes.len() < VALUE_HASH_LEN {
+            return Ok(StoredValue::Inline(bytes));
+        }
+
+        let hash = ValueHash::new(&bytes);
+        intern.put_value_bytes(&hash, &bytes)?;
+        Ok(StoredValue::Remote(hash))
+    }
+    /// The bytes by which this value is indexed: either the value itself, or its hash.
+    /// These cannot be confused with each other, as inline values are always shorter than a hash
+    fn index_bytes(&self) -> &[u8] {
+        match self {
+            StoredValue::Inline(bytes) => bytes,
+            StoredValue::Remote(hash) => &hash.0,
+        }
+    }
+    fn resolve<Val: TValue, R: Resolve>(&self, resolve: &R) -> Result<Val, Error> {
+        match self {
+            StoredValue::Inline(bytes) => TValue::deserialize(bytes),
+            StoredValue::Remote(hash) => TValue::deserialize(&resolve.get_value_bytes(hash)?),
+        }
+    }
+}
+
+/// A property whose symbol has been interned, and whose value has been serialized, along with its provenance if any
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+struct StoredProperty(SymbolIx, StoredValue, Option<ProvenanceIx>);
+
+impl StoredProperty {
+    fn new<Sym, Val, I>(prop: &Property<Sym, Val>, provenance: Option<ProvenanceIx>, intern: &I) -> Result<Self, Error>
+    where
+        Sym: TSymbol,
+        Val: TValue,
+        I: Intern,
+    {
+        Ok(StoredProperty(
+            intern.put_symbol_bytes(&TSymbol::serialize(&prop.key))?,
+            StoredValue::new(TValue::serialize(&prop.value), intern)?,
+            provenance,
+        ))
+    }
+    fn to_property<Sym, Val, R>(&self, resolve: &R) -> Result<Property<Sym, Val>, Error>
+    where
+        Sym: TSymbol,
+        Val: TValue,
+        R: Resolve,
+    {
+        Ok(Property {
+            key: TSymbol::deserialize(&resolve.get_symbol_bytes(self.0)?)?,
+            value: self.1.resolve(resolve)?,
+        })
+    }
+    fn matches<Sym, Val, F, R>(&self, filter: &F, resolve: &R) -> Result<bool, Error>
+    where
+        Sym: TSymbol,
+        Val: TValue,
+        F: Fn(&Sym, &Val) -> Result<bool, Error>,
+        R: Resolve,
+    {
+        let prop = self.to_property(resolve)?;
+        filter(&prop.key, &prop.value)
+    }
+    fn index_key(&self) -> Vec<u8> {
+        index::property_value_key(self.0, self.1.index_bytes())
+    }
+    /// Copy this property for use in another adapter, re-interning the symbol and copying the value and provenance if need be
+    fn translate<R: Resolve, I: Intern>(
+        &self, from: &R, to: &I, provenance: &mut BTreeMap<ProvenanceIx, ProvenanceIx>,
+    ) -> Result<Self, Error> {
+        if let StoredValue::Remote(hash) = &self.1 {
+            to.put_value_bytes(hash, &from.get_value_bytes(hash)?)?;
+        }
+        Ok(StoredProperty(
+            to.put_symbol_bytes(&from.get_symbol_bytes(self.0)?)?,
+            self.1.clone(),
+            translate_provenance(self.2, from, to, provenance)?,
+        ))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct StoredEntity(EntityId, Vec<StoredProperty>, EntityInner, Option<ProvenanceIx>);
 
 impl StoredEntity {
+    fn new<Sym, Val, Prov, I>(entity_id: EntityId, entity: Entity<Sym, Val>, provenance: Option<&Prov>, intern: &I) -> Result<Self, Error>
+    where
+        Sym: TSymbol,
+        Val: TValue,
+        Prov: TProvenance,
+        I: Intern,
+    {
+        let provenance = intern_provenance(provenance, intern)?;
+        let storedprops = entity
+            .properties
+            .iter()
+            .map(|prop| StoredProperty::new(prop, provenance, intern))
+            .collect::<Result<Vec<StoredProperty>, Error>>()?;
+
+        Ok(StoredEntity(entity_id, storedprops, entity.inner, provenance))
+    }
+    /// Copy this record for use in another adapter, whose `SymbolIx`s, value storage and provenance storage are its own
+    fn translate<R: Resolve, I: Intern>(&self, from: &R, to: &I) -> Result<Self, Error> {
+        let mut provenance = BTreeMap::new();
+        let storedprops = self
+            .1
+            .iter()
+            .map(|prop| prop.translate(from, to, &mut provenance))
+            .collect::<Result<Vec<StoredProperty>, Error>>()?;
+
+        Ok(StoredEntity(
+            self.0,
+            storedprops,
+            self.2.clone(),
+            translate_provenance(self.3, from, to, &mut provenance)?,
+        ))
+    }
+    /// Range index keys for each property whose value has a sort key
+    fn property_range_keys<Val: TValue, R: Resolve>(&self, entity_ix: EntityIx, resolve: &R) -> Result<Vec<Vec<u8>>, Error> {
+        property_range_keys::<Val, R>(&self.1, entity_ix, resolve)
+    }
+    /// Full-text index entries for the text of each property which has any
+    fn text_postings<Val: TValue, R: Resolve>(&self, resolve: &R) -> Result<Postings, Error> {
+        text_postings::<Val, R>(&self.1, resolve)
+    }
+    fn properties<Sym, Val, R>(&self, resolve: &R) -> Result<Vec<Property<Sym, Val>>, Error>
+    where
+        Sym: TSymbol,
+        Val: TValue,
+        R: Resolve,
+    {
+        self.1.iter().map(|prop| prop.to_property(resolve)).collect()
+    }
+    fn to_entity<Sym, Val, R>(&self, resolve: &R) -> Result<Entity<Sym, Val>, Error>
+    where
+        Sym: TSymbol,
+        Val: TValue,
+        R: Resolve,
+    {
+        Ok(Entity {
+            properties: self.properties(resolve)?,
+            inner: self.2.clone(),
+        })
+    }
+    fn attribution<Prov, R>(&self, resolve: &R) -> Result<Attribution<Prov>, Error>
+    where
+        Prov: TProvenance,
+        R: Resolve,
+    {
+        let provenance = |provenance_ix: Option<ProvenanceIx>| {
+            provenance_ix
+                .map(|provenance_ix| TProvenance::deserialize(&resolve.get_provenance_bytes(provenance_ix)?))
+                .transpose()
+        };
+        Ok(Attribution {
+            entity: provenance(self.3)?,
+            properties: self
+                .1
+                .iter()
+                .map(|prop| provenance(prop.2))
+                .collect::<Result<Vec<Option<Prov>>, Error>>()?,
+        })
+    }
     fn serialize(&self) -> Vec<u8> {
         bincode::serialize(self).unwrap()
     }
@@ -37,59 +397,70 @@ impl StoredEntity {
     }
 }
 
-/// The hash of the weight which was stored
-type ValueIx = u64;
-
-/// The hash of the symbol which was stored
-type SymbolIx = u64;
-
-// #[derive(Serialize, Deserialize)]
-// enum ValueRef {
-//     // Inline(Vec<u8>),
-//     Remote(ValueId),
-// }
-// enum SymbolRef {
-//     // Inline(Vec<u8>),
-//     Remote(ValueId),
-// }
-
-// impl ValueRef {
-//     fn ix(&self) -> ValueIx {
-//         match self {
-//             // ValueRef::Inline(bytes) => {
-//             //     let mut hasher = Sha512Trunc256::default();
-//             //     hasher.update(bytes);
-//             //     let id: ValueId = hasher.finalize().into();
-//             //     id
-//             // }
-//             ValueRef::Remote(id) => id.clone(),
-//         }
-//     }
-//     fn fixed(&self) -> [u8; 32] {
-//         match self {
-//             ValueRef::Remote(id) => id.to_owned(),
-//         }
-//     }
-// }
-
-// impl ValueRef {
-//     fn id(&self) -> ValueId {
-//         match self {
-//             // ValueRef::Inline(bytes) => {
-//             //     let mut hasher = Sha512Trunc256::default();
-//             //     hasher.update(bytes);
-//             //     let id: ValueId = hasher.finalize().into();
-//             //     id
-//             // }
-//             ValueRef::Remote(id) => id.clone(),
-//         }
-//     }
-//     fn fixed(&self) -> [u8; 32] {
-//         match self {
-//             ValueRef::Remote(id) => id.to_owned(),
-//         }
-//     }
-// }
-
-// #[derive(Serialize, Deserialize)]
-// pub(crate) struct StoredSymbolId(SymbolId);
+fn property_range_keys<Val: TValue, R: Resolve>(
+    properties: &[StoredProperty], entity_ix: EntityIx, resolve: &R,
+) -> Result<Vec<Vec<u8>>, Error> {
+    let mut keys = Vec::new();
+    for prop in properties.iter() {
+        let value: Val = prop.1.resolve(resolve)?;
+        if let Some(sort_key) = value.sort_key() {
+            keys.push(index::property_range_key(prop.0, &sort_key, entity_ix));
+        }
+    }
+    Ok(keys)
+}
+
+fn text_postings<Val: TValue, R: Resolve>(properties: &[StoredProperty], resolve: &R) -> Result<Postings, Error> {
+    let mut postings = Postings::default();
+    for (i, prop) in properties.iter().enumerate() {
+        let value: Val = prop.1.resolve(resolve)?;
+        if let Some(text) = value.text() {
+            postings.add(i as u32, prop.0, text);
+        }
+    }
+    Ok(postings)
+}
+
+/// An `Amendment` with its symbol interned and value serialized, such that it may be applied to stored properties
+enum StoredAmendment {
+    Append(StoredProperty),
+    Replace(StoredProperty),
+    Retract(SymbolIx),
+}
+
+impl StoredAmendment {
+    fn symbol_ix(&self) -> SymbolIx {
+        match self {
+            StoredAmendment::Append(prop) | StoredAmendment::Replace(prop) => prop.0,
+            StoredAmendment::Retract(symbol_ix) => *symbol_ix,
+        }
+    }
+    fn new<Sym, Val, I>(amendment: &Amendment<Sym, Val>, provenance: Option<ProvenanceIx>, intern: &I) -> Result<Self, Error>
+    where
+        Sym: TSymbol,
+        Val: TValue,
+        I: Intern,
+    {
+        Ok(match amendment {
+            Amendment::Append(prop) => StoredAmendment::Append(StoredProperty::new(prop, provenance, intern)?),
+            Amendment::Replace(prop) => StoredAmendment::Replace(StoredProperty::new(prop, provenance, intern)?),
+            Amendment::Retract(key) => StoredAmendment::Retract(intern.put_symbol_bytes(&TSymbol::serialize(key))?),
+        })
+    }
+}
+
+/// Apply each amendment in turn, yielding the properties of the next version
+fn amend_properties(properties: &[StoredProperty], amendments: &[StoredAmendment]) -> Vec<StoredProperty> {
+    let mut properties = properties.to_vec();
+    for amendment in amendments {
+        match amendment {
+            StoredAmendment::Append(prop) => properties.push(prop.clone()),
+            StoredAmendment::Replace(prop) => {
+                properties.retain(|p| p.0 != prop.0);
+                properties.push(prop.clone());
+            },
+            StoredAmendment::Retract(key) => properties.retain(|p| p.0 != *key),
+        }
+    }
+    properties
+}
@@ -0,0 +1,1124 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    convert::TryInto,
+    io::{Read, Write},
+    marker::PhantomData,
+    ops::Bound,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, RwLock, RwLockReadGuard,
+    },
+};
+
+use crate::{
+    changes::{ChangeId, ChangeKind, Feed, Filter, RawChange, StoredChange, StoredFilter, Subscription, TRIMMED_KEY},
+    entity::{Adjacency, Amendment, Attribution, EntityIx, IdPrefix, Property, ProvenanceIx, SymbolIx, ValueHash, Version, SHORT_BYTES},
+    index,
+    kv::{self, Store, Transaction, Tree},
+    migrate::{self, Migrate, Migration, FORMAT_VERSION, FORMAT_VERSION_KEY, META_TREE},
+    snapshot::{self, Manifest, ENTITY_STORAGE_TREE},
+    text::{self, Hit, Postings, TermKind, TextQuery},
+    traits::{TProvenance, TSymbol, TValue},
+    Entity, EntityId, Error,
+};
+
+pub mod fsck;
+
+use super::{
+    amend_properties, intern_provenance, Cascade, Entities, EntityKind, Intern, RemovalPlan, Resolve, ScanDirection,
+    StorageAdapter, StoredAmendment, StoredEntity, StoredProperty, StoredValue,
+};
+
+/// Storage adapter for any `kv::Store`. `SledAdapter` and `MemoryAdapter` are this, over sled and over BTreeMaps
+pub struct KvAdapter<S: Store, Sym, Val, Prov = ()> {
+    /// Keyed on UUID for now, but this is ripe for optimization
+    entity_storage: S::Tree,
+    entity_id_to_ix: S::Tree,
+    /// The short form of each id, then the id. See `index::short_id_key`
+    entity_by_short_id: S::Tree,
+    next_entity_ix: AtomicU64,
+
+    /// Smaller values are stored directly in the entity record, but larger values are stored here once,
+    /// keyed by `ValueHash`, and shared by reference
+    value_storage: S::Tree,
+    /// Symbols could potentially be large. We need to locally enumerate them for compactness
+    symbol_storage: S::Tree,
+    /// Symbol bytes -> `SymbolIx`, such that each distinct symbol is only stored once
+    symbol_by_bytes: S::Tree,
+    next_symbol_ix: AtomicU64,
+    /// Index the raw weight value directly to the hyperedge
+    idx_propertyvalue_to_entity: S::Tree,
+    /// Index the sort key of each ordered value, for range queries
+    idx_propertyrange_to_entity: S::Tree,
+    idx_entity_to_hyperedge: S::Tree,
+    /// Each word and stem in the text of property values -> where they occur in each entity. See `crate::text`
+    idx_term_to_entity: S::Tree,
+    /// `EntityIx` -> the number of words in the entity's text, and the totals for the whole full-text index
+    text_lengths: S::Tree,
+
+    /// Ids of removed entities, so they may be distinguished from ids which never existed
+    tombstones: S::Tree,
+    /// Hyperedge id -> the removed members it still references
+    dangling_members: S::Tree,
+    /// Entity id + version -> the entity's properties as of that version. Only amended entities have history
+    property_history: S::Tree,
+    /// `ProvenanceIx` -> who asserted an entity or properties. Ids are allocated by `Store::generate_id`
+    provenance_storage: S::Tree,
+    /// The format version, and anything else about the store as a whole
+    meta: S::Tree,
+    /// `ChangeId` -> `StoredChange`, for each insert, amendment and removal
+    changes: S::Tree,
+    /// Assigns `ChangeId`s, and delivers changes to subscribers. While there are any, it is held while committing any change,
+    /// so that changes are logged and delivered in the order they are committed
+    feed: Mutex<Feed>,
+    /// Held shared by commits which took their `ChangeId`s while there was nobody subscribed, and so are committed without
+    /// the feed held. Subscribing waits for them, so as not to miss their changes
+    unpublished: RwLock<()>,
+
+    store: S,
+    /// Held shared by every write, and exclusively while checking or repairing the store, so that they see it consistent
+    write_gate: RwLock<()>,
+
+    // Prevent mixing and matching
+    #[doc(hidden)]
+    _sym: PhantomData<Sym>,
+    #[doc(hidden)]
+    _val: PhantomData<Val>,
+    #[doc(hidden)]
+    _prov: PhantomData<Prov>,
+}
+
+impl<S, Sym, Val, Prov> KvAdapter<S, Sym, Val, Prov>
+where
+    S: Store,
+    Sym: TSymbol,
+    Val: TValue,
+    Prov: TProvenance,
+{
+    /// Open the hypergraph's trees within the store, creating them if need be.
+    /// Stores of any other format version are refused, and must be migrated first
+    pub fn from_store(store: S) -> Result<Self, Error> {
+        match migrate::format_version(&store)? {
+            None => store.open_tree(META_TREE)?.insert(FORMAT_VERSION_KEY, FORMAT_VERSION.to_be_bytes())?,
+            Some(FORMAT_VERSION) => {},
+            Some(version) => return Err(Error::UnsupportedFormat(version)),
+        }
+        Self::open_trees(store)
+    }
+
+    /// Upgrade the store in place to the current format version, if need be, and open it.
+    /// Each migration is applied in a single transaction, so an interrupted migration may simply be run again.
+    /// Returns the migrations which were applied
+    pub fn migrate_store(store: S) -> Result<(Self, Vec<&'static Migration>), Error> {
+        let version = match migrate::format_version(&store)? {
+            Some(version) => version,
+            None => return Ok((Self::from_store(store)?, Vec::new())),
+        };
+
+        let adapter = Self::open_trees(store)?;
+        let mut applied = Vec::new();
+        for migration in migrate::migrations_from(version)? {
+            (migration.apply)(&adapter, migration)?;
+            applied.push(migration);
+        }
+
+        Ok((adapter, applied))
+    }
+
+    fn open_trees(store: S) -> Result<Self, Error> {
+        let symbol_storage = store.open_tree("hypergraph::symbol_storage")?;
+        let symbol_by_bytes = store.open_tree("hypergraph::symbol_by_bytes")?;
+        let next_symbol_ix = AtomicU64::new(symbol_storage.last()?.map_or(0, |(k, _)| read_be_u64(&k) + 1));
+
+        let value_storage = store.open_tree("hypergraph::value_storage")?;
+
+        let entity_storage = store.open_tree(ENTITY_STORAGE_TREE)?;
+        let entity_id_to_ix = store.open_tree("hypergraph::entity_id_to_ix")?;
+        let entity_by_short_id = store.open_tree("hypergraph::entity_by_short_id")?;
+
+        let next_entity_ix = AtomicU64::new(fsck::recover_next_entity_ix(&entity_storage)?);
+
+        let idx_entity_to_hyperedge = store.open_tree("hypergraph::hyperedge_by_entity_id")?;
+        let idx_propertyvalue_to_entity = store.open_tree("hypergraph::entity_by_property_value")?;
+        let idx_propertyrange_to_entity = store.open_tree("hypergraph::entity_by_property_range")?;
+        let idx_term_to_entity = store.open_tree("hypergraph::entity_by_term")?;
+        let text_lengths = store.open_tree("hypergraph::text_lengths")?;
+
+        let tombstones = store.open_tree("hypergraph::tombstones")?;
+        let dangling_members = store.open_tree("hypergraph::dangling_members")?;
+
+        let property_history = store.open_tree("hypergraph::property_history")?;
+        let provenance_storage = store.open_tree("hypergraph::provenance_storage")?;
+        let meta = store.open_tree(META_TREE)?;
+
+        let changes = store.open_tree("hypergraph::changes")?;
+        // The log may have been trimmed of every change, and ids must not go back on those
+        let last_change = match changes.last()? {
+            Some((key, _)) => Some(ChangeId::from_slice(&key)?),
+            None => meta.get(TRIMMED_KEY)?.map(|bytes| ChangeId::from_slice(&bytes)).transpose()?,
+        };
+
+        Ok(KvAdapter {
+            _sym: PhantomData,
+            _val: PhantomData,
+            _prov: PhantomData,
+            store,
+            write_gate: RwLock::new(()),
+            symbol_storage,
+            symbol_by_bytes,
+            next_symbol_ix,
+            value_storage,
+            entity_storage,
+            entity_id_to_ix,
+            entity_by_short_id,
+            next_entity_ix,
+            idx_entity_to_hyperedge,
+            idx_propertyvalue_to_entity,
+            idx_propertyrange_to_entity,
+            idx_term_to_entity,
+            text_lengths,
+            tombstones,
+            dangling_members,
+            property_history,
+            provenance_storage,
+            meta,
+            changes,
+            feed: Mutex::new(Feed::new(last_change)),
+            unpublished: RwLock::new(()),
+        })
+    }
+
+    /// Write every entity in the other adapter into this one, whatever store it is backed by.
+    /// `EntityId`s are preserved, but `EntityIx`s, `SymbolIx`s and `ProvenanceIx`s are allocated by this adapter
+    pub fn copy_from<O: Store>(&self, other: &KvAdapter<O, Sym, Val, Prov>) -> Result<(), Error> {
+        let stored = other
+            .iter_stored()
+            .map(|record| record?.translate(other, self))
+            .collect::<Result<Vec<StoredEntity>, Error>>()?;
+
+        self.put_stored(&stored)?;
+        Ok(())
+    }
+
+    /// Restore a snapshot into a store whose trees are empty, and open it
+    pub fn restore_into<R: Read>(store: S, input: R) -> Result<(Self, Manifest), Error> {
+        let manifest = snapshot::restore(&store, input)?;
+        Ok((Self::from_store(store)?, manifest))
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+}
+
+impl<S, Sym, Val, Prov> Migrate for KvAdapter<S, Sym, Val, Prov>
+where
+    S: Store,
+    Sym: TSymbol,
+    Val: TValue,
+    Prov: TProvenance,
+{
+    /// Re-store unversioned entity records with their symbols and values interned, and index them, under the same ids
+    fn migrate_unversioned(&self, migration: &Migration) -> Result<(), Error> {
+        let unversioned = migrate::read_unversioned(&self.entity_storage)?;
+        let stored = unversioned
+            .iter()
+            .map(|(_, entity)| {
+                let properties = entity
+                    .1
+                    .iter()
+                    .map(|(key, value)| {
+                        Ok(StoredProperty(
+                            self.put_symbol_bytes(key)?,
+                            StoredValue::new(value.clone(), self)?,
+                            None,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                Ok(StoredEntity(entity.0, properties, entity.inner(), None))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // The old records are replaced by the new ones in the same transaction, reusing their keyspace from the start
+        let mut tx = Transaction::new();
+        for (entity_ix, entity) in unversioned.iter() {
+            tx.remove(&self.entity_storage, entity_ix.to_be_bytes());
+            tx.remove(&self.entity_id_to_ix, (entity.0).0);
+        }
+        self.next_entity_ix.store(0, Ordering::SeqCst);
+        self.stage_stored(&mut tx, &stored)?;
+        tx.insert(&self.meta, FORMAT_VERSION_KEY, migration.to.to_be_bytes());
+        self.commit(tx)
+    }
+
+    /// Build the full-text index from scratch. Stores migrated from unversioned have it already, and are simply rebuilt
+    fn migrate_text_index(&self, migration: &Migration) -> Result<(), Error> {
+        let mut prepared = Vec::new();
+        for rec in self.entity_storage.iter() {
+            let (key, value) = rec?;
+            let stored = StoredEntity::deserialize(&value)?;
+            prepared.push((read_be_u64(&key), stored.text_postings::<Val, _>(self)?));
+        }
+
+        let mut tx = Transaction::new();
+        for tree in [&self.idx_term_to_entity, &self.text_lengths].iter().copied() {
+            for rec in tree.iter() {
+                tx.remove(tree, rec?.0);
+            }
+        }
+        for (entity_ix, postings) in prepared.iter() {
+            for (key, occurrences) in postings.entries(*entity_ix) {
+                tx.insert(&self.idx_term_to_entity, key, occurrences);
+            }
+            if postings.length > 0 {
+                tx.insert(&self.text_lengths, entity_ix.to_be_bytes(), postings.length.to_be_bytes());
+                tx.merge(&self.text_lengths, text::TOTALS_KEY, text::totals_operand(postings.length, true), text::merge_totals);
+            }
+        }
+        tx.insert(&self.meta, FORMAT_VERSION_KEY, migration.to.to_be_bytes());
+        self.commit(tx)
+    }
+
+    /// Index every entity by its short id, from scratch
+    fn migrate_short_ids(&self, migration: &Migration) -> Result<(), Error> {
+        let mut tx = Transaction::new();
+        for rec in self.entity_by_short_id.iter() {
+            tx.remove(&self.entity_by_short_id, rec?.0);
+        }
+        for rec in self.entity_id_to_ix.iter() {
+            let entity_id = EntityId::from_slice(&rec?.0)?;
+            tx.insert(&self.entity_by_short_id, index::short_id_key(&entity_id), []);
+        }
+        tx.insert(&self.meta, FORMAT_VERSION_KEY, migration.to.to_be_bytes());
+        self.commit(tx)
+    }
+}
+
+impl<S: Store, Sym, Val, Prov> KvAdapter<S, Sym, Val, Prov> {
+    /// Write a snapshot of the whole store as it was at one point in time. Reads carry on as usual meanwhile, but writes
+    /// through this adapter wait until it is done. Anything else which writes to the store, such as another adapter over it,
+    /// is not held up, and stops the snapshot with `Error::Conflict` instead
+    pub fn snapshot<W: Write>(&self, out: W) -> Result<Manifest, Error> {
+        let _exclusive = self.write_gate.write().unwrap();
+        snapshot::write(&self.store, out)
+    }
+
+    fn gate(&self) -> RwLockReadGuard<'_, ()> {
+        self.write_gate.read().unwrap()
+    }
+
+    fn commit(&self, tx: Transaction<'_, S::Tree>) -> Result<(), Error> {
+        let _gate = self.gate();
+        self.store.apply(tx)
+    }
+
+    /// Log the changes in the same transaction as the writes which make them, and deliver them to subscribers once committed
+    fn commit_changes<'a>(&'a self, mut tx: Transaction<'a, S::Tree>, changes: Vec<StoredChange>) -> Result<(), Error> {
+        let mut feed = self.feed.lock().unwrap();
+        if !feed.has_subscribers() {
+            // There is nothing to publish, so the feed is only needed for the ids, and other writers need not wait meanwhile
+            let _unpublished = self.unpublished.read().unwrap();
+            for change in changes.iter() {
+                tx.insert(&self.changes, feed.next_id().0, bincode::serialize(change)?);
+            }
+            drop(feed);
+            return self.commit(tx);
+        }
+
+        // Symbols which subscribers asked for may have been interned since, along with these changes
+        for filter in feed.filters_mut() {
+            filter.intern_pending(|bytes| Ok(self.symbol_by_bytes.get(bytes)?.map(|ix_bytes| read_be_u64(&ix_bytes))))?;
+        }
+
+        let mut matched: Vec<Vec<RawChange>> = feed.filters().map(|_| Vec::new()).collect();
+        for change in changes.iter() {
+            let id = feed.next_id();
+            tx.insert(&self.changes, id.0, bincode::serialize(change)?);
+            for (filter, matched) in feed.filters().zip(matched.iter_mut()) {
+                matched.extend(RawChange::new(id, change, filter, |symbol_ix| self.get_symbol_bytes(symbol_ix))?);
+            }
+        }
+
+        self.commit(tx)?;
+        feed.publish(matched);
+        Ok(())
+    }
+}
+
+impl<S, Sym, Val, Prov> KvAdapter<S, Sym, Val, Prov>
+where
+    S: Store,
+    Val: TValue,
+{
+    /// Write entity records under freshly allocated `EntityIx`s, and index them.
+    /// All records and index entries are committed in a single transaction, or not at all.
+    /// Ids which are already in use, or were and have since been removed, are refused with `Error::DuplicateId`
+    pub(crate) fn put_stored<'a, I>(&self, stored: I) -> Result<Vec<EntityIx>, Error>
+    where
+        I: IntoIterator<Item = &'a StoredEntity>,
+    {
+        let stored: Vec<&StoredEntity> = stored.into_iter().collect();
+        let changes = stored
+            .iter()
+            .map(|stored| StoredChange::new(ChangeKind::Insert, stored.0, stored.1.iter().map(|prop| prop.0)))
+            .collect();
+
+        let mut tx = Transaction::new();
+        let mut seen = BTreeSet::new();
+        for entity_id in stored.iter().map(|stored| stored.0) {
+            if !seen.insert(entity_id)
+                || self.entity_id_to_ix.contains_key(entity_id.0)?
+                || self.tombstones.contains_key(entity_id.0)?
+            {
+                return Err(Error::DuplicateId(entity_id));
+            }
+            // Nor may the id be taken while this is being committed
+            tx.expect(&self.entity_id_to_ix, entity_id.0, None);
+            tx.expect(&self.tombstones, entity_id.0, None);
+        }
+        let entity_ixs = self.stage_stored(&mut tx, stored)?;
+        self.commit_changes(tx, changes)?;
+        Ok(entity_ixs)
+    }
+
+    /// Add the writes for `put_stored` to the transaction
+    fn stage_stored<'a, 'b, I>(&'a self, tx: &mut Transaction<'a, S::Tree>, stored: I) -> Result<Vec<EntityIx>, Error>
+    where
+        I: IntoIterator<Item = &'b StoredEntity>,
+    {
+        // Do everything fallible up front, so the transaction may be retried cheaply on conflict
+        let mut prepared = Vec::new();
+        for stored in stored {
+            let entity_ix = self.next_entity_ix.fetch_add(1, Ordering::SeqCst);
+            let range_keys = stored.property_range_keys::<Val, _>(entity_ix, self)?;
+            let postings = stored.text_postings::<Val, _>(self)?;
+            prepared.push((entity_ix, stored, range_keys, postings));
+        }
+
+        for (entity_ix, stored, range_keys, postings) in prepared.iter() {
+            let entity_id = stored.0;
+
+            // Index each member in both directions, so that the hyperedge may be found from either side
+            for (member, role) in stored.2.members() {
+                let adjacency = Adjacency {
+                    hyperedge: entity_id,
+                    role,
+                    degree: member.degree,
+                };
+                tx.merge(
+                    &self.idx_entity_to_hyperedge,
+                    member.entity_id.0,
+                    index::adjacency_bytes(&adjacency),
+                    index::merge_byte_list::<index::AdjacencyLen>,
+                );
+            }
+
+            self.index_properties(tx, &entity_id, *entity_ix, &stored.1, range_keys, postings);
+
+            tx.insert(&self.entity_id_to_ix, entity_id.0, entity_ix.to_be_bytes());
+            tx.insert(&self.entity_by_short_id, index::short_id_key(&entity_id), []);
+            tx.insert(&self.entity_storage, entity_ix.to_be_bytes(), stored.serialize());
+        }
+
+        Ok(prepared.iter().map(|(entity_ix, ..)| *entity_ix).collect())
+    }
+
+    /// Check that the entity has not been removed, plan its removal, and apply the plan. If anything the plan was made from is
+    /// changed concurrently, the transaction which applies it is refused, and it is all done again
+    fn remove_entity(&self, entity_id: &EntityId, cascade: Cascade) -> Result<Vec<EntityId>, Error> {
+        retry_on_conflict(|| {
+            if self.tombstones.contains_key(entity_id.0)? {
+                return Err(Error::Removed);
+            }
+
+            // The adjacencies of each entity visited by the plan, as read
+            let read = RefCell::new(Vec::new());
+            let plan = RemovalPlan::new(entity_id, cascade, |id| {
+                let bytes = self.idx_entity_to_hyperedge.get(id.0)?.map(|bytes| bytes.to_vec());
+                let adjacencies = match &bytes {
+                    Some(bytes) => index::adjacencies_from_bytes(bytes)?,
+                    None => vec![],
+                };
+                read.borrow_mut().push((*id, bytes));
+                Ok(adjacencies)
+            })?;
+
+            self.apply_removal(&plan, &read.into_inner())?;
+            Ok(plan.remove)
+        })
+    }
+
+    /// Remove the planned entities and their index entries, tombstone them, and flag any dangling hyperedges.
+    /// This all happens in a single transaction, which expects everything read for the plan to be as it was
+    fn apply_removal(&self, plan: &RemovalPlan, adjacencies: &[(EntityId, Option<Vec<u8>>)]) -> Result<(), Error> {
+        let mut prepared = Vec::with_capacity(plan.remove.len());
+        for entity_id in plan.remove.iter() {
+            let entity_ix = match self.get_ix(entity_id) {
+                // A hyperedge caught up in the cascade was removed since the plan was made
+                Err(Error::Removed) | Err(Error::NotFound) if entity_id != &plan.remove[0] => return Err(Error::Conflict),
+                entity_ix => entity_ix?,
+            };
+            let record = match self.entity_storage.get(entity_ix.to_be_bytes())? {
+                Some(bytes) => bytes.to_vec(),
+                None => return Err(Error::Conflict),
+            };
+            let stored = StoredEntity::deserialize(&record)?;
+            let range_keys = stored.property_range_keys::<Val, _>(entity_ix, self)?;
+            let postings = stored.text_postings::<Val, _>(self)?;
+            let history_keys = self
+                .property_history
+                .scan_prefix(entity_id.0)
+                .map(|rec| Ok(rec?.0))
+                .collect::<Result<Vec<_>, Error>>()?;
+            prepared.push((entity_ix, record, stored, range_keys, postings, history_keys));
+        }
+
+        let mut tx = Transaction::new();
+        for (entity_id, bytes) in adjacencies.iter() {
+            tx.expect(&self.idx_entity_to_hyperedge, entity_id.0, bytes.as_deref());
+        }
+        for (hyperedge, _) in plan.dangling.iter() {
+            tx.expect(&self.tombstones, hyperedge.0, None);
+        }
+
+        for (entity_ix, record, stored, range_keys, postings, history_keys) in prepared.iter() {
+            let entity_id = stored.0;
+
+            // Neither removed nor amended since it was read
+            tx.expect(&self.tombstones, entity_id.0, None);
+            tx.expect(&self.entity_storage, entity_ix.to_be_bytes(), Some(&record[..]));
+            let version = history_keys.last().map_or(0, |key| read_be_u32(&key[16..]));
+            tx.expect(&self.property_history, index::property_history_key(&entity_id, version + 1), None);
+
+            // This entity is no longer a member of anything
+            for (member, role) in stored.2.members() {
+                let adjacency = Adjacency {
+                    hyperedge: entity_id,
+                    role,
+                    degree: member.degree,
+                };
+                tx.merge(
+                    &self.idx_entity_to_hyperedge,
+                    member.entity_id.0,
+                    index::adjacency_bytes(&adjacency),
+                    index::unmerge_byte_list::<index::AdjacencyLen>,
+                );
+            }
+            // Nor does anything remain a member of it
+            tx.remove(&self.idx_entity_to_hyperedge, entity_id.0);
+            tx.remove(&self.dangling_members, entity_id.0);
+
+            self.unindex_properties(&mut tx, *entity_ix, &stored.1, range_keys, postings);
+            for key in history_keys.iter() {
+                tx.remove(&self.property_history, key);
+            }
+
+            tx.remove(&self.entity_id_to_ix, entity_id.0);
+            tx.remove(&self.entity_by_short_id, index::short_id_key(&entity_id));
+            tx.remove(&self.entity_storage, entity_ix.to_be_bytes());
+            tx.insert(&self.tombstones, entity_id.0, entity_ix.to_be_bytes());
+        }
+
+        for (hyperedge, missing) in plan.dangling.iter() {
+            tx.merge(&self.dangling_members, hyperedge.0, missing.0, index::merge_byte_list::<typenum::U16>);
+        }
+
+        let changes = prepared
+            .iter()
+            .map(|(_, _, stored, ..)| StoredChange::new(ChangeKind::Remove, stored.0, stored.1.iter().map(|prop| prop.0)))
+            .collect();
+        self.commit_changes(tx, changes)
+    }
+
+    /// Iterate over all entity records in `EntityIx` order
+    pub(crate) fn iter_stored(&self) -> impl Iterator<Item = Result<StoredEntity, Error>> {
+        self.entity_storage
+            .iter()
+            .map(|rec| StoredEntity::deserialize(&rec?.1))
+    }
+
+    fn get_symbol_ix_by_bytes(&self, bytes: &[u8]) -> Result<Option<SymbolIx>, Error> {
+        Ok(self.symbol_by_bytes.get(bytes)?.map(|ix_bytes| read_be_u64(&ix_bytes)))
+    }
+
+    /// The entities with a property of this key and value, as given by `StoredValue::index_bytes`
+    fn find_by_symbol_ix(&self, symbol_ix: SymbolIx, value_bytes: &[u8]) -> Result<Vec<EntityId>, Error> {
+        match self.idx_propertyvalue_to_entity.get(index::property_value_key(symbol_ix, value_bytes))? {
+            Some(bytes) => bytes
+                .chunks_exact(8)
+                .map(|ix_bytes| match self.entity_storage.get(ix_bytes)? {
+                    Some(entity_bytes) => Ok(StoredEntity::deserialize(&entity_bytes)?.0),
+                    None => Err(Error::NotFound),
+                })
+                .collect(),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn get_ix(&self, entity_id: &EntityId) -> Result<EntityIx, Error> {
+        match self.entity_id_to_ix.get(entity_id.0)? {
+            Some(ix_bytes) => Ok(read_be_u64(&ix_bytes)),
+            None if self.tombstones.contains_key(entity_id.0)? => Err(Error::Removed),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    fn get_stored(&self, entity_id: &EntityId) -> Result<StoredEntity, Error> {
+        match self.entity_storage.get(self.get_ix(entity_id)?.to_be_bytes())? {
+            Some(bytes) => StoredEntity::deserialize(&bytes),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    fn current_version(&self, entity_id: &EntityId) -> Result<Version, Error> {
+        match self.property_history.scan_prefix(entity_id.0).next_back() {
+            Some(rec) => Ok(read_be_u32(&rec?.0[16..])),
+            None => {
+                // Not yet amended, but make sure it exists
+                self.get_ix(entity_id)?;
+                Ok(0)
+            },
+        }
+    }
+
+    /// Re-index the entity's amended properties, and record both the prior and amended versions in the history.
+    /// This all happens in a single transaction, which is refused and done again if the entity changes concurrently
+    fn apply_amendments(&self, entity_id: &EntityId, amendments: &[StoredAmendment]) -> Result<Version, Error> {
+        retry_on_conflict(|| self.try_apply_amendments(entity_id, amendments))
+    }
+
+    fn try_apply_amendments(&self, entity_id: &EntityId, amendments: &[StoredAmendment]) -> Result<Version, Error> {
+        let entity_ix = self.get_ix(entity_id)?;
+        let record = match self.entity_storage.get(entity_ix.to_be_bytes())? {
+            Some(bytes) => bytes.to_vec(),
+            None => return Err(Error::NotFound),
+        };
+        let prior = StoredEntity::deserialize(&record)?;
+        let version = self.current_version(entity_id)?;
+
+        let amended = StoredEntity(prior.0, amend_properties(&prior.1, amendments), prior.2.clone(), prior.3);
+        let prior_range_keys = prior.property_range_keys::<Val, _>(entity_ix, self)?;
+        let range_keys = amended.property_range_keys::<Val, _>(entity_ix, self)?;
+        let prior_postings = prior.text_postings::<Val, _>(self)?;
+        let postings = amended.text_postings::<Val, _>(self)?;
+
+        let mut tx = Transaction::new();
+        // Another amendment of the same version would write the same history, and a removal would leave nothing to amend
+        tx.expect(&self.property_history, index::property_history_key(entity_id, version + 1), None);
+        tx.expect(&self.entity_storage, entity_ix.to_be_bytes(), Some(&record[..]));
+        self.unindex_properties(&mut tx, entity_ix, &prior.1, &prior_range_keys, &prior_postings);
+        self.index_properties(&mut tx, entity_id, entity_ix, &amended.1, &range_keys, &postings);
+
+        // History is only kept for entities which have been amended, so the first amendment records the original too
+        if version == 0 {
+            tx.insert(
+                &self.property_history,
+                index::property_history_key(entity_id, 0),
+                bincode::serialize(&prior.1)?,
+            );
+        }
+        tx.insert(
+            &self.property_history,
+            index::property_history_key(entity_id, version + 1),
+            bincode::serialize(&amended.1)?,
+        );
+        tx.insert(&self.entity_storage, entity_ix.to_be_bytes(), amended.serialize());
+
+        let change = StoredChange::new(ChangeKind::Update, *entity_id, amendments.iter().map(StoredAmendment::symbol_ix));
+        self.commit_changes(tx, vec![change])?;
+
+        Ok(version + 1)
+    }
+
+    /// Index each property by its value, each ordered property by its sort key, and the words of any text
+    fn index_properties<'a>(
+        &'a self, tx: &mut Transaction<'a, S::Tree>, entity_id: &EntityId, entity_ix: EntityIx, properties: &[StoredProperty],
+        range_keys: &[Vec<u8>], postings: &Postings,
+    ) {
+        // symbol + value -> [entity_ix]
+        for prop in properties.iter() {
+            tx.merge(
+                &self.idx_propertyvalue_to_entity,
+                prop.index_key(),
+                entity_ix.to_be_bytes(),
+                index::merge_byte_list::<typenum::U8>,
+            );
+        }
+        for key in range_keys.iter() {
+            tx.insert(&self.idx_propertyrange_to_entity, key, entity_id.0);
+        }
+        for (key, occurrences) in postings.entries(entity_ix) {
+            tx.insert(&self.idx_term_to_entity, key, occurrences);
+        }
+        if postings.length > 0 {
+            tx.insert(&self.text_lengths, entity_ix.to_be_bytes(), postings.length.to_be_bytes());
+            tx.merge(&self.text_lengths, text::TOTALS_KEY, text::totals_operand(postings.length, true), text::merge_totals);
+        }
+    }
+
+    /// Inverse of `index_properties`
+    fn unindex_properties<'a>(
+        &'a self, tx: &mut Transaction<'a, S::Tree>, entity_ix: EntityIx, properties: &[StoredProperty], range_keys: &[Vec<u8>],
+        postings: &Postings,
+    ) {
+        for prop in properties.iter() {
+            tx.merge(
+                &self.idx_propertyvalue_to_entity,
+                prop.index_key(),
+                entity_ix.to_be_bytes(),
+                index::unmerge_byte_list::<typenum::U8>,
+            );
+        }
+        for key in range_keys.iter() {
+            tx.remove(&self.idx_propertyrange_to_entity, key);
+        }
+        for (key, _) in postings.entries(entity_ix) {
+            tx.remove(&self.idx_term_to_entity, key);
+        }
+        if postings.length > 0 {
+            tx.remove(&self.text_lengths, entity_ix.to_be_bytes());
+            tx.merge(&self.text_lengths, text::TOTALS_KEY, text::totals_operand(postings.length, false), text::merge_totals);
+        }
+    }
+}
+
+impl<S: Store, Sym, Val, Prov> Intern for KvAdapter<S, Sym, Val, Prov> {
+    /// Look up the id of the serialized symbol, allocating one if this is the first time it has been seen
+    fn put_symbol_bytes(&self, bytes: &[u8]) -> Result<SymbolIx, Error> {
+        if let Some(ix_bytes) = self.symbol_by_bytes.get(bytes)? {
+            return Ok(read_be_u64(&ix_bytes));
+        }
+
+        let _gate = self.gate();
+        // Store the symbol before publishing its id, so that any id which can be found may also be resolved
+        let symbol_ix = self.next_symbol_ix.fetch_add(1, Ordering::SeqCst);
+        self.symbol_storage.insert(symbol_ix.to_be_bytes(), bytes)?;
+
+        match self.symbol_by_bytes.merge(bytes, symbol_ix.to_be_bytes(), kv::write_once)? {
+            None => Ok(symbol_ix),
+            Some(current) => {
+                // Somebody else interned it first, so ours is unreferenced
+                self.symbol_storage.remove(symbol_ix.to_be_bytes())?;
+                Ok(read_be_u64(&current))
+            },
+        }
+    }
+
+    fn put_value_bytes(&self, hash: &ValueHash, bytes: &[u8]) -> Result<(), Error> {
+        let _gate = self.gate();
+        // Write-once, so a value which is already stored is not rewritten
+        self.value_storage.merge(hash.0, bytes, kv::write_once)?;
+        Ok(())
+    }
+
+    fn put_provenance_bytes(&self, bytes: &[u8]) -> Result<ProvenanceIx, Error> {
+        let _gate = self.gate();
+        let provenance_ix = self.store.generate_id()?;
+        self.provenance_storage.insert(provenance_ix.to_be_bytes(), bytes)?;
+        Ok(provenance_ix)
+    }
+}
+
+impl<S: Store, Sym, Val, Prov> Resolve for KvAdapter<S, Sym, Val, Prov> {
+    fn get_symbol_bytes(&self, symbol_ix: SymbolIx) -> Result<Vec<u8>, Error> {
+        match self.symbol_storage.get(symbol_ix.to_be_bytes())? {
+            Some(bytes) => Ok(bytes.to_vec()),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    fn get_value_bytes(&self, hash: &ValueHash) -> Result<Vec<u8>, Error> {
+        match self.value_storage.get(hash.0)? {
+            Some(bytes) => Ok(bytes.to_vec()),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    fn get_provenance_bytes(&self, provenance_ix: ProvenanceIx) -> Result<Vec<u8>, Error> {
+        match self.provenance_storage.get(provenance_ix.to_be_bytes())? {
+            Some(bytes) => Ok(bytes.to_vec()),
+            None => Err(Error::NotFound),
+        }
+    }
+}
+
+impl<S, Sym, Val, Prov> StorageAdapter<Sym, Val, Prov> for KvAdapter<S, Sym, Val, Prov>
+where
+    S: Store,
+    Sym: TSymbol,
+    Val: TValue,
+    Prov: TProvenance,
+{
+    fn insert(&self, entity: Entity<Sym, Val>, provenance: Option<&Prov>) -> Result<(EntityIx, EntityId), Error> {
+        let entity_id = EntityId::generate();
+        let stored = StoredEntity::new(entity_id, entity, provenance, self)?;
+        let entity_ix = self.put_stored(&[stored])?[0];
+
+        Ok((entity_ix, entity_id))
+    }
+
+    fn insert_batch(&self, entities: Vec<(EntityId, Entity<Sym, Val>, Option<Prov>)>) -> Result<Vec<EntityIx>, Error> {
+        let stored: Vec<StoredEntity> = entities
+            .into_iter()
+            .map(|(entity_id, entity, provenance)| StoredEntity::new(entity_id, entity, provenance.as_ref(), self))
+            .collect::<Result<_, Error>>()?;
+
+        self.put_stored(&stored)
+    }
+
+    fn get_by_ix(&self, entity_ix: &EntityIx) -> Result<Entity<Sym, Val>, Error> {
+        match self.entity_storage.get(entity_ix.to_be_bytes())? {
+            Some(bytes) => StoredEntity::deserialize(&bytes)?.to_entity(self),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    fn get(&self, entity_id: &EntityId) -> Result<Entity<Sym, Val>, Error> {
+        self.get_stored(entity_id)?.to_entity(self)
+    }
+
+    fn get_attributed(&self, entity_id: &EntityId) -> Result<(Entity<Sym, Val>, Attribution<Prov>), Error> {
+        let stored = self.get_stored(entity_id)?;
+        Ok((stored.to_entity(self)?, stored.attribution(self)?))
+    }
+
+    fn scan(
+        &self, range: (Bound<EntityId>, Bound<EntityId>), kind: EntityKind, limit: usize,
+    ) -> Result<Entities<Sym, Val>, Error> {
+        let mut entities = Vec::new();
+        for rec in self.entity_id_to_ix.range((range.0.map(|id| id.0), range.1.map(|id| id.0))) {
+            if entities.len() == limit {
+                break;
+            }
+            let (id_bytes, ix_bytes) = rec?;
+            let stored = match self.entity_storage.get(ix_bytes)? {
+                Some(bytes) => StoredEntity::deserialize(&bytes)?,
+                None => return Err(Error::NotFound),
+            };
+            if kind.includes(&stored.2) {
+                entities.push((EntityId::from_slice(&id_bytes)?, stored.to_entity(self)?));
+            }
+        }
+        Ok(entities)
+    }
+
+    /// Ids within the span of the prefix are scanned, by their full form and, for prefixes short enough, by their short form
+    fn find_by_id_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<EntityId>, Error> {
+        let prefix = IdPrefix::parse(prefix)?;
+        let mut found = BTreeSet::new();
+
+        let (least, greatest) = prefix.full_span();
+        for rec in self.entity_id_to_ix.range(least..) {
+            let (key, _) = rec?;
+            if found.len() == limit || !index::within(&key, greatest) {
+                break;
+            }
+            let entity_id = EntityId::from_slice(&key)?;
+            if prefix.matches(&entity_id) {
+                found.insert(entity_id);
+            }
+        }
+
+        if let Some((least, greatest)) = prefix.short_span() {
+            for rec in self.entity_by_short_id.range(least..) {
+                let (key, _) = rec?;
+                if found.len() == limit || !index::within(&key, greatest) {
+                    break;
+                }
+                let entity_id = EntityId::from_slice(&key[SHORT_BYTES..])?;
+                if prefix.matches(&entity_id) {
+                    found.insert(entity_id);
+                }
+            }
+        }
+        Ok(found.into_iter().collect())
+    }
+
+    fn find_by_property(&self, key: &Sym, value: &Val) -> Result<Vec<EntityId>, Error> {
+        let symbol_ix = match self.get_symbol_ix_by_bytes(&TSymbol::serialize(key))? {
+            Some(symbol_ix) => symbol_ix,
+            None => return Ok(vec![]),
+        };
+        self.find_by_symbol_ix(symbol_ix, StoredValue::reference(TValue::serialize(value)).index_bytes())
+    }
+
+    fn find_by_similar_property(&self, key: &Sym, value: &Val, threshold: f64) -> Result<Vec<EntityId>, Error> {
+        let value = StoredValue::reference(TValue::serialize(value));
+        let mut found = Vec::new();
+        let mut seen = BTreeSet::new();
+        for (symbol_ix, _) in self.find_similar_symbols(key, threshold)? {
+            for entity_id in self.find_by_symbol_ix(symbol_ix, value.index_bytes())? {
+                if seen.insert(entity_id) {
+                    found.push(entity_id);
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    fn range_by_property(
+        &self, key: &Sym, range: (Bound<&Val>, Bound<&Val>), direction: ScanDirection, limit: Option<usize>,
+    ) -> Result<Vec<EntityId>, Error> {
+        let (lo, hi) = (index::sort_key_bound(range.0)?, index::sort_key_bound(range.1)?);
+        let symbol_ix = match self.get_symbol_ix_by_bytes(&TSymbol::serialize(key))? {
+            Some(symbol_ix) => symbol_ix,
+            None => return Ok(vec![]),
+        };
+        let bounds = match index::property_range_bounds(symbol_ix, lo, hi) {
+            Some(bounds) => bounds,
+            None => return Ok(vec![]),
+        };
+
+        let iter = self.idx_propertyrange_to_entity.range(bounds).map(|rec| rec.map(|(_, id)| id));
+        let limit = limit.unwrap_or(usize::MAX);
+        match direction {
+            ScanDirection::Forward => iter.take(limit).map(|id| EntityId::from_slice(&id?)).collect(),
+            ScanDirection::Reverse => iter.rev().take(limit).map(|id| EntityId::from_slice(&id?)).collect(),
+        }
+    }
+
+    fn search(&self, query: &TextQuery<Sym>) -> Result<Vec<Hit>, Error> {
+        let symbols = match &query.symbols {
+            Some(symbols) => {
+                let mut known = BTreeSet::new();
+                for symbol in symbols.iter() {
+                    known.extend(self.get_symbol_ix_by_bytes(&TSymbol::serialize(symbol))?);
+                }
+                Some(known)
+            },
+            None => None,
+        };
+
+        // Each clause is matched on its own, then the clauses are combined
+        let mut clauses = Vec::with_capacity(query.clauses.len());
+        for clause in query.clauses.iter() {
+            let mut postings = Vec::with_capacity(clause.len());
+            for word in clause.iter() {
+                let prefix = if query.stemmed {
+                    text::term_prefix(TermKind::Stem, &text::stem(word))
+                } else {
+                    text::term_prefix(TermKind::Word, word)
+                };
+                postings.push(text::read_postings(self.idx_term_to_entity.scan_prefix(&prefix), prefix.len(), symbols.as_ref())?);
+            }
+            clauses.push(text::clause_frequencies(&postings));
+        }
+
+        let mut matched: BTreeMap<EntityIx, usize> = BTreeMap::new();
+        for frequencies in clauses.iter() {
+            for entity_ix in frequencies.keys() {
+                *matched.entry(*entity_ix).or_default() += 1;
+            }
+        }
+        if !query.any {
+            matched.retain(|_, n| *n == clauses.len());
+        }
+
+        let (documents, words) = text::read_totals(self.text_lengths.get(text::TOTALS_KEY)?.as_deref());
+        let average_length = if documents > 0 { words as f64 / documents as f64 } else { 0.0 };
+
+        let mut scored = Vec::with_capacity(matched.len());
+        for entity_ix in matched.keys() {
+            let length = self.text_lengths.get(entity_ix.to_be_bytes())?.map_or(0, |bytes| read_be_u32(&bytes)) as f64;
+            let score: f64 = clauses
+                .iter()
+                .filter_map(|frequencies| {
+                    let frequency = frequencies.get(entity_ix)?;
+                    Some(text::bm25(*frequency, length, frequencies.len(), documents as f64, average_length))
+                })
+                .sum();
+            scored.push((*entity_ix, score));
+        }
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        scored.truncate(query.limit.unwrap_or(usize::MAX));
+
+        scored
+            .into_iter()
+            .map(|(entity_ix, score)| match self.entity_storage.get(entity_ix.to_be_bytes())? {
+                Some(bytes) => Ok(Hit {
+                    entity_id: StoredEntity::deserialize(&bytes)?.0,
+                    score,
+                }),
+                None => Err(Error::NotFound),
+            })
+            .collect()
+    }
+
+    fn remove(&self, entity_id: &EntityId, cascade: Cascade) -> Result<Vec<EntityId>, Error> {
+        self.remove_entity(entity_id, cascade)
+    }
+
+    fn is_removed(&self, entity_id: &EntityId) -> Result<bool, Error> {
+        self.tombstones.contains_key(entity_id.0)
+    }
+
+    fn get_dangling_members(&self, entity_id: &EntityId) -> Result<Vec<EntityId>, Error> {
+        match self.dangling_members.get(entity_id.0)? {
+            Some(bytes) => bytes.chunks_exact(16).map(EntityId::from_slice).collect(),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn amend(
+        &self, entity_id: &EntityId, amendments: Vec<Amendment<Sym, Val>>, provenance: Option<&Prov>,
+    ) -> Result<Version, Error> {
+        let provenance = intern_provenance(provenance, self)?;
+        let amendments = amendments
+            .iter()
+            .map(|amendment| StoredAmendment::new(amendment, provenance, self))
+            .collect::<Result<Vec<StoredAmendment>, Error>>()?;
+        self.apply_amendments(entity_id, &amendments)
+    }
+
+    fn get_version(&self, entity_id: &EntityId) -> Result<Version, Error> {
+        self.current_version(entity_id)
+    }
+
+    fn get_properties(&self, entity_id: &EntityId, version: Option<Version>) -> Result<Vec<Property<Sym, Val>>, Error> {
+        let version = match version {
+            Some(version) if version != self.get_version(entity_id)? => version,
+            _ => return self.get_stored(entity_id)?.properties(self),
+        };
+
+        match self.property_history.get(index::property_history_key(entity_id, version))? {
+            Some(bytes) => {
+                let properties: Vec<StoredProperty> = bincode::deserialize(&bytes)?;
+                properties.iter().map(|prop| prop.to_property(self)).collect()
+            },
+            None => Err(Error::NotFound),
+        }
+    }
+
+    fn put_symbol(&self, symbol: &Sym) -> Result<SymbolIx, Error> {
+        self.put_symbol_bytes(&TSymbol::serialize(symbol))
+    }
+
+    /// Every symbol is compared, as there is no index by similarity. Property keys are few enough that this is fine for now
+    fn find_similar_symbols(&self, symbol: &Sym, threshold: f64) -> Result<Vec<(SymbolIx, f64)>, Error> {
+        let mut similar = Vec::new();
+        for rec in self.symbol_storage.iter() {
+            let (ix_bytes, bytes) = rec?;
+            let similarity = symbol.similarity(&<Sym as TSymbol>::deserialize(&bytes)?);
+            if similarity >= threshold {
+                similar.push((read_be_u64(&ix_bytes), similarity));
+            }
+        }
+        similar.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        Ok(similar)
+    }
+
+    fn get_symbol_ix(&self, symbol: &Sym) -> Result<Option<SymbolIx>, Error> {
+        self.get_symbol_ix_by_bytes(&TSymbol::serialize(symbol))
+    }
+
+    fn get_symbol(&self, symbol_ix: SymbolIx) -> Result<Sym, Error> {
+        TSymbol::deserialize(&self.get_symbol_bytes(symbol_ix)?)
+    }
+
+    fn get_value(&self, hash: &ValueHash) -> Result<Val, Error> {
+        TValue::deserialize(&self.get_value_bytes(hash)?)
+    }
+
+    fn subscribe(&self, filter: Filter<Sym>, after: Option<ChangeId>) -> Result<Subscription<Sym>, Error> {
+        // Hold off new changes while catching up, so that none are missed or yielded twice
+        let mut feed = self.feed.lock().unwrap();
+        let _committed = self.unpublished.write().unwrap();
+
+        // Changes are matched by SymbolIx. Symbols which have yet to be interned are looked up again as changes are committed
+        let mut filter = match filter {
+            Filter::Any => StoredFilter::Any,
+            Filter::Symbols(symbols) => StoredFilter::Symbols {
+                interned: BTreeSet::new(),
+                pending: symbols.iter().map(TSymbol::serialize).collect(),
+            },
+        };
+        filter.intern_pending(|bytes| self.get_symbol_ix_by_bytes(bytes))?;
+
+        let mut backlog = Vec::new();
+        if let Some(after) = after {
+            if let Some(bytes) = self.meta.get(TRIMMED_KEY)? {
+                let through = ChangeId::from_slice(&bytes)?;
+                if after < through {
+                    return Err(Error::Trimmed(through));
+                }
+            }
+            for rec in self.changes.range((Bound::Excluded(after.0), Bound::Unbounded)) {
+                let (key, value) = rec?;
+                let change: StoredChange = bincode::deserialize(&value)?;
+                backlog.extend(RawChange::new(ChangeId::from_slice(&key)?, &change, &filter, |symbol_ix| {
+                    self.get_symbol_bytes(symbol_ix)
+                })?);
+            }
+        }
+
+        Ok(feed.subscribe(filter, backlog))
+    }
+
+    fn trim_changes(&self, before: ChangeId) -> Result<usize, Error> {
+        // Hold off subscribers catching up meanwhile, so that they either see the whole backlog or are refused
+        let _feed = self.feed.lock().unwrap();
+
+        let mut tx = Transaction::new();
+        let mut trimmed = 0;
+        let mut through = None;
+        for rec in self.changes.range(..before.0) {
+            let (key, _) = rec?;
+            tx.remove(&self.changes, &key);
+            trimmed += 1;
+            through = Some(key);
+        }
+        if let Some(through) = through {
+            tx.insert(&self.meta, TRIMMED_KEY, through);
+            self.commit(tx)?;
+        }
+        Ok(trimmed)
+    }
+
+    fn get_adjacencies(&self, entity_id: &EntityId) -> Result<Vec<Adjacency>, Error> {
+        match self.idx_entity_to_hyperedge.get(entity_id.0)? {
+            Some(bytes) => index::adjacencies_from_bytes(&bytes),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn get_adjacencies_matching<F>(&self, entity_id: &EntityId, filter: F) -> Result<Vec<Adjacency>, Error>
+    where
+        F: Fn(&Sym, &Val) -> Result<bool, Error>,
+    {
+        let mut out = Vec::new();
+        for adjacency in self.get_adjacencies(entity_id)? {
+            let stored = self.get_stored(&adjacency.hyperedge)?;
+
+            for prop in stored.1.iter() {
+                if prop.matches(&filter, self)? {
+                    out.push(adjacency);
+                    break;
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Read, plan and commit again for as long as the commit conflicts with a concurrent write
+fn retry_on_conflict<T, F: FnMut() -> Result<T, Error>>(mut attempt: F) -> Result<T, Error> {
+    loop {
+        match attempt() {
+            Err(Error::Conflict) => continue,
+            result => return result,
+        }
+    }
+}
+
+fn read_be_u64(input: &[u8]) -> u64 {
+    let (int_bytes, _rest) = input.split_at(std::mem::size_of::<u64>());
+    // *input = rest;
+    u64::from_be_bytes(int_bytes.try_into().unwrap())
+}
+
+fn read_be_u32(input: &[u8]) -> u32 {
+    let (int_bytes, _rest) = input.split_at(std::mem::size_of::<u32>());
+    // *input = rest;
+    u32::from_be_bytes(int_bytes.try_into().unwrap())
+}
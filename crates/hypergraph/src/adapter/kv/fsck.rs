@@ -0,0 +1,434 @@
+//! Verify the secondary trees of a `KvAdapter` against its primary storage, and rebuild them from it.
+//!
+//! Entity records, tombstones and property history are primary. `entity_id_to_ix`, the short id index, the property value
+//! and range indexes, the full-text index, the hyperedge adjacency index and the dangling member lists are all derived from
+//! them, and so may be rebuilt from scratch whenever they are found to disagree. The exception is an entity whose properties
+//! refer to missing symbols or values: whatever can be derived for it is, but its other range and text entries are left as
+//! they are, as they cannot be rebuilt.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryInto,
+    fmt::Display,
+    sync::atomic::Ordering,
+};
+
+use crate::{
+    adapter::{StoredProperty, StoredValue},
+    entity::{Adjacency, EntityIx, SymbolIx, ValueHash},
+    index,
+    kv::{MergeFn, Store, Transaction, Tree},
+    text::{self, Postings},
+    traits::TValue,
+    EntityId, Error,
+};
+
+use super::{KvAdapter, StoredEntity};
+
+/// Something found to be wrong with the store
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    /// A primary record which could not be decoded. These are left in place by a repair, as there is nothing to rebuild them from
+    Undecodable { tree: &'static str, key: Vec<u8> },
+    /// More than one entity record has this id. Only the first is indexed
+    DuplicateEntity(EntityId),
+    /// A hyperedge has a member which neither exists nor was removed
+    MissingMember { hyperedge: EntityId, member: EntityId },
+    /// A property refers to a symbol which is not in the symbol storage
+    MissingSymbol { entity_id: EntityId, symbol_ix: SymbolIx },
+    /// A property refers to a value which is not in the value storage
+    MissingValue { entity_id: EntityId, hash: ValueHash },
+    /// A derived entry which should exist, but does not
+    MissingEntry { tree: &'static str, key: Vec<u8> },
+    /// A derived entry which refers to no entity, or a history record for an entity which no longer exists
+    OrphanEntry { tree: &'static str, key: Vec<u8> },
+    /// A derived entry which exists, but is not what it should be
+    WrongEntry { tree: &'static str, key: Vec<u8> },
+}
+
+impl Problem {
+    /// Can this be fixed by rebuilding from primary storage?
+    pub fn is_repairable(&self) -> bool {
+        matches!(
+            self,
+            Problem::MissingEntry { .. } | Problem::OrphanEntry { .. } | Problem::WrongEntry { .. }
+        )
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use base64::STANDARD_NO_PAD;
+        let key = |key: &Vec<u8>| base64::encode_config(key, STANDARD_NO_PAD);
+        match self {
+            Problem::Undecodable { tree, key: k } => write!(f, "{}: undecodable record {}", tree, key(k)),
+            Problem::DuplicateEntity(entity_id) => write!(f, "duplicate entity {}", entity_id),
+            Problem::MissingMember { hyperedge, member } => write!(f, "hyperedge {} has missing member {}", hyperedge, member),
+            Problem::MissingSymbol { entity_id, symbol_ix } => write!(f, "entity {} has missing symbol {}", entity_id, symbol_ix),
+            Problem::MissingValue { entity_id, hash } => write!(f, "entity {} has missing value {}", entity_id, hash),
+            Problem::MissingEntry { tree, key: k } => write!(f, "{}: missing entry {}", tree, key(k)),
+            Problem::OrphanEntry { tree, key: k } => write!(f, "{}: orphan entry {}", tree, key(k)),
+            Problem::WrongEntry { tree, key: k } => write!(f, "{}: wrong entry {}", tree, key(k)),
+        }
+    }
+}
+
+/// The outcome of `KvAdapter::check` or `KvAdapter::repair`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    /// The number of entity records which could be decoded
+    pub entities: u64,
+    pub problems: Vec<Problem>,
+    /// Values which no property refers to. These are harmless, as values are stored ahead of the records which refer to them
+    pub unreferenced_values: u64,
+    /// The `EntityIx` to be allocated next, as recovered from the entity storage
+    pub next_entity_ix: EntityIx,
+    /// Were the repairable problems repaired?
+    pub repaired: bool,
+}
+
+impl Report {
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+const ENTITY_ID_TO_IX: &str = "hypergraph::entity_id_to_ix";
+const ENTITY_BY_SHORT_ID: &str = "hypergraph::entity_by_short_id";
+const HYPEREDGE_BY_ENTITY_ID: &str = "hypergraph::hyperedge_by_entity_id";
+const ENTITY_BY_PROPERTY_VALUE: &str = "hypergraph::entity_by_property_value";
+const ENTITY_BY_PROPERTY_RANGE: &str = "hypergraph::entity_by_property_range";
+const DANGLING_MEMBERS: &str = "hypergraph::dangling_members";
+const ENTITY_BY_TERM: &str = "hypergraph::entity_by_term";
+const TEXT_LENGTHS: &str = "hypergraph::text_lengths";
+const PROPERTY_HISTORY: &str = "hypergraph::property_history";
+
+/// The `EntityIx` following the greatest one in the entity storage. Keys which are not an `EntityIx` are skipped,
+/// and the records themselves need not be decodable
+pub(super) fn recover_next_entity_ix<T: Tree>(entity_storage: &T) -> Result<EntityIx, Error> {
+    for rec in entity_storage.iter().rev() {
+        let (key, _) = rec?;
+        if let Ok(key) = key.as_ref().try_into() {
+            return Ok(u64::from_be_bytes(key) + 1);
+        }
+    }
+    Ok(0)
+}
+
+/// Whether the entries of the derived tree are derived from property values, rather than from the records alone
+fn derived_from_values(tree: &str) -> bool {
+    tree == ENTITY_BY_PROPERTY_RANGE || tree == ENTITY_BY_TERM || tree == TEXT_LENGTHS
+}
+
+/// The entity to which an entry of a tree derived from property values belongs. Each such key ends with its `EntityIx`
+fn owner(key: &[u8]) -> Option<EntityIx> {
+    let at = key.len().checked_sub(8)?;
+    Some(u64::from_be_bytes(key[at..].try_into().ok()?))
+}
+
+/// The contents which the derived trees should have
+#[derive(Default)]
+struct Derived {
+    trees: BTreeMap<&'static str, BTreeMap<Vec<u8>, Vec<u8>>>,
+}
+
+impl Derived {
+    fn insert(&mut self, tree: &'static str, key: Vec<u8>, value: Vec<u8>) {
+        self.trees.entry(tree).or_default().insert(key, value);
+    }
+    fn merge(&mut self, tree: &'static str, key: Vec<u8>, operand: &[u8], merge: MergeFn) {
+        let tree = self.trees.entry(tree).or_default();
+        if let Some(merged) = merge(&key, tree.get(&key).map(|v| &v[..]), operand) {
+            tree.insert(key, merged);
+        }
+    }
+    fn take(&mut self, tree: &'static str) -> BTreeMap<Vec<u8>, Vec<u8>> {
+        self.trees.remove(tree).unwrap_or_default()
+    }
+}
+
+impl<S, Sym, Val, Prov> KvAdapter<S, Sym, Val, Prov>
+where
+    S: Store,
+    Val: TValue,
+{
+    /// Verify the derived trees against primary storage, and report any problems found. Nothing is written.
+    /// Writes wait until the check is done, so that it sees a consistent store
+    pub fn check(&self) -> Result<Report, Error> {
+        let _exclusive = self.write_gate.write().unwrap();
+        Ok(self.examine()?.0)
+    }
+
+    /// As `check`, then rebuild every derived tree from primary storage, in a single transaction.
+    /// Problems with primary storage itself are reported, but cannot be repaired
+    pub fn repair(&self) -> Result<Report, Error> {
+        let _exclusive = self.write_gate.write().unwrap();
+        let (mut report, tx) = self.examine()?;
+
+        // The write gate is already held, so apply directly
+        self.store.apply(tx)?;
+        self.store.flush()?;
+        self.next_entity_ix.fetch_max(report.next_entity_ix, Ordering::SeqCst);
+
+        report.repaired = true;
+        Ok(report)
+    }
+
+    fn derived_trees(&self) -> [(&'static str, &S::Tree); 8] {
+        [
+            (ENTITY_ID_TO_IX, &self.entity_id_to_ix),
+            (ENTITY_BY_SHORT_ID, &self.entity_by_short_id),
+            (HYPEREDGE_BY_ENTITY_ID, &self.idx_entity_to_hyperedge),
+            (ENTITY_BY_PROPERTY_VALUE, &self.idx_propertyvalue_to_entity),
+            (ENTITY_BY_PROPERTY_RANGE, &self.idx_propertyrange_to_entity),
+            (ENTITY_BY_TERM, &self.idx_term_to_entity),
+            (TEXT_LENGTHS, &self.text_lengths),
+            (DANGLING_MEMBERS, &self.dangling_members),
+        ]
+    }
+
+    /// Report on the store, and prepare the transaction which would repair it
+    fn examine(&self) -> Result<(Report, Transaction<'_, S::Tree>), Error> {
+        let mut report = Report {
+            next_entity_ix: recover_next_entity_ix(&self.entity_storage)?,
+            ..Default::default()
+        };
+        let mut tx = Transaction::new();
+
+        let mut tombstones = BTreeSet::new();
+        for rec in self.tombstones.iter() {
+            let (key, _) = rec?;
+            match EntityId::from_slice(&key) {
+                Ok(entity_id) => {
+                    tombstones.insert(entity_id);
+                },
+                Err(_) => report.problems.push(Problem::Undecodable {
+                    tree: "hypergraph::tombstones",
+                    key: key.to_vec(),
+                }),
+            }
+        }
+
+        let mut entities: BTreeMap<EntityId, (EntityIx, StoredEntity)> = BTreeMap::new();
+        for rec in self.entity_storage.iter() {
+            let (key, value) = rec?;
+            let stored = match (key.as_ref().try_into(), StoredEntity::deserialize(&value)) {
+                (Ok(ix_bytes), Ok(stored)) => (u64::from_be_bytes(ix_bytes), stored),
+                _ => {
+                    report.problems.push(Problem::Undecodable {
+                        tree: "hypergraph::entity_storage",
+                        key: key.to_vec(),
+                    });
+                    continue;
+                },
+            };
+            report.entities += 1;
+            if entities.contains_key(&(stored.1).0) {
+                report.problems.push(Problem::DuplicateEntity((stored.1).0));
+                continue;
+            }
+            entities.insert((stored.1).0, stored);
+        }
+
+        let mut referenced_values = BTreeSet::new();
+        let mut derived = Derived::default();
+        // Entities with missing symbols or values, whose derived entries may not be wholly derived
+        let mut unsound: BTreeSet<EntityIx> = BTreeSet::new();
+        for (entity_id, (entity_ix, stored)) in entities.iter() {
+            derived.insert(ENTITY_ID_TO_IX, entity_id.0.to_vec(), entity_ix.to_be_bytes().to_vec());
+            derived.insert(ENTITY_BY_SHORT_ID, index::short_id_key(entity_id), Vec::new());
+
+            for (member, role) in stored.2.members() {
+                // Removed members have no adjacencies, but are flagged as dangling instead
+                if tombstones.contains(&member.entity_id) && !entities.contains_key(&member.entity_id) {
+                    derived.merge(
+                        DANGLING_MEMBERS,
+                        entity_id.0.to_vec(),
+                        &member.entity_id.0,
+                        index::merge_byte_list::<typenum::U16>,
+                    );
+                    continue;
+                }
+                if !entities.contains_key(&member.entity_id) {
+                    report.problems.push(Problem::MissingMember {
+                        hyperedge: *entity_id,
+                        member: member.entity_id,
+                    });
+                }
+
+                let adjacency = Adjacency {
+                    hyperedge: *entity_id,
+                    role,
+                    degree: member.degree,
+                };
+                derived.merge(
+                    HYPEREDGE_BY_ENTITY_ID,
+                    member.entity_id.0.to_vec(),
+                    &index::adjacency_bytes(&adjacency),
+                    index::merge_byte_list::<index::AdjacencyLen>,
+                );
+            }
+
+            for prop in stored.1.iter() {
+                derived.merge(
+                    ENTITY_BY_PROPERTY_VALUE,
+                    prop.index_key(),
+                    &entity_ix.to_be_bytes(),
+                    index::merge_byte_list::<typenum::U8>,
+                );
+            }
+
+            // Range keys and text are derived from the values themselves, so those of any missing values cannot be derived.
+            // What can be is derived all the same, and whatever else the derived trees hold for the entity is left alone
+            let sound = self.check_references(entity_id, &stored.1, &mut referenced_values, &mut report)?;
+            if !sound {
+                unsound.insert(*entity_ix);
+            }
+            match self.derive_from_values(*entity_ix, &stored.1) {
+                Ok((keys, postings, whole)) => {
+                    for key in keys {
+                        derived.insert(ENTITY_BY_PROPERTY_RANGE, key, entity_id.0.to_vec());
+                    }
+                    for (key, occurrences) in postings.entries(*entity_ix) {
+                        derived.insert(ENTITY_BY_TERM, key, occurrences);
+                    }
+                    // The length of the text is only known if all of it is, otherwise the one recorded stands
+                    let length = if whole {
+                        postings.length
+                    } else {
+                        match self.text_lengths.get(entity_ix.to_be_bytes())? {
+                            Some(bytes) => u32::from_be_bytes(bytes.as_ref().try_into().map_err(|_| Error::InvalidSlice)?),
+                            None => 0,
+                        }
+                    };
+                    if length > 0 {
+                        derived.insert(TEXT_LENGTHS, entity_ix.to_be_bytes().to_vec(), length.to_be_bytes().to_vec());
+                        derived.merge(
+                            TEXT_LENGTHS,
+                            text::TOTALS_KEY.to_vec(),
+                            &text::totals_operand(length, true),
+                            text::merge_totals,
+                        );
+                    }
+                },
+                Err(_) => report.problems.push(Problem::Undecodable {
+                    tree: "hypergraph::entity_storage",
+                    key: entity_ix.to_be_bytes().to_vec(),
+                }),
+            }
+        }
+
+        // History is primary, but is of no use once its entity is gone
+        for rec in self.property_history.iter() {
+            let (key, value) = rec?;
+            let entity_id = match key.len() {
+                20 => EntityId::from_slice(&key[..16]).ok(),
+                _ => None,
+            };
+            match entity_id.zip(bincode::deserialize::<Vec<StoredProperty>>(&value).ok()) {
+                Some((entity_id, properties)) if entities.contains_key(&entity_id) => {
+                    self.check_references(&entity_id, &properties, &mut referenced_values, &mut report)?;
+                },
+                Some(_) => {
+                    report.problems.push(Problem::OrphanEntry {
+                        tree: PROPERTY_HISTORY,
+                        key: key.to_vec(),
+                    });
+                    tx.remove(&self.property_history, &key);
+                },
+                None => report.problems.push(Problem::Undecodable {
+                    tree: PROPERTY_HISTORY,
+                    key: key.to_vec(),
+                }),
+            }
+        }
+
+        for rec in self.value_storage.iter() {
+            let (key, _) = rec?;
+            if !referenced_values.contains(key.as_ref()) {
+                report.unreferenced_values += 1;
+            }
+        }
+
+        for (name, tree) in self.derived_trees().iter().copied() {
+            let mut expected = derived.take(name);
+            for rec in tree.iter() {
+                let (key, value) = rec?;
+                let expected_value = expected.remove(key.as_ref());
+                if derived_from_values(name) && matches!(owner(&key), Some(entity_ix) if unsound.contains(&entity_ix)) {
+                    continue;
+                }
+                match expected_value {
+                    Some(expected_value) if expected_value == value.as_ref() => {},
+                    Some(expected_value) => {
+                        report.problems.push(Problem::WrongEntry { tree: name, key: key.to_vec() });
+                        tx.insert(tree, &key, expected_value);
+                    },
+                    None => {
+                        report.problems.push(Problem::OrphanEntry { tree: name, key: key.to_vec() });
+                        tx.remove(tree, &key);
+                    },
+                }
+            }
+            for (key, value) in expected {
+                report.problems.push(Problem::MissingEntry { tree: name, key: key.clone() });
+                tx.insert(tree, key, value);
+            }
+        }
+
+        Ok((report, tx))
+    }
+
+    /// Range keys and text postings for the properties, as `StoredEntity::property_range_keys` and
+    /// `StoredEntity::text_postings` would derive them, but leaving out any whose values are missing.
+    /// Also returns whether none were left out
+    fn derive_from_values(&self, entity_ix: EntityIx, properties: &[StoredProperty]) -> Result<(Vec<Vec<u8>>, Postings, bool), Error> {
+        let mut keys = Vec::new();
+        let mut postings = Postings::default();
+        let mut whole = true;
+        for (i, prop) in properties.iter().enumerate() {
+            if let StoredValue::Remote(hash) = &prop.1 {
+                if !self.value_storage.contains_key(hash.0)? {
+                    whole = false;
+                    continue;
+                }
+            }
+            let value: Val = prop.1.resolve(self)?;
+            if let Some(sort_key) = value.sort_key() {
+                keys.push(index::property_range_key(prop.0, &sort_key, entity_ix));
+            }
+            if let Some(text) = value.text() {
+                postings.add(i as u32, prop.0, text);
+            }
+        }
+        Ok((keys, postings, whole))
+    }
+
+    /// Check that the symbols and values of the properties exist, and note which values are referenced.
+    /// Returns false if any are missing
+    fn check_references(
+        &self, entity_id: &EntityId, properties: &[StoredProperty], referenced_values: &mut BTreeSet<Vec<u8>>, report: &mut Report,
+    ) -> Result<bool, Error> {
+        let mut complete = true;
+        for prop in properties.iter() {
+            if !self.symbol_storage.contains_key(prop.0.to_be_bytes())? {
+                report.problems.push(Problem::MissingSymbol {
+                    entity_id: *entity_id,
+                    symbol_ix: prop.0,
+                });
+                complete = false;
+            }
+            if let StoredValue::Remote(hash) = &prop.1 {
+                referenced_values.insert(hash.0.to_vec());
+                if !self.value_storage.contains_key(hash.0)? {
+                    report.problems.push(Problem::MissingValue {
+                        entity_id: *entity_id,
+                        hash: *hash,
+                    });
+                    complete = false;
+                }
+            }
+        }
+        Ok(complete)
+    }
+}
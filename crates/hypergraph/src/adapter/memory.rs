@@ -1 +1,43 @@
-pub struct MemoryAdapter<Prop, Val, Prov = ()> {}
+use crate::{kv::memory::MemoryStore, Error};
+
+use super::{kv::KvAdapter, sled::SledAdapter};
+
+/// Non-persistent storage adapter, for tests and for building fixtures which may later be saved to a `SledAdapter`
+pub type MemoryAdapter<Sym, Val, Prov = ()> = KvAdapter<MemoryStore, Sym, Val, Prov>;
+
+impl<Sym, Val, Prov> KvAdapter<MemoryStore, Sym, Val, Prov>
+where
+    Sym: crate::traits::TSymbol,
+    Val: crate::traits::TValue,
+    Prov: crate::traits::TProvenance,
+{
+    pub fn new() -> Self {
+        // Opening the trees of a MemoryStore cannot fail
+        Self::from_store(MemoryStore::default()).unwrap()
+    }
+
+    /// Load every entity stored in the given `SledAdapter` into a new `MemoryAdapter`.
+    /// `EntityId`s are preserved, but `EntityIx`s and `SymbolIx`s are reassigned
+    pub fn from_sled(sled: &SledAdapter<Sym, Val, Prov>) -> Result<Self, Error> {
+        let me = Self::new();
+        me.copy_from(sled)?;
+        Ok(me)
+    }
+
+    /// Write every entity in this adapter into the given `SledAdapter`.
+    /// `EntityId`s are preserved, but `EntityIx`s and `SymbolIx`s are allocated by the `SledAdapter`
+    pub fn save_to_sled(&self, sled: &SledAdapter<Sym, Val, Prov>) -> Result<(), Error> {
+        sled.copy_from(self)
+    }
+}
+
+impl<Sym, Val, Prov> Default for KvAdapter<MemoryStore, Sym, Val, Prov>
+where
+    Sym: crate::traits::TSymbol,
+    Val: crate::traits::TValue,
+    Prov: crate::traits::TProvenance,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,6 @@
+use crate::kv::toboggan::TobogganStore;
+
+use super::kv::KvAdapter;
+
+/// Storage adapter over a toboggan-kv store, such as the one mindbase-core keeps its own trees in. See `crate::kv::toboggan`
+pub type TobogganAdapter<T, Sym, Val, Prov = ()> = KvAdapter<TobogganStore<T>, Sym, Val, Prov>;
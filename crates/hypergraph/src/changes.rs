@@ -0,0 +1,367 @@
+//! A feed of the changes made to a hypergraph, for reacting to them as they land rather than polling.
+//!
+//! Every insert, amendment and removal is recorded in a change log, in the same transaction as the change itself,
+//! under a monotonic ULID `ChangeId`. Subscribers are sent each change as it is committed, and may first catch up
+//! on whatever they missed by resuming after the last `ChangeId` they saw.
+//!
+//! The log grows with every change until it is trimmed, by `trim_changes` with a cut-off such as `ChangeId::at`. Each
+//! subscriber holds up to `INBOX_CAPACITY` changes which it has yet to take. Beyond that, changes are dropped, and the
+//! subscriber is told how many with `Error::Lagged`, after which it should resubscribe after the last change it saw.
+
+use std::{
+    collections::{BTreeSet, VecDeque},
+    convert::TryInto,
+    fmt::{Debug, Display},
+    marker::PhantomData,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, Weak},
+    task::{Context, Poll, Waker},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rusty_ulid::Ulid;
+use serde::{Deserialize, Serialize};
+
+use crate::{entity::SymbolIx, traits::TSymbol, EntityId, Error};
+
+/// How many changes a subscriber may fall behind by before they are dropped
+pub const INBOX_CAPACITY: usize = 1024;
+
+/// Under `migrate::META_TREE`, the last `ChangeId` trimmed from the change log
+pub(crate) const TRIMMED_KEY: &[u8] = b"changes_trimmed_through";
+
+/// Identifies a change, in the order in which changes were committed. Like `EntityId`, this is a ULID
+#[derive(Serialize, Deserialize, Clone, Copy, Ord, PartialOrd, PartialEq, Eq)]
+pub struct ChangeId(pub(crate) [u8; 16]);
+
+impl ChangeId {
+    pub fn from_slice(slice: &[u8]) -> Result<Self, Error> {
+        Ok(ChangeId(slice.try_into().map_err(|_| Error::InvalidSlice)?))
+    }
+    /// The lowest id which could have been assigned at the given time, such that resuming after it
+    /// yields every change made since. Resume after `ChangeId::at(UNIX_EPOCH)` to replay the whole log
+    pub fn at(time: SystemTime) -> Self {
+        let millis = time.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+        let mut bytes = [0u8; 16];
+        bytes[..6].copy_from_slice(&millis.to_be_bytes()[2..]);
+        ChangeId(bytes)
+    }
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+    pub fn full(&self) -> String {
+        use base64::STANDARD_NO_PAD;
+        base64::encode_config(self.0, STANDARD_NO_PAD)
+    }
+}
+
+impl Display for ChangeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.full())
+    }
+}
+
+impl Debug for ChangeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.full())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    Insert,
+    /// The entity's properties were amended
+    Update,
+    Remove,
+}
+
+/// A change to an entity, with the symbols of the properties it touched which match the subscription's filter.
+/// For inserts and removals, these are the symbols of all of the entity's properties; for updates, those which were amended
+#[derive(Debug, PartialEq)]
+pub struct Change<Sym> {
+    pub id: ChangeId,
+    pub kind: ChangeKind,
+    pub entity_id: EntityId,
+    pub symbols: Vec<Sym>,
+}
+
+/// Which changes a subscription should yield
+pub enum Filter<Sym> {
+    /// Every change
+    Any,
+    /// Only changes which touch a property with any of these symbols
+    Symbols(Vec<Sym>),
+}
+
+/// A change as it is recorded in the change log
+#[derive(Serialize, Deserialize)]
+pub(crate) struct StoredChange {
+    pub kind: ChangeKind,
+    pub entity_id: EntityId,
+    pub symbols: Vec<SymbolIx>,
+}
+
+impl StoredChange {
+    /// Symbols are kept in the order the entity holds them, without repeats
+    pub(crate) fn new(kind: ChangeKind, entity_id: EntityId, symbols: impl IntoIterator<Item = SymbolIx>) -> Self {
+        let mut seen = BTreeSet::new();
+        let symbols = symbols.into_iter().filter(|s| seen.insert(*s)).collect();
+        StoredChange { kind, entity_id, symbols }
+    }
+}
+
+/// A `Filter` whose symbols have been looked up
+pub(crate) enum StoredFilter {
+    Any,
+    Symbols {
+        interned: BTreeSet<SymbolIx>,
+        /// The serialized symbols which have yet to be interned. No change can touch them until they are
+        pending: Vec<Vec<u8>>,
+    },
+}
+
+impl StoredFilter {
+    /// Look up the pending symbols again, as they may have been interned since
+    pub(crate) fn intern_pending<F>(&mut self, lookup: F) -> Result<(), Error>
+    where
+        F: Fn(&[u8]) -> Result<Option<SymbolIx>, Error>,
+    {
+        if let StoredFilter::Symbols { interned, pending } = self {
+            let mut still_pending = Vec::new();
+            for bytes in pending.drain(..) {
+                match lookup(&bytes)? {
+                    Some(symbol_ix) => {
+                        interned.insert(symbol_ix);
+                    },
+                    None => still_pending.push(bytes),
+                }
+            }
+            *pending = still_pending;
+        }
+        Ok(())
+    }
+    /// The symbols of the change which match, or None if the change should not be yielded at all
+    pub(crate) fn matching(&self, change: &StoredChange) -> Option<Vec<SymbolIx>> {
+        match self {
+            StoredFilter::Any => Some(change.symbols.clone()),
+            StoredFilter::Symbols { interned, .. } => {
+                let matched: Vec<SymbolIx> = change.symbols.iter().copied().filter(|s| interned.contains(s)).collect();
+                if matched.is_empty() {
+                    None
+                } else {
+                    Some(matched)
+                }
+            },
+        }
+    }
+}
+
+/// A change whose symbols have been matched and resolved to their serialized form, ready for delivery
+pub(crate) struct RawChange {
+    id: ChangeId,
+    kind: ChangeKind,
+    entity_id: EntityId,
+    symbols: Vec<Vec<u8>>,
+}
+
+impl RawChange {
+    /// Match the change against the filter, and fetch the serialized symbols which match
+    pub(crate) fn new<F>(id: ChangeId, change: &StoredChange, filter: &StoredFilter, symbol_bytes: F) -> Result<Option<Self>, Error>
+    where
+        F: Fn(SymbolIx) -> Result<Vec<u8>, Error>,
+    {
+        let symbols = match filter.matching(change) {
+            Some(symbols) => symbols,
+            None => return Ok(None),
+        };
+        Ok(Some(RawChange {
+            id,
+            kind: change.kind,
+            entity_id: change.entity_id,
+            symbols: symbols.into_iter().map(symbol_bytes).collect::<Result<_, Error>>()?,
+        }))
+    }
+}
+
+#[derive(Default)]
+struct Inbox {
+    state: Mutex<InboxState>,
+    ready: Condvar,
+}
+
+/// What a subscriber is sent
+enum Delivery {
+    Change(RawChange),
+    /// This many changes were dropped, as the inbox was full
+    Lagged(u64),
+}
+
+#[derive(Default)]
+struct InboxState {
+    queue: VecDeque<Delivery>,
+    waker: Option<Waker>,
+    /// The hypergraph has been dropped, so nothing more will arrive
+    closed: bool,
+}
+
+impl InboxState {
+    /// Queue the changes, dropping those which do not fit. Consecutive drops are counted together
+    fn extend(&mut self, changes: impl IntoIterator<Item = RawChange>) {
+        for change in changes {
+            if self.queue.len() < INBOX_CAPACITY {
+                self.queue.push_back(Delivery::Change(change));
+            } else if let Some(Delivery::Lagged(dropped)) = self.queue.back_mut() {
+                *dropped += 1;
+            } else {
+                self.queue.push_back(Delivery::Lagged(1));
+            }
+        }
+    }
+}
+
+impl Inbox {
+    fn update<F: FnOnce(&mut InboxState)>(&self, f: F) {
+        let mut state = self.state.lock().unwrap();
+        f(&mut state);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        self.ready.notify_all();
+    }
+}
+
+/// The subscribers of an adapter, and the last `ChangeId` it assigned
+pub(crate) struct Feed {
+    last: Ulid,
+    subscribers: Vec<(StoredFilter, Weak<Inbox>)>,
+}
+
+impl Feed {
+    /// Continue on from the last change in the log, if any
+    pub(crate) fn new(last: Option<ChangeId>) -> Self {
+        Feed {
+            last: Ulid::from(last.map_or([0u8; 16], |id| id.0)),
+            subscribers: Vec::new(),
+        }
+    }
+    pub(crate) fn next_id(&mut self) -> ChangeId {
+        self.last = Ulid::next_monotonic(self.last);
+        ChangeId(self.last.into())
+    }
+    /// Including any which have been dropped, but not yet forgotten by `publish`
+    pub(crate) fn has_subscribers(&self) -> bool {
+        !self.subscribers.is_empty()
+    }
+    pub(crate) fn filters(&self) -> impl Iterator<Item = &StoredFilter> {
+        self.subscribers.iter().map(|(filter, _)| filter)
+    }
+    pub(crate) fn filters_mut(&mut self) -> impl Iterator<Item = &mut StoredFilter> {
+        self.subscribers.iter_mut().map(|(filter, _)| filter)
+    }
+    /// Add a subscriber, which will first yield as much of the backlog as fits in its inbox. The feed must stay locked from reading the backlog
+    /// until the subscriber is added, so that no change is missed or yielded twice
+    pub(crate) fn subscribe<Sym>(&mut self, filter: StoredFilter, backlog: Vec<RawChange>) -> Subscription<Sym> {
+        let inbox = Arc::new(Inbox::default());
+        inbox.state.lock().unwrap().extend(backlog);
+        self.subscribers.push((filter, Arc::downgrade(&inbox)));
+        Subscription {
+            inbox,
+            _sym: PhantomData,
+        }
+    }
+    /// Deliver committed changes to their subscribers. `matched` holds each change as matched by the filter of each subscriber,
+    /// in the order of `filters`. Subscribers which have since been dropped are forgotten
+    pub(crate) fn publish(&mut self, matched: Vec<Vec<RawChange>>) {
+        let mut matched = matched.into_iter();
+        self.subscribers.retain(|(_, inbox)| {
+            let changes = matched.next().unwrap_or_default();
+            match inbox.upgrade() {
+                Some(inbox) => {
+                    if !changes.is_empty() {
+                        inbox.update(|state| state.extend(changes));
+                    }
+                    true
+                },
+                None => false,
+            }
+        });
+    }
+}
+
+impl Drop for Feed {
+    fn drop(&mut self) {
+        for (_, inbox) in self.subscribers.iter() {
+            if let Some(inbox) = inbox.upgrade() {
+                inbox.update(|state| state.closed = true);
+            }
+        }
+    }
+}
+
+/// Yields changes as they are committed, blocking until each arrives. See `into_stream` for async use.
+/// Yields `Error::Lagged` where changes were dropped for want of room, and ends once the hypergraph it subscribes to has
+/// been dropped
+pub struct Subscription<Sym> {
+    inbox: Arc<Inbox>,
+    _sym: PhantomData<fn() -> Sym>,
+}
+
+/// A `Subscription` as an async `Stream`
+pub struct ChangeStream<Sym>(Subscription<Sym>);
+
+impl<Sym: TSymbol> Subscription<Sym> {
+    pub fn into_stream(self) -> ChangeStream<Sym> {
+        ChangeStream(self)
+    }
+    /// The next change, if one has already arrived
+    pub fn try_recv(&self) -> Option<Result<Change<Sym>, Error>> {
+        self.inbox.state.lock().unwrap().queue.pop_front().map(Self::resolve)
+    }
+
+    fn resolve(delivery: Delivery) -> Result<Change<Sym>, Error> {
+        let raw = match delivery {
+            Delivery::Change(raw) => raw,
+            Delivery::Lagged(dropped) => return Err(Error::Lagged(dropped)),
+        };
+        Ok(Change {
+            id: raw.id,
+            kind: raw.kind,
+            entity_id: raw.entity_id,
+            symbols: raw.symbols.iter().map(|bytes| TSymbol::deserialize(bytes)).collect::<Result<_, Error>>()?,
+        })
+    }
+}
+
+impl<Sym: TSymbol> Iterator for Subscription<Sym> {
+    type Item = Result<Change<Sym>, Error>;
+
+    /// Block until the next change arrives
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut state = self.inbox.state.lock().unwrap();
+        loop {
+            if let Some(delivery) = state.queue.pop_front() {
+                return Some(Self::resolve(delivery));
+            }
+            if state.closed {
+                return None;
+            }
+            state = self.inbox.ready.wait(state).unwrap();
+        }
+    }
+}
+
+impl<Sym: TSymbol> futures_core::Stream for ChangeStream<Sym> {
+    type Item = Result<Change<Sym>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.0.inbox.state.lock().unwrap();
+        if let Some(delivery) = state.queue.pop_front() {
+            return Poll::Ready(Some(Subscription::resolve(delivery)));
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
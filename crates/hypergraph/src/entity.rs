@@ -1,6 +1,8 @@
 use std::{
+    cmp::Ordering,
     convert::TryInto,
     fmt::{Debug, Display},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use itertools::Itertools;
@@ -15,18 +17,50 @@ use crate::{
 #[derive(Serialize, Deserialize, Clone, Copy, Ord, PartialOrd, PartialEq, Eq)]
 pub struct EntityId(pub(crate) [u8; 16]);
 pub type EntityIx = u64;
+/// Compact id of an interned symbol, which entity records use in place of the full symbol
+pub type SymbolIx = u64;
+/// Compact id of a provenance record, shared by the entity or properties asserted along with it
+pub type ProvenanceIx = u64;
+/// The version of an entity's properties. Entities are inserted at version 0, and each amendment increments it
+pub type Version = u32;
 
 impl EntityId {
+    pub(crate) fn generate() -> Self {
+        EntityId(rusty_ulid::generate_ulid_bytes())
+    }
     pub fn from_slice(slice: &[u8]) -> Result<Self, Error> {
         Ok(EntityId(slice.try_into().map_err(|_| Error::InvalidSlice)?))
     }
+    /// The time at which this id was generated, to the millisecond
+    pub fn created(&self) -> SystemTime {
+        let mut millis = [0u8; 8];
+        millis[2..].copy_from_slice(&self.0[..6]);
+        UNIX_EPOCH + Duration::from_millis(u64::from_be_bytes(millis))
+    }
+    /// The lowest id which could have been generated at the given time. Ids sort by their timestamp first,
+    /// so every id generated within the same millisecond or later sorts at or above this one
+    pub(crate) fn lower_bound(time: SystemTime) -> Self {
+        let millis = time.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+        let mut bytes = [0u8; 16];
+        bytes[..6].copy_from_slice(&millis.to_be_bytes()[2..]);
+        EntityId(bytes)
+    }
     pub fn short(&self) -> String {
         use base64::STANDARD_NO_PAD;
         base64::encode_config(&self.0[12..], STANDARD_NO_PAD)
     }
     pub fn full(&self) -> String {
         use base64::STANDARD_NO_PAD;
-        base64::encode_config(&self.0, STANDARD_NO_PAD)
+        base64::encode_config(self.0, STANDARD_NO_PAD)
+    }
+    /// Parse the full form of an id, as given by `full`
+    pub fn from_full(full: &str) -> Result<Self, Error> {
+        use base64::STANDARD_NO_PAD;
+        if full.len() != FULL_LEN {
+            return Err(Error::InvalidId);
+        }
+        let bytes = base64::decode_config(full, STANDARD_NO_PAD).map_err(|_| Error::InvalidId)?;
+        Self::from_slice(&bytes)
     }
     pub fn write_short<W: std::io::Write>(&self, w: W) {
         use base64::STANDARD_NO_PAD;
@@ -44,6 +78,66 @@ impl EntityId {
     }
 }
 
+/// The length of `EntityId::full`
+const FULL_LEN: usize = 22;
+/// The length of `EntityId::short`
+const SHORT_LEN: usize = 6;
+
+/// The length of the id bytes which the short form spells
+pub(crate) const SHORT_BYTES: usize = 4;
+
+/// The start of an `EntityId` as a user might give it, in either its full or its short form
+pub(crate) struct IdPrefix<'a> {
+    prefix: &'a str,
+    /// The bytes the prefix spells, with whatever bits it leaves unspelled as zeros
+    least: Vec<u8>,
+    /// and as ones
+    greatest: Vec<u8>,
+}
+
+impl<'a> IdPrefix<'a> {
+    pub(crate) fn parse(prefix: &'a str) -> Result<Self, Error> {
+        let valid = !prefix.is_empty()
+            && prefix.len() <= FULL_LEN
+            && prefix.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/');
+        if !valid {
+            return Err(Error::InvalidId);
+        }
+        Ok(IdPrefix {
+            prefix,
+            least: spelled(prefix, 'A')?,
+            greatest: spelled(prefix, '/')?,
+        })
+    }
+    /// The least and greatest values which the start of a matching id may have
+    pub(crate) fn full_span(&self) -> (&[u8], &[u8]) {
+        (&self.least, &self.greatest)
+    }
+    /// The same, for the bytes which the short form spells, if the prefix is not too long to be one
+    pub(crate) fn short_span(&self) -> Option<(&[u8], &[u8])> {
+        if self.prefix.len() > SHORT_LEN {
+            return None;
+        }
+        let len = self.least.len().min(SHORT_BYTES);
+        Some((&self.least[..len], &self.greatest[..len]))
+    }
+    pub(crate) fn matches(&self, entity_id: &EntityId) -> bool {
+        entity_id.full().starts_with(self.prefix) || (self.prefix.len() <= SHORT_LEN && entity_id.short().starts_with(self.prefix))
+    }
+}
+
+/// The bytes which a base64 prefix spells, up to the length of an id, with any bits it leaves unspelled taken from `fill`
+fn spelled(prefix: &str, fill: char) -> Result<Vec<u8>, Error> {
+    use base64::STANDARD_NO_PAD;
+    let mut padded = prefix.to_string();
+    while padded.len() < prefix.len().div_ceil(4) * 4 {
+        padded.push(fill);
+    }
+    let mut bytes = base64::decode_config(&padded, STANDARD_NO_PAD).map_err(|_| Error::InvalidId)?;
+    bytes.truncate((prefix.len() * 6).div_ceil(8).min(16));
+    Ok(bytes)
+}
+
 impl Display for EntityId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.short())
@@ -55,6 +149,44 @@ impl Debug for EntityId {
     }
 }
 
+/// The length of a `ValueHash`, and so also the length at which values stop being stored inline
+pub const VALUE_HASH_LEN: usize = 32;
+
+/// Sha512Trunc256 of a serialized value, by which larger values are stored and shared
+#[derive(Serialize, Deserialize, Clone, Copy, Ord, PartialOrd, PartialEq, Eq, Hash)]
+pub struct ValueHash(pub(crate) [u8; VALUE_HASH_LEN]);
+
+impl ValueHash {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        use sha2::{Digest, Sha512Trunc256};
+        let mut hasher = Sha512Trunc256::default();
+        hasher.update(bytes);
+        ValueHash(hasher.finalize().into())
+    }
+    /// The hash under which this value would be stored, if it is too large to be stored inline
+    pub fn of<Val: TValue>(value: &Val) -> Self {
+        Self::new(&TValue::serialize(value))
+    }
+    pub fn from_slice(slice: &[u8]) -> Result<Self, Error> {
+        Ok(ValueHash(slice.try_into().map_err(|_| Error::InvalidSlice)?))
+    }
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Display for ValueHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use base64::STANDARD_NO_PAD;
+        write!(f, "{}", base64::encode_config(self.0, STANDARD_NO_PAD))
+    }
+}
+impl Debug for ValueHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ValueHash:{}", self)
+    }
+}
+
 #[derive(Debug)]
 pub struct Property<Sym, Val>
 where
@@ -75,25 +207,145 @@ where
     pub(crate) inner: EntityInner,
 }
 
-/// TODO1 - implement fuzzy membership
-#[derive(Serialize, Deserialize, Debug)]
+impl<Sym, Val> Entity<Sym, Val>
+where
+    Sym: TSymbol,
+    Val: TValue,
+{
+    pub fn is_vertex(&self) -> bool {
+        matches!(self.inner, EntityInner::Vertex)
+    }
+    /// Every member of this hyperedge, its degree of membership, and the side it sits on. Vertices have no members
+    pub fn members(&self) -> Vec<(Member, MemberRole)> {
+        self.inner.members()
+    }
+}
+
+/// Who asserted an entity, and who asserted each of its properties, in the same order as `Entity::properties`.
+/// None where the assertion was made without provenance
+#[derive(Debug)]
+pub struct Attribution<Prov> {
+    pub entity: Option<Prov>,
+    pub properties: Vec<Option<Prov>>,
+}
+
+/// A change to the properties of an existing entity
+#[derive(Debug)]
+pub enum Amendment<Sym, Val>
+where
+    Sym: TSymbol,
+    Val: TValue,
+{
+    /// Add a property, alongside any others with the same key
+    Append(Property<Sym, Val>),
+    /// Retract every property with the same key, and add this one in their place
+    Replace(Property<Sym, Val>),
+    /// Retract every property with this key
+    Retract(Sym),
+}
+
+/// The degree of membership of members which are given without one
+pub const FULL_MEMBERSHIP: f64 = 1.0;
+
+/// A member of a hyperedge, and the degree to which it is a member, in the manner of `fs::Item`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Member {
+    pub entity_id: EntityId,
+    pub degree: f64,
+}
+
+impl From<EntityId> for Member {
+    fn from(entity_id: EntityId) -> Self {
+        Member {
+            entity_id,
+            degree: FULL_MEMBERSHIP,
+        }
+    }
+}
+impl From<(EntityId, f64)> for Member {
+    fn from((entity_id, degree): (EntityId, f64)) -> Self {
+        Member { entity_id, degree }
+    }
+}
+
+impl Display for Member {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.degree == FULL_MEMBERSHIP {
+            write!(f, "{}", self.entity_id.short())
+        } else {
+            write!(f, "{}^{:0.2}", self.entity_id.short(), self.degree)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub(crate) enum EntityInner {
     Vertex,
-    Edge(Vec<EntityId>),
-    DirectedEdge(Vec<EntityId>, Vec<EntityId>),
+    Edge(Vec<Member>),
+    DirectedEdge(Vec<Member>, Vec<Member>),
+}
+
+/// The side of a hyperedge on which a given member entity sits
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Ord, PartialOrd, PartialEq, Eq)]
+pub enum MemberRole {
+    /// Member of an undirected hyperedge
+    Undirected,
+    /// Member of the "from" side of a directed hyperedge
+    From,
+    /// Member of the "to" side of a directed hyperedge
+    To,
+}
+
+/// A hyperedge which includes some entity, the role that entity plays within it, and the degree of its membership
+#[derive(Clone, Copy, Debug)]
+pub struct Adjacency {
+    pub hyperedge: EntityId,
+    pub role: MemberRole,
+    pub degree: f64,
+}
+
+// Degrees are compared by their total order, so that adjacencies may be kept in ordered sets
+impl Ord for Adjacency {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.hyperedge
+            .cmp(&other.hyperedge)
+            .then(self.role.cmp(&other.role))
+            .then(self.degree.total_cmp(&other.degree))
+    }
+}
+impl PartialOrd for Adjacency {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl PartialEq for Adjacency {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Adjacency {}
+
+impl EntityInner {
+    /// Every member of this entity, and the side of the hyperedge it sits on. Vertices have no members
+    pub(crate) fn members(&self) -> Vec<(Member, MemberRole)> {
+        match self {
+            EntityInner::Vertex => vec![],
+            EntityInner::Edge(m) => m.iter().map(|e| (*e, MemberRole::Undirected)).collect(),
+            EntityInner::DirectedEdge(f, t) => f
+                .iter()
+                .map(|e| (*e, MemberRole::From))
+                .chain(t.iter().map(|e| (*e, MemberRole::To)))
+                .collect(),
+        }
+    }
 }
 
 impl Display for EntityInner {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             EntityInner::Vertex => write!(f, "Vertex"),
-            EntityInner::Edge(e) => write!(f, "Edge({})", e.iter().map(|eid| eid.short()).join(",")),
-            EntityInner::DirectedEdge(fe, te) => write!(
-                f,
-                "DirectedEdge({} -> {})",
-                fe.iter().map(|eid| eid.short()).join(","),
-                te.iter().map(|eid| eid.short()).join(",")
-            ),
+            EntityInner::Edge(e) => write!(f, "Edge({})", e.iter().join(",")),
+            EntityInner::DirectedEdge(fe, te) => write!(f, "DirectedEdge({} -> {})", fe.iter().join(","), te.iter().join(",")),
         }
         // let mut comma = false;
         // match self {
@@ -114,29 +366,37 @@ impl Display for EntityInner {
     }
 }
 
-pub fn directed<'a, Sym, Val, PI, F, T>(properties: PI, from: F, to: T) -> Entity<Sym, Val>
+/// Members may be given as bare `EntityId`s, which are full members, or as `(EntityId, degree)` pairs
+pub fn directed<Sym, Val, PI, F, T>(properties: PI, from: F, to: T) -> Entity<Sym, Val>
 where
     PI: Into<Vec<Property<Sym, Val>>>,
-    F: Into<Vec<EntityId>>,
-    T: Into<Vec<EntityId>>,
+    F: IntoIterator,
+    F::Item: Into<Member>,
+    T: IntoIterator,
+    T::Item: Into<Member>,
     Sym: TSymbol,
     Val: TValue,
 {
     Entity {
         properties: properties.into(),
-        inner: EntityInner::DirectedEdge(from.into(), to.into()),
+        inner: EntityInner::DirectedEdge(
+            from.into_iter().map(Into::into).collect(),
+            to.into_iter().map(Into::into).collect(),
+        ),
     }
 }
-pub fn undirected<'a, Sym, Val, PI, M>(properties: PI, members: M) -> Entity<Sym, Val>
+/// Members may be given as bare `EntityId`s, which are full members, or as `(EntityId, degree)` pairs
+pub fn undirected<Sym, Val, PI, M>(properties: PI, members: M) -> Entity<Sym, Val>
 where
     PI: Into<Vec<Property<Sym, Val>>>,
-    M: Into<Vec<EntityId>>,
+    M: IntoIterator,
+    M::Item: Into<Member>,
     Sym: TSymbol,
     Val: TValue,
 {
     Entity {
         properties: properties.into(),
-        inner: EntityInner::Edge(members.into()),
+        inner: EntityInner::Edge(members.into_iter().map(Into::into).collect()),
     }
 }
 
@@ -1,10 +1,42 @@
+use crate::EntityId;
+
 #[derive(Debug)]
 pub enum Error {
     Sled(sled::Error),
+    Toboggan(toboggan_kv::Error),
     Bincode(bincode::Error),
     Io(std::io::Error),
     NotFound,
+    /// The entity existed, but has since been removed
+    Removed,
     InvalidSlice,
+    /// The entity is not attributed to any of the given agents
+    NotAsserted,
+    /// The value has no sort key, and so cannot be used as a range bound
+    Unordered,
+    /// The file is not a snapshot, or is truncated
+    InvalidSnapshot,
+    /// The restored contents of the named tree do not match the snapshot manifest
+    ChecksumMismatch(String),
+    /// Refusing to restore over existing data
+    AlreadyExists,
+    /// The store was written in another on-disk format version, and must be migrated before it can be opened
+    UnsupportedFormat(u32),
+    /// Not an `EntityId` in base64, nor the start of one
+    InvalidId,
+    /// An entity with this id exists already, or did and has been removed
+    DuplicateId(EntityId),
+    /// More than one entity matches the id prefix. Holds some of those which match
+    Ambiguous(Vec<EntityId>),
+    /// Namespace names may not be empty, nor contain ':'
+    InvalidNamespace(String),
+    /// Something the transaction depended on was changed concurrently, so it was not applied. See `kv::Transaction::expect`.
+    /// Or the store was written while a snapshot was being taken, so it is incomplete
+    Conflict,
+    /// A subscriber fell behind, and this many changes were dropped. Resubscribe after the last change seen to catch up
+    Lagged(u64),
+    /// Some of the changes to be caught up on have been trimmed from the change log, which now starts after this one
+    Trimmed(crate::changes::ChangeId),
 }
 
 impl From<sled::Error> for Error {
@@ -13,10 +45,25 @@ impl From<sled::Error> for Error {
     }
 }
 
+impl From<toboggan_kv::Error> for Error {
+    fn from(e: toboggan_kv::Error) -> Self {
+        Self::Toboggan(e)
+    }
+}
+
+impl From<sled::transaction::TransactionError<Error>> for Error {
+    fn from(e: sled::transaction::TransactionError<Error>) -> Self {
+        use sled::transaction::TransactionError;
+        match e {
+            TransactionError::Abort(e) => e,
+            TransactionError::Storage(e) => Self::Sled(e),
+        }
+    }
+}
+
 impl std::convert::From<Error> for std::io::Error {
     fn from(error: Error) -> Self {
-        use std::io::ErrorKind;
-        std::io::Error::new(ErrorKind::Other, format!("{:?}", error))
+        std::io::Error::other(format!("{:?}", error))
     }
 }
 
@@ -0,0 +1,265 @@
+//! Write the hypergraph, or part of it, in formats which graph tools can open: Graphviz DOT, GraphML for yEd, and GEXF for Gephi.
+//!
+//! None of these formats can express a hyperedge, so each hyperedge is written as a node of its own, linked to each of its members.
+//! Members on the "from" side of a directed hyperedge link into it, it links out to members on its "to" side, and members of an
+//! undirected hyperedge are linked to it without direction. A hyperedge which includes other hyperedges is linked to them just the same.
+//! Nodes carry their kind and their properties as attributes, with the values of a repeated key joined by ", ".
+//! Links carry the role and degree of the membership.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    io::Write,
+};
+
+use crate::{
+    entity::{EntityInner, MemberRole},
+    traits::{TSymbol, TValue},
+    Entity, EntityId, Error,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Graphviz DOT
+    Dot,
+    GraphMl,
+    Gexf,
+}
+
+struct Node {
+    entity_id: EntityId,
+    kind: &'static str,
+    /// Indices into `Export::keys`, and the values for that key
+    properties: Vec<(usize, String)>,
+}
+
+struct Link {
+    source: EntityId,
+    target: EntityId,
+    role: MemberRole,
+    degree: f64,
+}
+
+impl Link {
+    fn is_directed(&self) -> bool {
+        self.role != MemberRole::Undirected
+    }
+}
+
+/// Entities laid out as plain nodes and links, ready to be written in any `Format`
+pub(crate) struct Export {
+    keys: Vec<String>,
+    nodes: Vec<Node>,
+    links: Vec<Link>,
+}
+
+impl Export {
+    /// Only members which are among the given entities are linked. Keys are numbered in order of their symbols' bytes, and links
+    /// are ordered by source, target and role, so that the same entities are always written the same way
+    pub(crate) fn new<Sym, Val>(entities: Vec<(EntityId, Entity<Sym, Val>)>) -> Self
+    where
+        Sym: TSymbol + Display,
+        Val: TValue + Display,
+    {
+        let included: BTreeSet<EntityId> = entities.iter().map(|(entity_id, _)| *entity_id).collect();
+        let symbols: BTreeMap<Vec<u8>, String> = entities
+            .iter()
+            .flat_map(|(_, entity)| entity.properties.iter())
+            .map(|property| (TSymbol::serialize(&property.key), property.key.to_string()))
+            .collect();
+        let key_ixs: BTreeMap<&[u8], usize> = symbols.keys().enumerate().map(|(key_ix, symbol)| (&symbol[..], key_ix)).collect();
+        let mut nodes = Vec::new();
+        let mut links = Vec::new();
+
+        for (entity_id, entity) in entities {
+            let mut properties: Vec<(usize, String)> = Vec::new();
+            for property in entity.properties.iter() {
+                let key_ix = key_ixs[&TSymbol::serialize(&property.key)[..]];
+                match properties.iter_mut().find(|(ix, _)| *ix == key_ix) {
+                    Some((_, values)) => {
+                        values.push_str(", ");
+                        values.push_str(&property.value.to_string());
+                    },
+                    None => properties.push((key_ix, property.value.to_string())),
+                }
+            }
+
+            for (member, role) in entity.members() {
+                if !included.contains(&member.entity_id) {
+                    continue;
+                }
+                let (source, target) = match role {
+                    MemberRole::From => (member.entity_id, entity_id),
+                    MemberRole::To | MemberRole::Undirected => (entity_id, member.entity_id),
+                };
+                links.push(Link {
+                    source,
+                    target,
+                    role,
+                    degree: member.degree,
+                });
+            }
+
+            let kind = match entity.inner {
+                EntityInner::Vertex => "vertex",
+                EntityInner::Edge(_) => "edge",
+                EntityInner::DirectedEdge(..) => "directed",
+            };
+            nodes.push(Node {
+                entity_id,
+                kind,
+                properties,
+            });
+        }
+        links.sort_by_key(|link| (link.source, link.target, link.role));
+
+        Export {
+            keys: symbols.into_values().collect(),
+            nodes,
+            links,
+        }
+    }
+
+    pub(crate) fn write<W: Write>(&self, format: Format, writer: W) -> Result<(), Error> {
+        match format {
+            Format::Dot => self.write_dot(writer),
+            Format::GraphMl => self.write_graphml(writer),
+            Format::Gexf => self.write_gexf(writer),
+        }
+    }
+
+    fn write_dot<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        writeln!(w, "digraph hypergraph {{")?;
+        for node in self.nodes.iter() {
+            let mut label = node.entity_id.short();
+            write!(w, "    \"{}\" [", node.entity_id.full())?;
+            for (key_ix, values) in node.properties.iter() {
+                write!(w, "\"{}\"=\"{}\", ", dot(&self.keys[*key_ix]), dot(values))?;
+                label.push_str(&format!("\n{}: {}", self.keys[*key_ix], values));
+            }
+            let shape = if node.kind == "vertex" { "ellipse" } else { "box" };
+            writeln!(w, "kind=\"{}\", label=\"{}\", shape={}];", node.kind, dot(&label), shape)?;
+        }
+        for link in self.links.iter() {
+            write!(
+                w,
+                "    \"{}\" -> \"{}\" [role=\"{}\", degree={}",
+                link.source.full(),
+                link.target.full(),
+                role(link.role),
+                link.degree
+            )?;
+            if !link.is_directed() {
+                write!(w, ", dir=none")?;
+            }
+            writeln!(w, "];")?;
+        }
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+
+    fn write_graphml<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(w, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+        writeln!(w, r#"  <key id="kind" for="node" attr.name="kind" attr.type="string"/>"#)?;
+        for (key_ix, key) in self.keys.iter().enumerate() {
+            writeln!(w, r#"  <key id="p{}" for="node" attr.name="{}" attr.type="string"/>"#, key_ix, xml(key))?;
+        }
+        writeln!(w, r#"  <key id="role" for="edge" attr.name="role" attr.type="string"/>"#)?;
+        writeln!(w, r#"  <key id="degree" for="edge" attr.name="degree" attr.type="double"/>"#)?;
+        writeln!(w, r#"  <graph id="hypergraph" edgedefault="directed">"#)?;
+        for node in self.nodes.iter() {
+            writeln!(w, r#"    <node id="{}">"#, node.entity_id.full())?;
+            writeln!(w, r#"      <data key="kind">{}</data>"#, node.kind)?;
+            for (key_ix, values) in node.properties.iter() {
+                writeln!(w, r#"      <data key="p{}">{}</data>"#, key_ix, xml(values))?;
+            }
+            writeln!(w, r#"    </node>"#)?;
+        }
+        for (link_ix, link) in self.links.iter().enumerate() {
+            writeln!(
+                w,
+                r#"    <edge id="l{}" source="{}" target="{}" directed="{}">"#,
+                link_ix,
+                link.source.full(),
+                link.target.full(),
+                link.is_directed()
+            )?;
+            writeln!(w, r#"      <data key="role">{}</data>"#, role(link.role))?;
+            writeln!(w, r#"      <data key="degree">{}</data>"#, link.degree)?;
+            writeln!(w, r#"    </edge>"#)?;
+        }
+        writeln!(w, r#"  </graph>"#)?;
+        writeln!(w, r#"</graphml>"#)?;
+        Ok(())
+    }
+
+    fn write_gexf<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(w, r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#)?;
+        writeln!(w, r#"  <graph defaultedgetype="directed" mode="static">"#)?;
+        writeln!(w, r#"    <attributes class="node">"#)?;
+        writeln!(w, r#"      <attribute id="kind" title="kind" type="string"/>"#)?;
+        for (key_ix, key) in self.keys.iter().enumerate() {
+            writeln!(w, r#"      <attribute id="p{}" title="{}" type="string"/>"#, key_ix, xml(key))?;
+        }
+        writeln!(w, r#"    </attributes>"#)?;
+        writeln!(w, r#"    <attributes class="edge">"#)?;
+        writeln!(w, r#"      <attribute id="role" title="role" type="string"/>"#)?;
+        writeln!(w, r#"    </attributes>"#)?;
+        writeln!(w, r#"    <nodes>"#)?;
+        for node in self.nodes.iter() {
+            writeln!(w, r#"      <node id="{}" label="{}">"#, node.entity_id.full(), node.entity_id.short())?;
+            writeln!(w, r#"        <attvalues>"#)?;
+            writeln!(w, r#"          <attvalue for="kind" value="{}"/>"#, node.kind)?;
+            for (key_ix, values) in node.properties.iter() {
+                writeln!(w, r#"          <attvalue for="p{}" value="{}"/>"#, key_ix, xml(values))?;
+            }
+            writeln!(w, r#"        </attvalues>"#)?;
+            writeln!(w, r#"      </node>"#)?;
+        }
+        writeln!(w, r#"    </nodes>"#)?;
+        writeln!(w, r#"    <edges>"#)?;
+        for (link_ix, link) in self.links.iter().enumerate() {
+            writeln!(
+                w,
+                r#"      <edge id="l{}" source="{}" target="{}" type="{}" weight="{}">"#,
+                link_ix,
+                link.source.full(),
+                link.target.full(),
+                if link.is_directed() { "directed" } else { "undirected" },
+                link.degree
+            )?;
+            writeln!(w, r#"        <attvalues>"#)?;
+            writeln!(w, r#"          <attvalue for="role" value="{}"/>"#, role(link.role))?;
+            writeln!(w, r#"        </attvalues>"#)?;
+            writeln!(w, r#"      </edge>"#)?;
+        }
+        writeln!(w, r#"    </edges>"#)?;
+        writeln!(w, r#"  </graph>"#)?;
+        writeln!(w, r#"</gexf>"#)?;
+        Ok(())
+    }
+}
+
+fn role(role: MemberRole) -> &'static str {
+    match role {
+        MemberRole::Undirected => "undirected",
+        MemberRole::From => "from",
+        MemberRole::To => "to",
+    }
+}
+
+/// Escape for a quoted DOT string
+fn dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Escape for XML text and attribute values
+fn xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -1,35 +1,45 @@
-use std::{convert::TryInto, fmt::Display, marker::PhantomData, sync::atomic::AtomicU64};
+use std::{
+    collections::BTreeSet,
+    fmt::{Debug, Display},
+    io::Write,
+    marker::PhantomData,
+    ops::{Bound, RangeBounds},
+    time::SystemTime,
+};
 
-use traits::GraphInterface;
+use itertools::Itertools;
 
 use crate::{
-    adapter::StorageAdapter,
-    entity::{undirected, Entity, EntityInner, EntityIx},
-    index,
-    traits::{self, TValue},
+    adapter::{memory::MemoryAdapter, Cascade, EntityKind, ScanDirection, StorageAdapter},
+    changes::{ChangeId, Filter, Subscription},
+    entity::{Adjacency, Amendment, Attribution, Entity, EntityIx, Property, SymbolIx, ValueHash, Version},
+    export::{Export, Format},
+    text::{Hit, TextQuery},
+    traits,
+    traverse::Traverse,
     Error,
 };
 
-use std::io::Write;
+use crate::entity::EntityId;
 
-use rusty_ulid::generate_ulid_bytes;
+/// How many of the matching entities to report when an id prefix is ambiguous
+const AMBIGUOUS_CANDIDATES: usize = 8;
 
-use crate::entity::EntityId;
 /// ?? Claims are sometimes artifact instances, but illegal instance values are possible to represent
 /// Mixed Hypergraph ( undirected edges are categories, directed are analogies? )
-
+///
 /// How do Hypergraphs contend with edges referencing edges? (This seems exactly how weight
 /// composition ought to work, and there is likely a body of literature on recursion here)
 ///
 /// In this case, an edge would be a symbol, and...
 /// the distinction between edges and nodes starts to break down >_>
-
+///
 /// # HyperGraph
 /// What's special vs a garden variety graph?
 /// * deduplicated weights (Artifacts)
 /// * indexed lookup of nodes and edges by weight
 /// * provenance (and filtration by same)
-pub struct Hypergraph<Stor, Sym, Val, Prov> {
+pub struct Hypergraph<Stor, Sym, Val, Prov = ()> {
     adapter: Stor,
     #[doc(hidden)]
     _sym: PhantomData<Sym>,
@@ -39,10 +49,22 @@ pub struct Hypergraph<Stor, Sym, Val, Prov> {
     _prov: PhantomData<Prov>,
 }
 
+impl<Sym, Val, Prov> Hypergraph<MemoryAdapter<Sym, Val, Prov>, Sym, Val, Prov>
+where
+    Sym: traits::TSymbol,
+    Val: traits::TValue,
+    Prov: traits::TProvenance,
+{
+    /// Create a hypergraph which lives only in memory
+    pub fn memory() -> Self {
+        Self::new(MemoryAdapter::new())
+    }
+}
+
 // TODO: invert the factorization such that we implement put_* and get_* for Sled directly
 impl<Stor, Sym, Val, Prov> Hypergraph<Stor, Sym, Val, Prov>
 where
-    Stor: StorageAdapter<Sym, Val>,
+    Stor: StorageAdapter<Sym, Val, Prov>,
     Sym: traits::TSymbol,
     Val: traits::TValue,
     Prov: traits::TProvenance,
@@ -55,9 +77,9 @@ where
             _prov: PhantomData,
         }
     }
-    pub fn memory() -> Self {
-        unimplemented!()
-        // Self { adapter: StorageAdapter }
+    /// The storage adapter, for operations which are particular to it, such as snapshots
+    pub fn adapter(&self) -> &Stor {
+        &self.adapter
     }
     //  /// Insert an entity into the hypergraph
     //  /// ```
@@ -67,81 +89,612 @@ where
     //  /// graph.insert(entity::vertex("123")).unwrap()
     //  /// ```
     pub fn insert(&self, entity: Entity<Sym, Val>) -> Result<(EntityIx, EntityId), Error> {
-        self.adapter.insert(entity)
-    }
-
-    // fn get_adjacencies(&self, entity_id: &EntityId) -> Result<Vec<EntityId>, Error> {
-    //     match self.idx_entity_to_hyperedge.get(entity_id.0)? {
-    //         Some(bytes) => Ok(bytes.chunks_exact(16).map(|b| EntityId(b.try_into().unwrap())).collect()),
-    //         None => Ok(vec![]),
-    //     }
-    // }
-
-    // fn get_adjacencies_matching<F>(&self, entity_id: &EntityId, filter: F) -> Result<Vec<EntityId>, Error>
-    // where
-    //     F: Fn(&W) -> Result<bool, Error>,
-    // {
-    //     // TODO - How do we index by weight AND symbol - decompose?
-    //     // weights might contain multiple symbols in the future, but for
-    //     // now they may only contain one.
-    //     // Maybe a way to interrogate the weight to get its symbol(s), and index by that
-    //     // How do we index by fuzzy symbols?
-
-    //     let entity_ids = self.get_adjacencies(entity_id)?;
-    //     println!("Adjacencies unfiltered: {:?}", entity_ids);
-
-    //     let mut out = Vec::new();
-
-    //     for entity_id in entity_ids {
-    //         let entity = self.get(&entity_id)?;
-    //         if filter(&entity.weight)? {
-    //             out.push(entity_id)
-    //         }
-    //     }
-    //     Ok(out)
-    // }
+        self.adapter.insert(entity, None)
+    }
+
+    /// Insert an entity, attributing it and each of its properties to whoever is named by the provenance
+    pub fn insert_as(&self, entity: Entity<Sym, Val>, provenance: &Prov) -> Result<(EntityIx, EntityId), Error> {
+        self.adapter.insert(entity, Some(provenance))
+    }
+
+    /// Start a batch of insertions which will be committed atomically.
+    /// Entity ids are assigned as each entity is added, so later entities in the batch may reference earlier ones
+    /// ```
+    /// use mindbase_hypergraph::{entity::{directed, vertex}, Hypergraph};
+    /// let graph = Hypergraph::<_, String, String>::memory();
+    /// let mut batch = graph.batch();
+    /// let a = batch.insert(vertex(vec![]));
+    /// let b = batch.insert(vertex(vec![]));
+    /// batch.insert(directed(vec![], [a], [b]));
+    /// batch.commit().unwrap();
+    /// ```
+    pub fn batch(&self) -> Batch<'_, Stor, Sym, Val, Prov> {
+        Batch {
+            graph: self,
+            entities: Vec::new(),
+        }
+    }
+
+    /// Read an entity, with its current properties
+    pub fn get(&self, entity_id: &EntityId) -> Result<Entity<Sym, Val>, Error> {
+        self.adapter.get(entity_id)
+    }
+
+    /// Read an entity by its storage index. Prefer `get` unless the `EntityIx` came from this same adapter
+    pub fn get_by_ix(&self, entity_ix: &EntityIx) -> Result<Entity<Sym, Val>, Error> {
+        self.adapter.get_by_ix(entity_ix)
+    }
+
+    /// Stream every entity, in creation order
+    pub fn iter(&self) -> Entities<'_, Stor, Sym, Val, Prov> {
+        Entities::new(&self.adapter, EntityKind::Any, Bound::Unbounded, Bound::Unbounded)
+    }
+
+    pub fn iter_vertices(&self) -> Entities<'_, Stor, Sym, Val, Prov> {
+        Entities::new(&self.adapter, EntityKind::Vertex, Bound::Unbounded, Bound::Unbounded)
+    }
+
+    /// Stream every hyperedge, directed or not, in creation order
+    pub fn iter_edges(&self) -> Entities<'_, Stor, Sym, Val, Prov> {
+        Entities::new(&self.adapter, EntityKind::Edge, Bound::Unbounded, Bound::Unbounded)
+    }
+
+    /// Stream the entities created at or after `start`, and before `end`, in creation order.
+    /// Creation times are taken from the `EntityId`, and so are only precise to the millisecond
+    /// ```
+    /// use std::time::{Duration, SystemTime};
+    /// use mindbase_hypergraph::{entity::vertex, Hypergraph};
+    /// let graph = Hypergraph::<_, String, String>::memory();
+    /// let start = SystemTime::now() - Duration::from_secs(1);
+    /// let (_, e) = graph.insert(vertex(vec![])).unwrap();
+    /// let mut found = graph.iter_created_between(start, SystemTime::now() + Duration::from_secs(1));
+    /// assert_eq!(found.next().unwrap().unwrap().0, e);
+    /// ```
+    pub fn iter_created_between(&self, start: SystemTime, end: SystemTime) -> Entities<'_, Stor, Sym, Val, Prov> {
+        Entities::new(
+            &self.adapter,
+            EntityKind::Any,
+            Bound::Included(EntityId::lower_bound(start)),
+            Bound::Excluded(EntityId::lower_bound(end)),
+        )
+    }
+
+    /// Write a line for each entity, for debugging
+    pub fn dump_entities<O: Write>(&self, mut writer: O) -> Result<(), Error>
+    where
+        Sym: Debug,
+        Val: Debug,
+    {
+        for entity in self.iter() {
+            let (entity_id, entity) = entity?;
+            write!(writer, "{} = {}", entity_id, entity.inner)?;
+            for property in entity.properties {
+                write!(writer, " {:?}: {:?}", property.key, property.value)?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Write every entity which has not been removed, for opening in a graph tool. See `crate::export` for how hyperedges are laid out
+    pub fn export<W: Write>(&self, format: Format, writer: W) -> Result<(), Error>
+    where
+        Sym: Display,
+        Val: Display,
+    {
+        let mut entities = Vec::new();
+        for entity in self.iter() {
+            let (entity_id, entity) = entity?;
+            if !self.is_removed(&entity_id)? {
+                entities.push((entity_id, entity));
+            }
+        }
+        Export::new(entities).write(format, writer)
+    }
+
+    /// Write only the given entities, leaving out any which have been removed. Members outside of the subgraph are not linked
+    /// ```
+    /// use mindbase_hypergraph::{entity::{directed, vertex}, export::Format, Hypergraph};
+    /// let graph = Hypergraph::<_, String, String>::memory();
+    /// let (_, a) = graph.insert(vertex(vec![])).unwrap();
+    /// let (_, b) = graph.insert(vertex(vec![])).unwrap();
+    /// graph.insert(directed(vec![], [a], [b])).unwrap();
+    ///
+    /// // Everything within one hyperedge of a, along with the hyperedges crossed to get there
+    /// let subgraph = graph.traverse(a).max_depth(1).flat_map(|v| {
+    ///     let v = v.unwrap();
+    ///     std::iter::once(v.entity_id).chain(v.via)
+    /// });
+    /// let mut dot = Vec::new();
+    /// graph.export_subgraph(subgraph, Format::Dot, &mut dot).unwrap();
+    /// assert!(String::from_utf8(dot).unwrap().contains(&format!("\"{}\" -> ", a.full())));
+    /// ```
+    pub fn export_subgraph<I, W>(&self, entities: I, format: Format, writer: W) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = EntityId>,
+        W: Write,
+        Sym: Display,
+        Val: Display,
+    {
+        let mut seen = BTreeSet::new();
+        let mut subgraph = Vec::new();
+        for entity_id in entities {
+            if seen.insert(entity_id) && !self.is_removed(&entity_id)? {
+                subgraph.push((entity_id, self.get(&entity_id)?));
+            }
+        }
+        Export::new(subgraph).write(format, writer)
+    }
+
+    /// Read an entity along with who asserted it, and who asserted each of its properties
+    pub fn get_attributed(&self, entity_id: &EntityId) -> Result<(Entity<Sym, Val>, Attribution<Prov>), Error> {
+        self.adapter.get_attributed(entity_id)
+    }
+
+    /// Read an entity with only those properties attributed to one of the given agents.
+    /// Returns `Error::NotAsserted` if the entity itself was not attributed to one of them.
+    /// This goes by the agent recorded in each provenance, as given when it was written. Nothing is verified,
+    /// so any signature a provenance may carry must be checked by the caller before the agent is relied upon
+    pub fn get_asserted_by(&self, entity_id: &EntityId, agents: &[Prov::Agent]) -> Result<Entity<Sym, Val>, Error> {
+        let is_asserted = |provenance: &Option<Prov>| match provenance {
+            Some(provenance) => agents.contains(provenance.agent()),
+            None => false,
+        };
+
+        let (mut entity, attribution) = self.adapter.get_attributed(entity_id)?;
+        if !is_asserted(&attribution.entity) {
+            return Err(Error::NotAsserted);
+        }
+
+        entity.properties = entity
+            .properties
+            .into_iter()
+            .zip(attribution.properties.iter())
+            .filter(|(_, provenance)| is_asserted(provenance))
+            .map(|(property, _)| property)
+            .collect();
+        Ok(entity)
+    }
+
+    /// Look up the entity with the given id, which may be given in full as by `EntityId::full`, in short as by `EntityId::short`
+    /// and `Display`, or as the start of either, so long as only one entity matches. Removed entities do not match
+    /// ```
+    /// use mindbase_hypergraph::{entity::vertex, Error, Hypergraph};
+    /// let graph = Hypergraph::<_, String, String>::memory();
+    /// let (_, e) = graph.insert(vertex(vec![])).unwrap();
+    /// assert_eq!(graph.resolve_id(&e.to_string()).unwrap(), e);
+    /// assert_eq!(graph.resolve_id(&e.full()[..12]).unwrap(), e);
+    /// assert!(matches!(graph.resolve_id("not an id"), Err(Error::InvalidId)));
+    /// ```
+    pub fn resolve_id(&self, id: &str) -> Result<EntityId, Error> {
+        let mut found = self.find_by_id_prefix(id, AMBIGUOUS_CANDIDATES)?;
+        match found.len() {
+            0 => Err(Error::NotFound),
+            1 => Ok(found.remove(0)),
+            _ => Err(Error::Ambiguous(found)),
+        }
+    }
+
+    /// List up to `limit` entities whose id starts with the given base64, in either its full or its short form
+    pub fn find_by_id_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<EntityId>, Error> {
+        self.adapter.find_by_id_prefix(prefix, limit)
+    }
+
+    /// Find entities by the words in the text of their property values, best ranked first. See `crate::text`
+    pub fn search(&self, query: &TextQuery<Sym>) -> Result<Vec<Hit>, Error> {
+        self.adapter.search(query)
+    }
+
+    /// Look up the entities which have a property with exactly this key and value
+    pub fn find_by_property(&self, key: &Sym, value: &Val) -> Result<Vec<EntityId>, Error> {
+        self.adapter.find_by_property(key, value)
+    }
+
+    /// Look up the entities which have a property with this value, and a key at least `threshold` similar to the given one,
+    /// as judged by `TSymbol::similarity`. Those with the most similar keys come first. With the default similarity,
+    /// any threshold above 0.0 finds just what `find_by_property` does
+    pub fn find_by_similar_property(&self, key: &Sym, value: &Val, threshold: f64) -> Result<Vec<EntityId>, Error> {
+        self.adapter.find_by_similar_property(key, value, threshold)
+    }
+
+    /// Look up the entities which have a property with this key, and a value within the range.
+    /// Results are returned in value order, or the reverse if requested, up to the given limit
+    /// ```
+    /// use mindbase_hypergraph::{adapter::ScanDirection, entity::{vertex, Property}, Hypergraph};
+    /// let graph = Hypergraph::<_, String, u32>::memory();
+    /// let (_, e) = graph.insert(vertex(vec![Property { key: "reading".to_string(), value: 5 }])).unwrap();
+    /// let found = graph.range_by_property(&"reading".to_string(), 1..10, ScanDirection::Forward, None).unwrap();
+    /// assert_eq!(found, vec![e]);
+    /// ```
+    pub fn range_by_property<R>(
+        &self, key: &Sym, range: R, direction: ScanDirection, limit: Option<usize>,
+    ) -> Result<Vec<EntityId>, Error>
+    where
+        R: RangeBounds<Val>,
+    {
+        self.adapter
+            .range_by_property(key, (range.start_bound(), range.end_bound()), direction, limit)
+    }
+
+    /// List the hyperedges which include the given entity, regardless of which side it sits on
+    pub fn get_adjacencies(&self, entity_id: &EntityId) -> Result<Vec<EntityId>, Error> {
+        Ok(self
+            .adapter
+            .get_adjacencies(entity_id)?
+            .into_iter()
+            .map(|a| a.hyperedge)
+            .dedup()
+            .collect())
+    }
+
+    /// List the hyperedges which include the given entity, and have at least one property matching the filter
+    pub fn get_adjacencies_matching<F>(&self, entity_id: &EntityId, filter: F) -> Result<Vec<EntityId>, Error>
+    where
+        F: Fn(&Sym, &Val) -> Result<bool, Error>,
+    {
+        Ok(self
+            .adapter
+            .get_adjacencies_matching(entity_id, filter)?
+            .into_iter()
+            .map(|a| a.hyperedge)
+            .dedup()
+            .collect())
+    }
+
+    /// List the hyperedges which include the given entity, and the role it plays in each.
+    /// Use this to walk directed hyperedges forward (`MemberRole::From`) or backward (`MemberRole::To`)
+    pub fn get_memberships(&self, entity_id: &EntityId) -> Result<Vec<Adjacency>, Error> {
+        self.adapter.get_adjacencies(entity_id)
+    }
+
+    /// Walk outward from the start entity across the hyperedges which include it, visiting each entity once.
+    /// Breadth first in both directions by default. See `Traverse` for the options
+    pub fn traverse(&self, start: EntityId) -> Traverse<'_, Stor, Sym, Val, Prov> {
+        Traverse::new(self, start)
+    }
+
+    /// Remove an entity, and clean up its index entries. The `EntityId` is tombstoned rather than forgotten.
+    /// Hyperedges which include the entity are either removed as well, or flagged as dangling, per `cascade`.
+    /// Returns the ids of every entity which was removed
+    pub fn remove(&self, entity_id: &EntityId, cascade: Cascade) -> Result<Vec<EntityId>, Error> {
+        self.adapter.remove(entity_id, cascade)
+    }
+
+    pub fn is_removed(&self, entity_id: &EntityId) -> Result<bool, Error> {
+        self.adapter.is_removed(entity_id)
+    }
+
+    /// List the members of a hyperedge which were removed with `Cascade::FlagDangling`
+    pub fn get_dangling_members(&self, entity_id: &EntityId) -> Result<Vec<EntityId>, Error> {
+        self.adapter.get_dangling_members(entity_id)
+    }
+
+    /// Append, replace or retract properties of an existing entity, all together as a single new version.
+    /// Every prior version is retained, and may be read with `get_properties`. Returns the new version
+    pub fn amend(&self, entity_id: &EntityId, amendments: Vec<Amendment<Sym, Val>>) -> Result<Version, Error> {
+        self.adapter.amend(entity_id, amendments, None)
+    }
+
+    /// Amend an entity, attributing the appended and replacement properties to whoever is named by the provenance
+    pub fn amend_as(&self, entity_id: &EntityId, amendments: Vec<Amendment<Sym, Val>>, provenance: &Prov) -> Result<Version, Error> {
+        self.adapter.amend(entity_id, amendments, Some(provenance))
+    }
+
+    /// The current version of an entity's properties. Entities are inserted at version 0
+    pub fn get_version(&self, entity_id: &EntityId) -> Result<Version, Error> {
+        self.adapter.get_version(entity_id)
+    }
+
+    /// Get the properties of an entity as they are now, or as they were at the given version
+    pub fn get_properties(&self, entity_id: &EntityId, version: Option<Version>) -> Result<Vec<Property<Sym, Val>>, Error> {
+        self.adapter.get_properties(entity_id, version)
+    }
+
+    /// Intern a symbol, returning its compact id. Property keys are interned automatically on insert
+    pub fn put_symbol(&self, symbol: &Sym) -> Result<SymbolIx, Error> {
+        self.adapter.put_symbol(symbol)
+    }
+
+    /// Look up the id of a symbol, if it has been interned
+    pub fn get_symbol_ix(&self, symbol: &Sym) -> Result<Option<SymbolIx>, Error> {
+        self.adapter.get_symbol_ix(symbol)
+    }
+
+    /// Every interned symbol which is at least `threshold` similar to the given one, and how similar, most similar first
+    pub fn find_similar_symbols(&self, symbol: &Sym, threshold: f64) -> Result<Vec<(SymbolIx, f64)>, Error> {
+        self.adapter.find_similar_symbols(symbol, threshold)
+    }
+
+    pub fn get_symbol(&self, symbol_ix: SymbolIx) -> Result<Sym, Error> {
+        self.adapter.get_symbol(symbol_ix)
+    }
+
+    /// Fetch a value by its hash. Values at least as long as a `ValueHash` are stored once, and shared by reference
+    /// between every property which has that value. Shorter values are stored inline, and may not be fetched this way
+    pub fn get_value(&self, hash: &ValueHash) -> Result<Val, Error> {
+        self.adapter.get_value(hash)
+    }
+
+    /// Subscribe to inserts, updates and removals matching the filter, from now on
+    pub fn subscribe(&self, filter: Filter<Sym>) -> Result<Subscription<Sym>, Error> {
+        self.adapter.subscribe(filter, None)
+    }
+
+    /// Subscribe to changes matching the filter, first catching up on those made after the given change,
+    /// such as the last one seen before a restart. Refused with `Error::Trimmed` if some of those have been trimmed
+    pub fn subscribe_after(&self, filter: Filter<Sym>, after: ChangeId) -> Result<Subscription<Sym>, Error> {
+        self.adapter.subscribe(filter, Some(after))
+    }
+
+    /// Remove every change before the cut-off from the change log, such as those older than `ChangeId::at` some time,
+    /// so that it does not grow without bound. Returns how many changes were removed
+    pub fn trim_changes(&self, before: ChangeId) -> Result<usize, Error> {
+        self.adapter.trim_changes(before)
+    }
+}
+
+impl<Stor, Sym, Val, Prov> traits::GraphInterface<Sym, Val> for Hypergraph<Stor, Sym, Val, Prov>
+where
+    Stor: StorageAdapter<Sym, Val, Prov>,
+    Sym: traits::TSymbol,
+    Val: traits::TValue,
+    Prov: traits::TProvenance,
+{
+    fn insert(&self, entity: Entity<Sym, Val>) -> Result<EntityId, Error> {
+        Ok(Hypergraph::insert(self, entity)?.1)
+    }
+
+    fn get(&self, entity_id: &EntityId) -> Result<Entity<Sym, Val>, Error> {
+        Hypergraph::get(self, entity_id)
+    }
+
+    fn get_adjacencies(&self, entity_id: &EntityId) -> Result<Vec<EntityId>, Error> {
+        Hypergraph::get_adjacencies(self, entity_id)
+    }
+
+    fn get_adjacencies_matching<F>(&self, entity_id: &EntityId, filter: F) -> Result<Vec<EntityId>, Error>
+    where
+        F: Fn(&Sym, &Val) -> Result<bool, Error>,
+    {
+        Hypergraph::get_adjacencies_matching(self, entity_id, filter)
+    }
+}
+
+/// A lazy scan over entities in `EntityId` order, which is also the order in which they were created.
+/// Entities are read a page at a time, so a large database may be walked without loading it whole.
+/// The scan may be resumed later, even by a fresh iterator, using the `cursor` of this one
+pub struct Entities<'a, Stor, Sym, Val, Prov>
+where
+    Sym: traits::TSymbol,
+    Val: traits::TValue,
+{
+    adapter: &'a Stor,
+    kind: EntityKind,
+    start: Bound<EntityId>,
+    end: Bound<EntityId>,
+    page: std::vec::IntoIter<(EntityId, Entity<Sym, Val>)>,
+    page_size: usize,
+    cursor: Option<EntityId>,
+    exhausted: bool,
+    #[doc(hidden)]
+    _prov: PhantomData<Prov>,
 }
 
-// #[cfg(test)]
-// mod test {
-//     #[test]
-//     fn insert() -> Result<(), std::io::Error> {
-//         use crate::{entity, Hypergraph};
-//         use adapter::BTreeAdapter;
-//         let graph = Hypergraph::<BTreeAdapter, String>::memory();
-//         use crate::traits::GraphInterface;
-
-//         let a = graph.insert(entity::vertex(
-//             "This is the weight associated with an entity to be created (of type vertex)",
-//         ))?;
-//         let b = graph.insert(entity::vertex(
-//             "This is a different weight associated with a DIFFERENT entity to be created (of type vertex)",
-//         ))?;
-//         let c = graph.insert(entity::vertex("This weight is shared by multiple (vertex) entities"))?;
-//         let d = graph.insert(entity::vertex("This weight is shared by multiple (vertex) entities"))?;
-
-//         let x = graph.insert(entity::undirected(
-//             "This weight is associated with an entity of type undirected (which is a hyperedege)",
-//             [a, b, c, d],
-//         ))?;
-//         let y = graph.insert(entity::directed(
-//             "This weight is associated with an entity of type directed (which is a hyperedege)",
-//             [a, b], // This is the "From" side of the hyperedge
-//             [c, d], // This is the "To" side of the hyperedge
-//         ))?;
-
-//         let _z = graph.insert(entity::directed(
-//             "Crucially, hyperedges can also include other hyperedges",
-//             [x, y],       // From some hyperedge entities
-//             [a, b, c, d], // To some other entities
-//         ))?;
-
-//         // let mut out: Vec<u8> = Vec::new();
-//         // graph.dump_entities(&mut out).unwrap();
-//         // println!("{}", String::from_utf8(out).unwrap());
-//         Ok(())
-//     }
-// }
+impl<'a, Stor, Sym, Val, Prov> Entities<'a, Stor, Sym, Val, Prov>
+where
+    Stor: StorageAdapter<Sym, Val, Prov>,
+    Sym: traits::TSymbol,
+    Val: traits::TValue,
+    Prov: traits::TProvenance,
+{
+    const DEFAULT_PAGE_SIZE: usize = 100;
+
+    fn new(adapter: &'a Stor, kind: EntityKind, start: Bound<EntityId>, end: Bound<EntityId>) -> Self {
+        Self {
+            adapter,
+            kind,
+            start,
+            end,
+            page: Vec::new().into_iter(),
+            page_size: Self::DEFAULT_PAGE_SIZE,
+            cursor: None,
+            exhausted: false,
+            _prov: PhantomData,
+        }
+    }
+
+    /// Skip every entity up to and including the one at the cursor
+    pub fn after(mut self, cursor: EntityId) -> Self {
+        let later = match self.start {
+            Bound::Included(start) => cursor >= start,
+            Bound::Excluded(start) => cursor > start,
+            Bound::Unbounded => true,
+        };
+        if later {
+            self.start = Bound::Excluded(cursor);
+        }
+        self
+    }
+
+    /// How many entities to read from storage at a time
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// The id of the last entity returned, from which the scan may be resumed with `after`
+    pub fn cursor(&self) -> Option<EntityId> {
+        self.cursor
+    }
+
+    fn is_past_end(&self) -> bool {
+        match (self.start, self.end) {
+            (Bound::Included(start), Bound::Included(end)) => start > end,
+            (Bound::Included(start), Bound::Excluded(end))
+            | (Bound::Excluded(start), Bound::Included(end))
+            | (Bound::Excluded(start), Bound::Excluded(end)) => start >= end,
+            _ => false,
+        }
+    }
+}
+
+impl<'a, Stor, Sym, Val, Prov> Iterator for Entities<'a, Stor, Sym, Val, Prov>
+where
+    Stor: StorageAdapter<Sym, Val, Prov>,
+    Sym: traits::TSymbol,
+    Val: traits::TValue,
+    Prov: traits::TProvenance,
+{
+    type Item = Result<(EntityId, Entity<Sym, Val>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((entity_id, entity)) = self.page.next() {
+                self.cursor = Some(entity_id);
+                return Some(Ok((entity_id, entity)));
+            }
+            if self.exhausted || self.is_past_end() {
+                return None;
+            }
+
+            match self.adapter.scan((self.start, self.end), self.kind, self.page_size) {
+                Ok(page) => {
+                    self.exhausted = page.len() < self.page_size;
+                    if let Some((last, _)) = page.last() {
+                        self.start = Bound::Excluded(*last);
+                    }
+                    self.page = page.into_iter();
+                },
+                Err(e) => {
+                    self.exhausted = true;
+                    return Some(Err(e));
+                },
+            }
+        }
+    }
+}
+
+/// A set of entities to be inserted into the hypergraph all at once, or not at all
+pub struct Batch<'a, Stor, Sym, Val, Prov>
+where
+    Sym: traits::TSymbol,
+    Val: traits::TValue,
+{
+    graph: &'a Hypergraph<Stor, Sym, Val, Prov>,
+    entities: Vec<(EntityId, Entity<Sym, Val>, Option<Prov>)>,
+}
+
+impl<'a, Stor, Sym, Val, Prov> Batch<'a, Stor, Sym, Val, Prov>
+where
+    Stor: StorageAdapter<Sym, Val, Prov>,
+    Sym: traits::TSymbol,
+    Val: traits::TValue,
+    Prov: traits::TProvenance,
+{
+    /// Add an entity to the batch, returning the id it will have once committed
+    pub fn insert(&mut self, entity: Entity<Sym, Val>) -> EntityId {
+        let entity_id = EntityId::generate();
+        self.entities.push((entity_id, entity, None));
+        entity_id
+    }
+
+    /// Add an entity to the batch, attributed to whoever is named by the provenance
+    pub fn insert_as(&mut self, entity: Entity<Sym, Val>, provenance: Prov) -> EntityId {
+        let entity_id = EntityId::generate();
+        self.entities.push((entity_id, entity, Some(provenance)));
+        entity_id
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Write every entity in the batch in a single transaction
+    pub fn commit(self) -> Result<Vec<(EntityIx, EntityId)>, Error> {
+        let entity_ids: Vec<EntityId> = self.entities.iter().map(|(entity_id, ..)| *entity_id).collect();
+        let entity_ixs = self.graph.adapter.insert_batch(self.entities)?;
+
+        Ok(entity_ixs.into_iter().zip(entity_ids).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        adapter::{memory::MemoryAdapter, sled::SledAdapter},
+        entity::{self, Property},
+        Hypergraph,
+    };
+
+    fn prop(value: &str) -> Vec<Property<String, String>> {
+        vec![Property {
+            key: "weight".to_string(),
+            value: value.to_string(),
+        }]
+    }
+
+    #[test]
+    fn insert() -> Result<(), std::io::Error> {
+        let graph = Hypergraph::<_, String, String>::memory();
+
+        let (_, a) = graph.insert(entity::vertex(prop(
+            "This is the weight associated with an entity to be created (of type vertex)",
+        )))?;
+        let (_, b) = graph.insert(entity::vertex(prop(
+            "This is a different weight associated with a DIFFERENT entity to be created (of type vertex)",
+        )))?;
+        let (_, c) = graph.insert(entity::vertex(prop("This weight is shared by multiple (vertex) entities")))?;
+        let (_, d) = graph.insert(entity::vertex(prop("This weight is shared by multiple (vertex) entities")))?;
+
+        let (_, x) = graph.insert(entity::undirected(
+            prop("This weight is associated with an entity of type undirected (which is a hyperedege)"),
+            [a, b, c, d],
+        ))?;
+        let (_, y) = graph.insert(entity::directed(
+            prop("This weight is associated with an entity of type directed (which is a hyperedege)"),
+            [a, b], // This is the "From" side of the hyperedge
+            [c, d], // This is the "To" side of the hyperedge
+        ))?;
+
+        let (_, z) = graph.insert(entity::directed(
+            prop("Crucially, hyperedges can also include other hyperedges"),
+            [x, y],       // From some hyperedge entities
+            [a, b, c, d], // To some other entities
+        ))?;
+
+        assert_eq!(graph.get_adjacencies(&x)?, vec![z]);
+        assert_eq!(graph.get_adjacencies(&d)?.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn memory_snapshot() -> Result<(), std::io::Error> {
+        let tmpdir = tempfile::tempdir()?;
+
+        let memory = MemoryAdapter::<String, String>::new();
+        let graph = Hypergraph::new(memory);
+        let (_, a) = graph.insert(entity::vertex(prop("a")))?;
+        let (_, b) = graph.insert(entity::vertex(prop("b")))?;
+        let (_, x) = graph.insert(entity::directed(prop("x"), [a], [b]))?;
+
+        let sled = SledAdapter::open(tmpdir.path())?;
+        graph.adapter.save_to_sled(&sled)?;
+
+        let restored = Hypergraph::new(MemoryAdapter::from_sled(&sled)?);
+        assert_eq!(restored.get_adjacencies(&a)?, vec![x]);
+        assert_eq!(restored.get_adjacencies(&b)?, vec![x]);
+
+        let sled = Hypergraph::new(sled);
+        assert_eq!(sled.get_adjacencies(&b)?, vec![x]);
+
+        Ok(())
+    }
+}
 
 // // /// Convenience function, equivalent to
 // // /// ```
@@ -1,5 +1,12 @@
+use std::{convert::TryInto, ops::Bound};
 use typenum::Unsigned;
 
+use crate::{
+    entity::{Adjacency, EntityIx, MemberRole, SymbolIx, Version, SHORT_BYTES},
+    traits::TValue,
+    EntityId, Error,
+};
+
 pub fn merge_byte_list<U: Unsigned>(
     _key: &[u8],               // the key being merged
     last_bytes: Option<&[u8]>, // the previous value, if one existed
@@ -17,3 +24,168 @@ pub fn merge_byte_list<U: Unsigned>(
         None => op_bytes.to_vec(),
     })
 }
+
+/// The inverse of `merge_byte_list`. Returns None if the list is left empty
+pub(crate) fn remove_from_byte_list<U: Unsigned>(list: &[u8], entity: &[u8]) -> Option<Vec<u8>> {
+    let out: Vec<u8> = list
+        .chunks_exact(U::USIZE)
+        .filter(|chunk| *chunk != entity)
+        .flatten()
+        .copied()
+        .collect();
+
+    if out.is_empty() {
+        None
+    } else {
+        Some(out)
+    }
+}
+
+/// `remove_from_byte_list` in the shape of a merge, for use within a `kv::Transaction`
+pub(crate) fn unmerge_byte_list<U: Unsigned>(_key: &[u8], last_bytes: Option<&[u8]>, op_bytes: &[u8]) -> Option<Vec<u8>> {
+    last_bytes.and_then(|list| remove_from_byte_list::<U>(list, op_bytes))
+}
+
+/// Adjacency list entries are the 16 byte hyperedge id, a single role byte, and the 8 byte degree of membership
+pub(crate) type AdjacencyLen = typenum::U25;
+
+pub(crate) fn adjacency_bytes(adjacency: &Adjacency) -> [u8; 25] {
+    let mut out = [0u8; 25];
+    out[0..16].copy_from_slice(&adjacency.hyperedge.0);
+    out[16] = match adjacency.role {
+        MemberRole::Undirected => 0,
+        MemberRole::From => 1,
+        MemberRole::To => 2,
+    };
+    out[17..].copy_from_slice(&adjacency.degree.to_be_bytes());
+    out
+}
+
+pub(crate) fn adjacencies_from_bytes(bytes: &[u8]) -> Result<Vec<Adjacency>, Error> {
+    let chunks = bytes.chunks_exact(AdjacencyLen::USIZE);
+    if !chunks.remainder().is_empty() {
+        return Err(Error::InvalidSlice);
+    }
+
+    chunks
+        .map(|chunk| {
+            let role = match chunk[16] {
+                0 => MemberRole::Undirected,
+                1 => MemberRole::From,
+                2 => MemberRole::To,
+                _ => return Err(Error::InvalidSlice),
+            };
+            Ok(Adjacency {
+                hyperedge: EntityId::from_slice(&chunk[0..16])?,
+                role,
+                degree: f64::from_be_bytes(chunk[17..].try_into().map_err(|_| Error::InvalidSlice)?),
+            })
+        })
+        .collect()
+}
+
+/// Property index keys are the `SymbolIx`, followed by the serialized value.
+/// The `SymbolIx` is fixed width, so one symbol may never be mistaken for the prefix of another
+pub(crate) fn property_value_key(symbol_ix: SymbolIx, value: &[u8]) -> Vec<u8> {
+    let mut key = property_symbol_prefix(symbol_ix);
+    key.extend_from_slice(value);
+    key
+}
+
+pub(crate) fn property_symbol_prefix(symbol_ix: SymbolIx) -> Vec<u8> {
+    symbol_ix.to_be_bytes().to_vec()
+}
+
+/// Property history keys are the `EntityId` followed by the `Version`, such that all versions of an entity
+/// are contiguous, and in order
+pub(crate) fn property_history_key(entity_id: &EntityId, version: Version) -> [u8; 20] {
+    let mut key = [0u8; 20];
+    key[..16].copy_from_slice(&entity_id.0);
+    key[16..].copy_from_slice(&version.to_be_bytes());
+    key
+}
+
+/// Whether the key starts with bytes no greater than `greatest`, as when scanning the keys which start within a span
+pub(crate) fn within(key: &[u8], greatest: &[u8]) -> bool {
+    key[..key.len().min(greatest.len())] <= *greatest
+}
+
+/// The bytes of the id which its short form spells, followed by the id itself
+pub(crate) fn short_id_key(entity_id: &EntityId) -> Vec<u8> {
+    let mut key = entity_id.0[16 - SHORT_BYTES..].to_vec();
+    key.extend_from_slice(&entity_id.0);
+    key
+}
+
+/// Range index keys are the property symbol prefix, then the value sort key, then the `EntityIx`.
+/// The trailing `EntityIx` keeps entities which share a property value distinct
+pub(crate) fn property_range_key(symbol_ix: SymbolIx, sort_key: &[u8], entity_ix: EntityIx) -> Vec<u8> {
+    let mut key = property_symbol_prefix(symbol_ix);
+    key.extend_from_slice(sort_key);
+    key.extend_from_slice(&entity_ix.to_be_bytes());
+    key
+}
+
+pub(crate) fn sort_key_bound<Val: TValue>(bound: Bound<&Val>) -> Result<Bound<Vec<u8>>, Error> {
+    Ok(match bound {
+        Bound::Included(v) => Bound::Included(v.sort_key().ok_or(Error::Unordered)?),
+        Bound::Excluded(v) => Bound::Excluded(v.sort_key().ok_or(Error::Unordered)?),
+        Bound::Unbounded => Bound::Unbounded,
+    })
+}
+
+pub(crate) type KeyRange = (Bound<Vec<u8>>, Bound<Vec<u8>>);
+
+/// Translate bounds on value sort keys into bounds on range index keys for the given symbol.
+/// Returns None if the range is empty
+pub(crate) fn property_range_bounds(
+    symbol_ix: SymbolIx, lo: Bound<Vec<u8>>, hi: Bound<Vec<u8>>,
+) -> Option<KeyRange> {
+    let prefix = property_symbol_prefix(symbol_ix);
+    let with = |sort_key: &[u8], ix_fill: u8| {
+        let mut key = prefix.clone();
+        key.extend_from_slice(sort_key);
+        key.extend_from_slice(&[ix_fill; 8]);
+        key
+    };
+
+    let start = match lo {
+        Bound::Included(k) => Bound::Included(with(&k, 0x00)),
+        Bound::Excluded(k) => Bound::Excluded(with(&k, 0xFF)),
+        Bound::Unbounded => Bound::Included(prefix.clone()),
+    };
+    let end = match hi {
+        Bound::Included(k) => Bound::Included(with(&k, 0xFF)),
+        Bound::Excluded(k) => Bound::Excluded(with(&k, 0x00)),
+        Bound::Unbounded => match prefix_successor(&prefix) {
+            Some(successor) => Bound::Excluded(successor),
+            None => Bound::Unbounded,
+        },
+    };
+
+    let empty = match (&start, &end) {
+        (Bound::Included(s), Bound::Included(e)) => s > e,
+        (Bound::Included(s), Bound::Excluded(e)) | (Bound::Excluded(s), Bound::Included(e)) | (Bound::Excluded(s), Bound::Excluded(e)) => {
+            s >= e
+        },
+        _ => false,
+    };
+
+    if empty {
+        None
+    } else {
+        Some((start, end))
+    }
+}
+
+/// The smallest key which is greater than every key starting with the given prefix
+fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut out = prefix.to_vec();
+    while let Some(last) = out.pop() {
+        if last < 0xFF {
+            out.push(last + 1);
+            return Some(out);
+        }
+    }
+    None
+}
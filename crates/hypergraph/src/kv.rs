@@ -0,0 +1,151 @@
+//! A sled flavored key-value abstraction, over which the hypergraph's storage is written once. It extends that of toboggan-kv
+//! with what the hypergraph needs: each backend provides named trees of ordered byte keys, read-modify-write merges, ordered
+//! scans, and transactions which apply a series of writes across several trees atomically. The backends are sled, BTreeMaps,
+//! and any toboggan-kv store, by way of `toboggan::TobogganStore`
+//! ```
+//! use mindbase_hypergraph::kv::{memory::MemoryStore, Store, Transaction, Tree};
+//! let store = MemoryStore::default();
+//! let beasts = store.open_tree("beasts").unwrap();
+//! let sounds = store.open_tree("sounds").unwrap();
+//!
+//! let mut tx = Transaction::new();
+//! tx.insert(&beasts, "cat", "felis");
+//! tx.insert(&sounds, "meow", "cat");
+//! store.apply(tx).unwrap();
+//!
+//! assert_eq!(beasts.get("cat").unwrap().as_deref(), Some(&b"felis"[..]));
+//! assert_eq!(sounds.scan_prefix("me").count(), 1);
+//! ```
+
+use std::{fmt::Debug, ops::RangeBounds};
+
+use crate::Error;
+
+pub mod memory;
+pub mod sled;
+pub mod toboggan;
+
+/// Combine the operand with the prior value of a key, if any, to yield its next value. Returning None removes the key
+pub type MergeFn = fn(&[u8], Option<&[u8]>, &[u8]) -> Option<Vec<u8>>;
+/// A key, and its value
+pub type Pair<V> = (V, V);
+
+pub trait Store: Clone {
+    type Tree: Tree;
+
+    /// Open the named tree, creating it if need be
+    fn open_tree(&self, name: &str) -> Result<Self::Tree, Error>;
+    /// The names of every tree in the store, in order
+    fn tree_names(&self) -> Result<Vec<String>, Error>;
+    /// Remove the named tree and everything in it. Returns false if there was no such tree
+    fn drop_tree(&self, name: &str) -> Result<bool, Error>;
+    /// A unique id, greater than any this store has generated before
+    fn generate_id(&self) -> Result<u64, Error>;
+    /// Advanced by every transaction the store applies, such that a reader may tell whether any was applied meanwhile.
+    /// Writes made directly to a tree do not advance it
+    fn generation(&self) -> Result<u64, Error>;
+    /// Apply every write in the transaction, in order, or none of them. Merges see the writes which precede them.
+    /// Expectations are checked against the store as it was before any of the writes, and if any is not met, nothing is
+    /// written and `Error::Conflict` is returned
+    fn apply(&self, transaction: Transaction<'_, Self::Tree>) -> Result<(), Error>;
+    fn flush(&self) -> Result<(), Error>;
+}
+
+pub trait Tree {
+    type Value: AsRef<[u8]> + std::ops::Deref<Target = [u8]> + Debug;
+    /// Pairs of keys and values, in key order
+    type Iter: DoubleEndedIterator<Item = Result<Pair<Self::Value>, Error>>;
+
+    fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Self::Value>, Error>;
+    fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> Result<(), Error>;
+    fn remove<K: AsRef<[u8]>>(&self, key: K) -> Result<(), Error>;
+    /// Atomically replace the value of the key with the result of the merge function. Returns the prior value
+    fn merge<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, operand: V, merge: MergeFn) -> Result<Option<Self::Value>, Error>;
+    fn range<K: AsRef<[u8]>, R: RangeBounds<K>>(&self, range: R) -> Self::Iter;
+    fn scan_prefix<P: AsRef<[u8]>>(&self, prefix: P) -> Self::Iter;
+    fn iter(&self) -> Self::Iter;
+
+    fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> Result<bool, Error> {
+        Ok(self.get(key)?.is_some())
+    }
+    /// The pair with the greatest key, if any
+    fn last(&self) -> Result<Option<Pair<Self::Value>>, Error> {
+        self.iter().next_back().transpose()
+    }
+}
+
+/// A write to be applied as part of a `Transaction`
+pub enum Op {
+    Insert(Vec<u8>, Vec<u8>),
+    Remove(Vec<u8>),
+    Merge(Vec<u8>, Vec<u8>, MergeFn),
+    /// Not a write, but a condition of the others: that the key holds this value, or is absent if None
+    Expect(Vec<u8>, Option<Vec<u8>>),
+}
+
+/// A series of writes across one or more trees of the same store, to be applied atomically by `Store::apply`.
+/// Reads are done ahead of time, so that a backend may retry the writes as many times as it needs to.
+/// Anything read which the writes depend on may be checked with `expect`, so that they are not applied over a concurrent change
+pub struct Transaction<'a, T> {
+    pub(crate) ops: Vec<(&'a T, Op)>,
+}
+
+impl<'a, T: Tree> Transaction<'a, T> {
+    pub fn new() -> Self {
+        Transaction { ops: Vec::new() }
+    }
+    pub fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&mut self, tree: &'a T, key: K, value: V) {
+        self.ops
+            .push((tree, Op::Insert(key.as_ref().to_vec(), value.as_ref().to_vec())));
+    }
+    pub fn remove<K: AsRef<[u8]>>(&mut self, tree: &'a T, key: K) {
+        self.ops.push((tree, Op::Remove(key.as_ref().to_vec())));
+    }
+    pub fn merge<K: AsRef<[u8]>, V: AsRef<[u8]>>(&mut self, tree: &'a T, key: K, operand: V, merge: MergeFn) {
+        self.ops
+            .push((tree, Op::Merge(key.as_ref().to_vec(), operand.as_ref().to_vec(), merge)));
+    }
+    /// Apply the transaction only if the key still holds the value that was read, or is still absent if None
+    pub fn expect<K: AsRef<[u8]>>(&mut self, tree: &'a T, key: K, value: Option<&[u8]>) {
+        self.ops
+            .push((tree, Op::Expect(key.as_ref().to_vec(), value.map(<[u8]>::to_vec))));
+    }
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+    /// The distinct trees written to, and for each op, the position of its tree among them
+    pub(crate) fn trees(&self) -> (Vec<&'a T>, Vec<usize>) {
+        let mut trees: Vec<&'a T> = Vec::new();
+        let positions = self
+            .ops
+            .iter()
+            .map(|(tree, _)| match trees.iter().position(|t| std::ptr::eq(*t, *tree)) {
+                Some(position) => position,
+                None => {
+                    trees.push(tree);
+                    trees.len() - 1
+                },
+            })
+            .collect();
+        (trees, positions)
+    }
+}
+
+impl<'a, T: Tree> Default for Transaction<'a, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keep whichever value was written first
+pub fn write_once(
+    _key: &[u8],               // the key being merged
+    last_bytes: Option<&[u8]>, // the previous value, if one existed
+    op_bytes: &[u8],           /* the new bytes being merged in */
+) -> Option<Vec<u8>> {
+    // Returning None would delete the key, so keep whatever was there first
+    match last_bytes {
+        Some(last_bytes) => Some(last_bytes.to_vec()),
+        None => Some(op_bytes.to_vec()),
+    }
+}
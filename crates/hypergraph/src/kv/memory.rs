@@ -0,0 +1,245 @@
+use std::{
+    collections::BTreeMap,
+    ops::{Bound, RangeBounds},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use super::{MergeFn, Op, Pair, Store, Transaction, Tree};
+use crate::Error;
+
+type Trees = BTreeMap<String, BTreeMap<Vec<u8>, Vec<u8>>>;
+
+/// Non-persistent store of BTreeMaps, for tests and for building fixtures.
+/// Clones share the same trees
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    trees: Arc<RwLock<Trees>>,
+    next_id: Arc<AtomicU64>,
+    generation: Arc<AtomicU64>,
+}
+
+/// A handle to one of the trees of a `MemoryStore`
+#[derive(Clone)]
+pub struct MemoryTree {
+    name: String,
+    trees: Arc<RwLock<Trees>>,
+}
+
+impl Store for MemoryStore {
+    type Tree = MemoryTree;
+
+    fn open_tree(&self, name: &str) -> Result<MemoryTree, Error> {
+        self.trees.write().unwrap().entry(name.to_string()).or_default();
+        Ok(MemoryTree {
+            name: name.to_string(),
+            trees: self.trees.clone(),
+        })
+    }
+
+    fn tree_names(&self) -> Result<Vec<String>, Error> {
+        Ok(self.trees.read().unwrap().keys().cloned().collect())
+    }
+
+    fn drop_tree(&self, name: &str) -> Result<bool, Error> {
+        Ok(self.trees.write().unwrap().remove(name).is_some())
+    }
+
+    fn generate_id(&self) -> Result<u64, Error> {
+        Ok(self.next_id.fetch_add(1, Ordering::SeqCst))
+    }
+
+    fn generation(&self) -> Result<u64, Error> {
+        Ok(self.generation.load(Ordering::SeqCst))
+    }
+
+    /// Every write is applied under the same lock, so readers see all of them or none
+    fn apply(&self, transaction: Transaction<'_, MemoryTree>) -> Result<(), Error> {
+        let mut trees = self.trees.write().unwrap();
+        for (tree, op) in transaction.ops.iter() {
+            if let Op::Expect(key, expected) = op {
+                if trees.get(&tree.name).and_then(|tree| tree.get(key)) != expected.as_ref() {
+                    return Err(Error::Conflict);
+                }
+            }
+        }
+        for (tree, op) in transaction.ops {
+            let tree = trees.entry(tree.name.clone()).or_default();
+            match op {
+                Op::Insert(key, value) => {
+                    tree.insert(key, value);
+                },
+                Op::Remove(key) => {
+                    tree.remove(&key);
+                },
+                Op::Merge(key, operand, merge) => {
+                    merge_into(tree, key, &operand, merge);
+                },
+                Op::Expect(..) => {},
+            }
+        }
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+fn merge_into(tree: &mut BTreeMap<Vec<u8>, Vec<u8>>, key: Vec<u8>, operand: &[u8], merge: MergeFn) -> Option<Vec<u8>> {
+    let merged = merge(&key, tree.get(&key).map(|v| &v[..]), operand);
+    match merged {
+        Some(merged) => tree.insert(key, merged),
+        None => tree.remove(&key),
+    }
+}
+
+impl MemoryTree {
+    fn read<T, F: FnOnce(&BTreeMap<Vec<u8>, Vec<u8>>) -> T>(&self, f: F) -> T {
+        let trees = self.trees.read().unwrap();
+        // A dropped tree reads as empty until it is written to again
+        match trees.get(&self.name) {
+            Some(tree) => f(tree),
+            None => f(&BTreeMap::new()),
+        }
+    }
+    fn write<T, F: FnOnce(&mut BTreeMap<Vec<u8>, Vec<u8>>) -> T>(&self, f: F) -> T {
+        let mut trees = self.trees.write().unwrap();
+        f(trees.entry(self.name.clone()).or_default())
+    }
+}
+
+impl Tree for MemoryTree {
+    type Value = Vec<u8>;
+    type Iter = Iter;
+
+    fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.read(|tree| tree.get(key.as_ref()).cloned()))
+    }
+
+    fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> Result<(), Error> {
+        self.write(|tree| tree.insert(key.as_ref().to_vec(), value.as_ref().to_vec()));
+        Ok(())
+    }
+
+    fn remove<K: AsRef<[u8]>>(&self, key: K) -> Result<(), Error> {
+        self.write(|tree| tree.remove(key.as_ref()));
+        Ok(())
+    }
+
+    fn merge<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, operand: V, merge: MergeFn) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.write(|tree| merge_into(tree, key.as_ref().to_vec(), operand.as_ref(), merge)))
+    }
+
+    fn range<K: AsRef<[u8]>, R: RangeBounds<K>>(&self, range: R) -> Iter {
+        Iter {
+            tree: self.clone(),
+            start: owned_bound(range.start_bound()),
+            end: owned_bound(range.end_bound()),
+        }
+    }
+
+    fn scan_prefix<P: AsRef<[u8]>>(&self, prefix: P) -> Iter {
+        let prefix = prefix.as_ref();
+        Iter {
+            tree: self.clone(),
+            start: Bound::Included(prefix.to_vec()),
+            end: prefix_end(prefix),
+        }
+    }
+
+    fn iter(&self) -> Iter {
+        self.range::<&[u8], _>(..)
+    }
+
+    fn last(&self) -> Result<Option<(Vec<u8>, Vec<u8>)>, Error> {
+        Ok(self.read(|tree| tree.iter().next_back().map(|(k, v)| (k.clone(), v.clone()))))
+    }
+}
+
+/// Iterates over a range of a `MemoryTree`, reading one pair at a time under a lock of its own, and narrowing the range past it.
+/// As with `sled::Iter`, nothing is copied up front, and pairs written part way through are seen if they are still ahead
+pub struct Iter {
+    tree: MemoryTree,
+    start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+}
+
+impl Iter {
+    fn step<F>(&mut self, pick: F) -> Option<Result<Pair<Vec<u8>>, Error>>
+    where
+        F: for<'a> FnOnce(std::collections::btree_map::Range<'a, Vec<u8>, Vec<u8>>) -> Option<(&'a Vec<u8>, &'a Vec<u8>)>,
+    {
+        if is_empty_range(&self.start, &self.end) {
+            return None;
+        }
+
+        let range = (bound_bytes(self.start.as_ref()), bound_bytes(self.end.as_ref()));
+        let (key, value) = self
+            .tree
+            .read(|tree| pick(tree.range::<[u8], _>(range)).map(|(k, v)| (k.clone(), v.clone())))?;
+        Some(Ok((key, value)))
+    }
+}
+
+impl Iterator for Iter {
+    type Item = Result<Pair<Vec<u8>>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.step(|mut range| range.next());
+        if let Some(Ok((key, _))) = &item {
+            self.start = Bound::Excluded(key.clone());
+        }
+        item
+    }
+}
+
+impl DoubleEndedIterator for Iter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let item = self.step(|mut range| range.next_back());
+        if let Some(Ok((key, _))) = &item {
+            self.end = Bound::Excluded(key.clone());
+        }
+        item
+    }
+}
+
+pub(crate) fn owned_bound<K: AsRef<[u8]>>(bound: Bound<&K>) -> Bound<Vec<u8>> {
+    match bound {
+        Bound::Included(k) => Bound::Included(k.as_ref().to_vec()),
+        Bound::Excluded(k) => Bound::Excluded(k.as_ref().to_vec()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// The least key greater than every key with this prefix, if there is one
+pub(crate) fn prefix_end(prefix: &[u8]) -> Bound<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Bound::Excluded(end);
+        }
+    }
+    Bound::Unbounded
+}
+
+/// BTreeMap::range panics on backwards ranges, but these are merely empty
+pub(crate) fn is_empty_range(start: &Bound<Vec<u8>>, end: &Bound<Vec<u8>>) -> bool {
+    match (start, end) {
+        (Bound::Included(s), Bound::Included(e)) => s > e,
+        (Bound::Included(s), Bound::Excluded(e)) | (Bound::Excluded(s), Bound::Included(e)) | (Bound::Excluded(s), Bound::Excluded(e)) => s >= e,
+        _ => false,
+    }
+}
+
+pub(crate) fn bound_bytes<K: AsRef<[u8]>>(bound: Bound<&K>) -> Bound<&[u8]> {
+    match bound {
+        Bound::Included(k) => Bound::Included(k.as_ref()),
+        Bound::Excluded(k) => Bound::Excluded(k.as_ref()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
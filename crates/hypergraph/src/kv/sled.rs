@@ -0,0 +1,161 @@
+use std::{convert::TryInto, ops::RangeBounds};
+
+use sled::{
+    transaction::{ConflictableTransactionError, ConflictableTransactionResult},
+    IVec, Transactional,
+};
+
+use super::{MergeFn, Op, Store, Transaction, Tree};
+use crate::Error;
+
+/// The generation of the store, under `GENERATION_KEY`. Written by every transaction, along with its writes
+const GENERATION_TREE: &str = "kv::generation";
+const GENERATION_KEY: &[u8] = b"generation";
+
+fn decode_generation(bytes: Option<IVec>) -> Result<u64, Error> {
+    match bytes {
+        Some(bytes) => Ok(u64::from_be_bytes(bytes[..].try_into().map_err(|_| Error::InvalidSlice)?)),
+        None => Ok(0),
+    }
+}
+
+impl Store for sled::Db {
+    type Tree = sled::Tree;
+
+    fn open_tree(&self, name: &str) -> Result<sled::Tree, Error> {
+        Ok(sled::Db::open_tree(self, name)?)
+    }
+
+    fn tree_names(&self) -> Result<Vec<String>, Error> {
+        let mut names = sled::Db::tree_names(self)
+            .iter()
+            .map(|name| String::from_utf8(name.to_vec()).map_err(|_| Error::InvalidSlice))
+            .collect::<Result<Vec<String>, Error>>()?;
+        names.sort();
+        Ok(names)
+    }
+
+    fn drop_tree(&self, name: &str) -> Result<bool, Error> {
+        Ok(sled::Db::drop_tree(self, name)?)
+    }
+
+    fn generate_id(&self) -> Result<u64, Error> {
+        Ok(sled::Db::generate_id(self)?)
+    }
+
+    fn generation(&self) -> Result<u64, Error> {
+        decode_generation(sled::Db::open_tree(self, GENERATION_TREE)?.get(GENERATION_KEY)?)
+    }
+
+    fn apply(&self, transaction: Transaction<'_, sled::Tree>) -> Result<(), Error> {
+        if transaction.is_empty() {
+            return Ok(());
+        }
+        let (mut trees, positions) = transaction.trees();
+        let generation = sled::Db::open_tree(self, GENERATION_TREE)?;
+        trees.push(&generation);
+
+        trees.as_slice().transaction(|tx_trees| -> ConflictableTransactionResult<(), Error> {
+            // Before any writes, as reads within the transaction would see them
+            for ((_, op), position) in transaction.ops.iter().zip(positions.iter()) {
+                if let Op::Expect(key, expected) = op {
+                    if tx_trees[*position].get(&key[..])?.as_deref() != expected.as_deref() {
+                        return Err(ConflictableTransactionError::Abort(Error::Conflict));
+                    }
+                }
+            }
+            for ((_, op), position) in transaction.ops.iter().zip(positions.iter()) {
+                let tree = &tx_trees[*position];
+                match op {
+                    Op::Insert(key, value) => {
+                        tree.insert(&key[..], &value[..])?;
+                    },
+                    Op::Remove(key) => {
+                        tree.remove(&key[..])?;
+                    },
+                    // Transactions do not support merge, so read the prior value and write the merged one in its place
+                    Op::Merge(key, operand, merge) => {
+                        let prior = tree.get(&key[..])?;
+                        match merge(key, prior.as_deref(), operand) {
+                            Some(merged) => tree.insert(&key[..], merged)?,
+                            None => tree.remove(&key[..])?,
+                        };
+                    },
+                    Op::Expect(..) => {},
+                }
+            }
+            let generation = &tx_trees[tx_trees.len() - 1];
+            let next = decode_generation(generation.get(GENERATION_KEY)?).map_err(ConflictableTransactionError::Abort)? + 1;
+            generation.insert(GENERATION_KEY, &next.to_be_bytes())?;
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        sled::Tree::flush(self)?;
+        Ok(())
+    }
+}
+
+impl Tree for sled::Tree {
+    type Value = IVec;
+    type Iter = Iter;
+
+    fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<IVec>, Error> {
+        Ok(sled::Tree::get(self, key)?)
+    }
+
+    fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> Result<(), Error> {
+        sled::Tree::insert(self, key, value.as_ref())?;
+        Ok(())
+    }
+
+    fn remove<K: AsRef<[u8]>>(&self, key: K) -> Result<(), Error> {
+        sled::Tree::remove(self, key)?;
+        Ok(())
+    }
+
+    fn merge<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, operand: V, merge: MergeFn) -> Result<Option<IVec>, Error> {
+        let key = key.as_ref();
+        Ok(self.fetch_and_update(key, |prior| merge(key, prior, operand.as_ref()))?)
+    }
+
+    fn range<K: AsRef<[u8]>, R: RangeBounds<K>>(&self, range: R) -> Iter {
+        Iter(sled::Tree::range(self, range))
+    }
+
+    fn scan_prefix<P: AsRef<[u8]>>(&self, prefix: P) -> Iter {
+        Iter(sled::Tree::scan_prefix(self, prefix))
+    }
+
+    fn iter(&self) -> Iter {
+        Iter(sled::Tree::iter(self))
+    }
+
+    fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> Result<bool, Error> {
+        Ok(sled::Tree::contains_key(self, key)?)
+    }
+
+    fn last(&self) -> Result<Option<(IVec, IVec)>, Error> {
+        Ok(sled::Tree::last(self)?)
+    }
+}
+
+/// `sled::Iter`, with errors converted
+pub struct Iter(sled::Iter);
+
+impl Iterator for Iter {
+    type Item = Result<(IVec, IVec), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|rec| Ok(rec?))
+    }
+}
+
+impl DoubleEndedIterator for Iter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|rec| Ok(rec?))
+    }
+}
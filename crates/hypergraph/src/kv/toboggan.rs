@@ -0,0 +1,326 @@
+//! `Store` over any toboggan-kv store, such that a hypergraph may share a backend with the rest of mindbase.
+//!
+//! Toboggan offers less than a `Store` needs, so this makes up the difference. It has no way to list or drop trees, so the
+//! names of those opened are recorded in a tree of their own, and dropped trees are cleared. It can only iterate over a whole
+//! tree from the start, so the keys of each tree are also kept in memory once it is opened, for ranges to seek in, with the
+//! values read from toboggan one at a time. It has no transactions, so each
+//! transaction has its expectations checked and its writes applied one at a time under a lock which every write to the store
+//! takes. That keeps writers from interleaving, but readers may see a transaction part way through, and a crash part way
+//! through leaves it partly applied. Use the sled and in-memory stores where that matters.
+//! ```
+//! use mindbase_hypergraph::{entity::{vertex, Property}, kv::toboggan::TobogganStore, adapter::kv::KvAdapter, Hypergraph};
+//! use toboggan_kv::adapter::BTreeAdapter;
+//!
+//! let store = TobogganStore::new(BTreeAdapter::new());
+//! let graph = Hypergraph::<_, String, String>::new(KvAdapter::from_store(store).unwrap());
+//! let prop = Property { key: "name".to_string(), value: "Lamp".to_string() };
+//! let (_, lamp) = graph.insert(vertex(vec![prop])).unwrap();
+//! assert!(graph.get(&lamp).is_ok());
+//! ```
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    convert::TryInto,
+    ops::{Bound, RangeBounds},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, RwLock,
+    },
+};
+
+use toboggan_kv::{Toboggan, Tree as _};
+
+use super::{
+    memory::{bound_bytes, is_empty_range, owned_bound, prefix_end},
+    MergeFn, Op, Pair, Store, Transaction, Tree,
+};
+use crate::Error;
+
+/// The names of every tree opened, as keys with empty values
+const TREE_NAMES: &str = "kv::tree_names";
+/// The next id to be generated, under `NEXT_ID_KEY`
+const IDS: &str = "kv::ids";
+const NEXT_ID_KEY: &[u8] = b"next_id";
+
+/// Merge operands, as toboggan can only remove a key by merging
+const REMOVE: u8 = 0;
+
+/// The keys of one tree, in order
+type Keys = Arc<RwLock<BTreeSet<Vec<u8>>>>;
+
+/// A `Store` over a toboggan-kv store. Clones share the same lock, generation and keys, so share one store between threads
+/// by cloning it
+#[derive(Clone)]
+pub struct TobogganStore<T> {
+    inner: T,
+    write_lock: Arc<Mutex<()>>,
+    generation: Arc<AtomicU64>,
+    /// Those of every tree opened so far, by name
+    keys: Arc<Mutex<HashMap<String, Keys>>>,
+}
+
+/// A handle to one of the trees of a `TobogganStore`
+pub struct TobogganTree<T> {
+    tree: Arc<T>,
+    keys: Keys,
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl<T: Toboggan> TobogganStore<T> {
+    pub fn new(inner: T) -> Self {
+        TobogganStore {
+            inner,
+            write_lock: Arc::new(Mutex::new(())),
+            generation: Arc::new(AtomicU64::new(0)),
+            keys: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        self.write_lock.lock().unwrap()
+    }
+
+    fn open_raw(&self, name: &str) -> Result<T::Tree, Error> {
+        let tree = self.inner.open_tree(name)?;
+        tree.set_merge_operator(remove_on_merge);
+        Ok(tree)
+    }
+
+    /// The keys of the named tree, which are read from toboggan the first time it is opened. Until then, there is no handle
+    /// by which it could be written to meanwhile
+    fn keys(&self, name: &str, tree: &T::Tree) -> Result<Keys, Error> {
+        let mut opened = self.keys.lock().unwrap();
+        if let Some(keys) = opened.get(name) {
+            return Ok(keys.clone());
+        }
+        let keys = tree.iter().map(|rec| Ok(rec?.0.to_vec())).collect::<Result<BTreeSet<_>, Error>>()?;
+        let keys = Arc::new(RwLock::new(keys));
+        opened.insert(name.to_string(), keys.clone());
+        Ok(keys)
+    }
+}
+
+/// Every merge applied directly by toboggan is a removal. Merges by `MergeFn` are read, merged and written under the lock
+fn remove_on_merge(_key: &[u8], _last: Option<&[u8]>, _operand: &[u8]) -> Option<Vec<u8>> {
+    None
+}
+
+impl<T: Toboggan> Store for TobogganStore<T> {
+    type Tree = TobogganTree<T::Tree>;
+
+    fn open_tree(&self, name: &str) -> Result<Self::Tree, Error> {
+        let tree = self.open_raw(name)?;
+        let names = self.open_raw(TREE_NAMES)?;
+        if names.get(name)?.is_none() {
+            let _lock = self.lock();
+            names.insert(name, [0u8; 0])?;
+        }
+        let keys = self.keys(name, &tree)?;
+        Ok(TobogganTree {
+            tree: Arc::new(tree),
+            keys,
+            write_lock: self.write_lock.clone(),
+        })
+    }
+
+    fn tree_names(&self) -> Result<Vec<String>, Error> {
+        self.open_raw(TREE_NAMES)?
+            .iter()
+            .map(|rec| String::from_utf8(rec?.0.to_vec()).map_err(|_| Error::InvalidSlice))
+            .collect()
+    }
+
+    /// Toboggan cannot drop a tree, so it is cleared, and forgotten until it is opened again
+    fn drop_tree(&self, name: &str) -> Result<bool, Error> {
+        let names = self.open_raw(TREE_NAMES)?;
+        let _lock = self.lock();
+        if names.get(name)?.is_none() {
+            return Ok(false);
+        }
+        self.open_raw(name)?.clear()?;
+        if let Some(keys) = self.keys.lock().unwrap().get(name) {
+            keys.write().unwrap().clear();
+        }
+        names.merge(name, [REMOVE])?;
+        Ok(true)
+    }
+
+    fn generate_id(&self) -> Result<u64, Error> {
+        let ids = self.open_raw(IDS)?;
+        let _lock = self.lock();
+        let id = match ids.get(NEXT_ID_KEY)? {
+            Some(bytes) => u64::from_be_bytes(bytes[..].try_into().map_err(|_| Error::InvalidSlice)?),
+            None => 0,
+        };
+        ids.insert(NEXT_ID_KEY, (id + 1).to_be_bytes())?;
+        Ok(id)
+    }
+
+    fn generation(&self) -> Result<u64, Error> {
+        Ok(self.generation.load(Ordering::SeqCst))
+    }
+
+    fn apply(&self, transaction: Transaction<'_, Self::Tree>) -> Result<(), Error> {
+        let _lock = self.lock();
+        for (tree, op) in transaction.ops.iter() {
+            if let Op::Expect(key, expected) = op {
+                if tree.tree.get(key)?.as_deref() != expected.as_deref() {
+                    return Err(Error::Conflict);
+                }
+            }
+        }
+        // Before and after, so that a reader which starts part way through sees the generation change too
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        for (tree, op) in transaction.ops {
+            match op {
+                Op::Insert(key, value) => tree.insert_unlocked(&key, &value)?,
+                Op::Remove(key) => tree.remove_unlocked(&key)?,
+                Op::Merge(key, operand, merge) => {
+                    tree.merge_unlocked(&key, &operand, merge)?;
+                },
+                Op::Expect(..) => {},
+            }
+        }
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        for name in self.tree_names()?.iter().map(String::as_str).chain([TREE_NAMES, IDS].iter().copied()) {
+            self.inner.open_tree(name)?.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: toboggan_kv::Tree> TobogganTree<T> {
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        self.write_lock.lock().unwrap()
+    }
+
+    /// The caller must hold the lock, as for each of these
+    fn insert_unlocked(&self, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        self.tree.insert(key, value)?;
+        self.keys.write().unwrap().insert(key.to_vec());
+        Ok(())
+    }
+
+    fn remove_unlocked(&self, key: &[u8]) -> Result<(), Error> {
+        self.tree.merge(key, [REMOVE])?;
+        self.keys.write().unwrap().remove(key);
+        Ok(())
+    }
+
+    fn merge_unlocked(&self, key: &[u8], operand: &[u8], merge: MergeFn) -> Result<Option<Vec<u8>>, Error> {
+        let prior = self.tree.get(key)?;
+        match merge(key, prior.as_deref(), operand) {
+            Some(merged) => self.insert_unlocked(key, &merged)?,
+            None => self.remove_unlocked(key)?,
+        }
+        Ok(prior.map(|prior| prior.to_vec()))
+    }
+}
+
+impl<T: toboggan_kv::Tree> Tree for TobogganTree<T> {
+    type Value = Vec<u8>;
+    type Iter = Iter<T>;
+
+    fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.tree.get(key)?.map(|value| value.to_vec()))
+    }
+
+    fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> Result<(), Error> {
+        let _lock = self.lock();
+        self.insert_unlocked(key.as_ref(), value.as_ref())
+    }
+
+    fn remove<K: AsRef<[u8]>>(&self, key: K) -> Result<(), Error> {
+        let _lock = self.lock();
+        self.remove_unlocked(key.as_ref())
+    }
+
+    fn merge<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, operand: V, merge: MergeFn) -> Result<Option<Vec<u8>>, Error> {
+        let _lock = self.lock();
+        self.merge_unlocked(key.as_ref(), operand.as_ref(), merge)
+    }
+
+    fn range<K: AsRef<[u8]>, R: RangeBounds<K>>(&self, range: R) -> Iter<T> {
+        Iter {
+            tree: self.tree.clone(),
+            keys: self.keys.clone(),
+            start: owned_bound(range.start_bound()),
+            end: owned_bound(range.end_bound()),
+        }
+    }
+
+    fn scan_prefix<P: AsRef<[u8]>>(&self, prefix: P) -> Iter<T> {
+        let prefix = prefix.as_ref();
+        Iter {
+            tree: self.tree.clone(),
+            keys: self.keys.clone(),
+            start: Bound::Included(prefix.to_vec()),
+            end: prefix_end(prefix),
+        }
+    }
+
+    fn iter(&self) -> Iter<T> {
+        self.range::<&[u8], _>(..)
+    }
+}
+
+/// Iterates over a range of a `TobogganTree`, as the memory store's does: each key is sought in the keys of the tree, under
+/// their lock, and the range narrowed past it. Its value is then read from toboggan, and if it has been removed meanwhile,
+/// the next key is sought instead
+pub struct Iter<T> {
+    tree: Arc<T>,
+    keys: Keys,
+    start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+}
+
+impl<T: toboggan_kv::Tree> Iter<T> {
+    fn step(&mut self, from_back: bool) -> Option<Result<Pair<Vec<u8>>, Error>> {
+        loop {
+            if is_empty_range(&self.start, &self.end) {
+                return None;
+            }
+            let key = {
+                let keys = self.keys.read().unwrap();
+                let mut range = keys.range::<[u8], _>((bound_bytes(self.start.as_ref()), bound_bytes(self.end.as_ref())));
+                if from_back {
+                    range.next_back()
+                } else {
+                    range.next()
+                }
+                .cloned()?
+            };
+            if from_back {
+                self.end = Bound::Excluded(key.clone());
+            } else {
+                self.start = Bound::Excluded(key.clone());
+            }
+            match self.tree.get(&key) {
+                Ok(Some(value)) => return Some(Ok((key, value.to_vec()))),
+                Ok(None) => continue,
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+    }
+}
+
+impl<T: toboggan_kv::Tree> Iterator for Iter<T> {
+    type Item = Result<Pair<Vec<u8>>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.step(false)
+    }
+}
+
+impl<T: toboggan_kv::Tree> DoubleEndedIterator for Iter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.step(true)
+    }
+}
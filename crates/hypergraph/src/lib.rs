@@ -1,9 +1,18 @@
 pub mod adapter;
+pub mod changes;
 pub mod entity;
 pub mod error;
+pub mod export;
 pub mod hypergraph;
 mod index;
+pub mod kv;
+pub mod migrate;
+pub mod namespace;
+pub mod ordered;
+pub mod snapshot;
+pub mod text;
 pub mod traits;
+pub mod traverse;
 
 pub use entity::{Entity, EntityId};
 pub use error::Error;
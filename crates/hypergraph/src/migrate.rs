@@ -0,0 +1,141 @@
+//! On-disk format versioning. The format version is written into the store when it is created,
+//! and adapters refuse to open a store of any other version until it has been upgraded in place by `KvAdapter::migrate_store`.
+//!
+//! To change the layout of any stored record: bump `FORMAT_VERSION`, and add a `Migration` from the prior version
+//! to `MIGRATIONS`, along with a `Migrate` method for it to apply.
+
+use std::convert::TryInto;
+
+use serde::Deserialize;
+
+use crate::{
+    entity::{EntityInner, EntityIx, Member},
+    kv::{Store, Tree},
+    snapshot::ENTITY_STORAGE_TREE,
+    EntityId, Error,
+};
+
+/// The format version written by this version of the crate
+pub const FORMAT_VERSION: u32 = 3;
+
+/// Stores which contain entities but no format version predate versioning
+pub const UNVERSIONED: u32 = 0;
+
+pub(crate) const META_TREE: &str = "hypergraph::meta";
+pub(crate) const FORMAT_VERSION_KEY: &[u8] = b"format_version";
+
+/// Upgrades a store from one format version to the next
+pub struct Migration {
+    pub from: u32,
+    pub to: u32,
+    pub description: &'static str,
+    /// Rewrite the store, and stamp it with the new version, in one transaction
+    pub(crate) apply: fn(&dyn Migrate, &Migration) -> Result<(), Error>,
+}
+
+impl std::fmt::Debug for Migration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Migration")
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
+impl PartialEq for Migration {
+    fn eq(&self, other: &Self) -> bool {
+        (self.from, self.to) == (other.from, other.to)
+    }
+}
+
+/// What each migration does to an opened store
+pub(crate) trait Migrate {
+    fn migrate_unversioned(&self, migration: &Migration) -> Result<(), Error>;
+    fn migrate_text_index(&self, migration: &Migration) -> Result<(), Error>;
+    fn migrate_short_ids(&self, migration: &Migration) -> Result<(), Error>;
+}
+
+/// Every migration, in the order they must be applied
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: UNVERSIONED,
+        to: 1,
+        description: "Intern property symbols and values, and index entities by membership and property",
+        apply: |store, migration| store.migrate_unversioned(migration),
+    },
+    Migration {
+        from: 1,
+        to: 2,
+        description: "Index the words in the text of property values, for full-text search",
+        apply: |store, migration| store.migrate_text_index(migration),
+    },
+    Migration {
+        from: 2,
+        to: 3,
+        description: "Index entities by their short id, for resolving short id prefixes",
+        apply: |store, migration| store.migrate_short_ids(migration),
+    },
+];
+
+/// The format version of the store, or None if it has never been written to
+pub fn format_version<S: Store>(store: &S) -> Result<Option<u32>, Error> {
+    if let Some(bytes) = store.open_tree(META_TREE)?.get(FORMAT_VERSION_KEY)? {
+        let bytes: [u8; 4] = bytes.as_ref().try_into().map_err(|_| Error::InvalidSlice)?;
+        return Ok(Some(u32::from_be_bytes(bytes)));
+    }
+
+    if store.open_tree(ENTITY_STORAGE_TREE)?.iter().next().is_some() {
+        Ok(Some(UNVERSIONED))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The migrations needed to bring a store of the given version up to `FORMAT_VERSION`.
+/// Stores written by a newer version of the crate, or in a version never released, cannot be migrated
+pub fn migrations_from(version: u32) -> Result<&'static [Migration], Error> {
+    if version == FORMAT_VERSION {
+        return Ok(&[]);
+    }
+    match MIGRATIONS.iter().position(|m| m.from == version) {
+        Some(start) => Ok(&MIGRATIONS[start..]),
+        None => Err(Error::UnsupportedFormat(version)),
+    }
+}
+
+/// Entity records as they were stored before versioning: properties held their serialized symbol and value inline
+#[derive(Deserialize)]
+pub(crate) struct UnversionedEntity(pub EntityId, pub Vec<(Vec<u8>, Vec<u8>)>, UnversionedInner);
+
+#[derive(Deserialize)]
+enum UnversionedInner {
+    Vertex,
+    Edge(Vec<EntityId>),
+    DirectedEdge(Vec<EntityId>, Vec<EntityId>),
+}
+
+impl UnversionedEntity {
+    /// Members had no degree of membership, and so are full members
+    pub(crate) fn inner(&self) -> EntityInner {
+        let members = |ids: &Vec<EntityId>| ids.iter().copied().map(Member::from).collect();
+        match &self.2 {
+            UnversionedInner::Vertex => EntityInner::Vertex,
+            UnversionedInner::Edge(m) => EntityInner::Edge(members(m)),
+            UnversionedInner::DirectedEdge(f, t) => EntityInner::DirectedEdge(members(f), members(t)),
+        }
+    }
+}
+
+/// Read every unversioned entity record, in `EntityIx` order. Nothing is written, so a record which fails to decode
+/// leaves the store as it was
+pub(crate) fn read_unversioned<T: Tree>(entity_storage: &T) -> Result<Vec<(EntityIx, UnversionedEntity)>, Error> {
+    entity_storage
+        .iter()
+        .map(|rec| {
+            let (key, value) = rec?;
+            let key: [u8; 8] = key.as_ref().try_into().map_err(|_| Error::InvalidSlice)?;
+            Ok((u64::from_be_bytes(key), bincode::deserialize(&value)?))
+        })
+        .collect()
+}
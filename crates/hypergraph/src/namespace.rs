@@ -0,0 +1,263 @@
+//! Several hypergraphs within one store, each in a namespace of its own with separate entity, index and symbol trees.
+//!
+//! The default namespace holds the trees just as a `KvAdapter` names them, such that a store written before namespaces
+//! existed is simply the default namespace. Every other namespace holds the same trees, under `namespace::<name>::`.
+//! ```
+//! use mindbase_hypergraph::{entity::{vertex, Property}, kv::memory::MemoryStore, namespace::Namespaces, Hypergraph};
+//! let namespaces = Namespaces::new(MemoryStore::default());
+//! let house = Hypergraph::<_, String, String>::new(namespaces.create("house").unwrap());
+//! let garden = Hypergraph::<_, String, String>::new(namespaces.create("garden").unwrap());
+//!
+//! let prop = Property { key: "name".to_string(), value: "Lamp".to_string() };
+//! let (_, lamp) = house.insert(vertex(vec![prop])).unwrap();
+//! assert!(house.get(&lamp).is_ok());
+//! assert!(garden.get(&lamp).is_err());
+//! assert_eq!(namespaces.list().unwrap(), vec!["garden", "house"]);
+//! ```
+
+use crate::{
+    adapter::kv::KvAdapter,
+    kv::{Store, Transaction, Tree},
+    migrate::META_TREE,
+    snapshot::ENTITY_STORAGE_TREE,
+    traits::{TProvenance, TSymbol, TValue},
+    Error,
+};
+
+/// The name of the namespace whose trees are not prefixed
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+const PREFIX: &str = "namespace::";
+
+/// Every tree written by a `KvAdapter` is named with this prefix. Anything else in the store is left alone
+const ADAPTER_TREE_PREFIX: &str = "hypergraph::";
+
+/// How many entries `Namespaces::copy` reads before it checks the source is unchanged, and writes them
+pub const COPY_CHUNK: usize = 1024;
+
+/// One namespace of a store, which is itself a `Store` whose trees are those of the namespace.
+/// Transactions, generated ids and the generation are those of the underlying store
+#[derive(Clone)]
+pub struct Namespace<S> {
+    store: S,
+    name: String,
+    /// Prepended to the name of each tree. Empty for the default namespace
+    prefix: String,
+}
+
+impl<S: Store> Namespace<S> {
+    /// Names may be anything but empty, and may not contain ':'
+    pub fn new(store: S, name: &str) -> Result<Self, Error> {
+        if name.is_empty() || name.contains(':') {
+            return Err(Error::InvalidNamespace(name.to_string()));
+        }
+        let prefix = if name == DEFAULT_NAMESPACE {
+            String::new()
+        } else {
+            format!("{}{}::", PREFIX, name)
+        };
+
+        Ok(Namespace {
+            store,
+            name: name.to_string(),
+            prefix,
+        })
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// The store which holds this namespace, and any others
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+    /// Whether a hypergraph has been created in this namespace. Unlike opening its trees, this writes nothing
+    pub fn exists(&self) -> Result<bool, Error> {
+        Ok(self
+            .tree_names()?
+            .iter()
+            .any(|name| name == META_TREE || name == ENTITY_STORAGE_TREE))
+    }
+}
+
+impl<S: Store> Store for Namespace<S> {
+    type Tree = S::Tree;
+
+    fn open_tree(&self, name: &str) -> Result<S::Tree, Error> {
+        self.store.open_tree(&format!("{}{}", self.prefix, name))
+    }
+
+    fn tree_names(&self) -> Result<Vec<String>, Error> {
+        let names = self.store.tree_names()?.into_iter();
+        Ok(if self.prefix.is_empty() {
+            names.filter(|name| !name.starts_with(PREFIX)).collect()
+        } else {
+            names.filter_map(|name| name.strip_prefix(&self.prefix).map(str::to_string)).collect()
+        })
+    }
+
+    fn drop_tree(&self, name: &str) -> Result<bool, Error> {
+        self.store.drop_tree(&format!("{}{}", self.prefix, name))
+    }
+
+    fn generate_id(&self) -> Result<u64, Error> {
+        self.store.generate_id()
+    }
+
+    /// That of the whole store, as transactions are
+    fn generation(&self) -> Result<u64, Error> {
+        self.store.generation()
+    }
+
+    fn apply(&self, transaction: Transaction<'_, S::Tree>) -> Result<(), Error> {
+        self.store.apply(transaction)
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        self.store.flush()
+    }
+}
+
+/// Lists, creates, opens, drops and copies the namespaces of a store
+pub struct Namespaces<S> {
+    store: S,
+}
+
+impl<S: Store> Namespaces<S> {
+    pub fn new(store: S) -> Self {
+        Namespaces { store }
+    }
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+    pub fn namespace(&self, name: &str) -> Result<Namespace<S>, Error> {
+        Namespace::new(self.store.clone(), name)
+    }
+
+    /// The names of every namespace which holds a hypergraph, in order
+    pub fn list(&self) -> Result<Vec<String>, Error> {
+        let mut names = Vec::new();
+        if self.namespace(DEFAULT_NAMESPACE)?.exists()? {
+            names.push(DEFAULT_NAMESPACE.to_string());
+        }
+        for tree in self.store.tree_names()? {
+            let name = match tree.strip_prefix(PREFIX).and_then(|rest| rest.strip_suffix(META_TREE)) {
+                Some(name) => name,
+                None => continue,
+            };
+            if let Some(name) = name.strip_suffix("::") {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    /// Create a hypergraph in a namespace which does not already hold one
+    pub fn create<Sym, Val, Prov>(&self, name: &str) -> Result<KvAdapter<Namespace<S>, Sym, Val, Prov>, Error>
+    where
+        Sym: TSymbol,
+        Val: TValue,
+        Prov: TProvenance,
+    {
+        let namespace = self.namespace(name)?;
+        if namespace.exists()? {
+            return Err(Error::AlreadyExists);
+        }
+        KvAdapter::from_store(namespace)
+    }
+
+    /// Open the hypergraph in an existing namespace. As with `KvAdapter::from_store`, it must be of the current format version.
+    /// Use `KvAdapter::migrate_store` on `namespace(name)` to upgrade it
+    pub fn open<Sym, Val, Prov>(&self, name: &str) -> Result<KvAdapter<Namespace<S>, Sym, Val, Prov>, Error>
+    where
+        Sym: TSymbol,
+        Val: TValue,
+        Prov: TProvenance,
+    {
+        let namespace = self.namespace(name)?;
+        if !namespace.exists()? {
+            return Err(Error::NotFound);
+        }
+        KvAdapter::from_store(namespace)
+    }
+
+    /// Remove the namespace and everything in it. Any adapter still open on it must not be used again.
+    /// Returns false if there was no such namespace
+    pub fn drop(&self, name: &str) -> Result<bool, Error> {
+        let namespace = self.namespace(name)?;
+        if !namespace.exists()? {
+            return Ok(false);
+        }
+        Self::drop_adapter_trees(&namespace)?;
+        self.store.flush()?;
+        Ok(true)
+    }
+
+    /// Copy everything in one namespace into another, which must not already exist, as it was at one point in time.
+    /// Entries are read and written in chunks of `COPY_CHUNK`, not in one transaction, so writers are not held up meanwhile.
+    /// Instead, if the store applies a transaction, the copy stops with `Error::Conflict` and whatever it wrote is removed
+    pub fn copy(&self, from: &str, to: &str) -> Result<(), Error> {
+        let source = self.namespace(from)?;
+        let target = self.namespace(to)?;
+        if !source.exists()? {
+            return Err(Error::NotFound);
+        }
+        if target.exists()? {
+            return Err(Error::AlreadyExists);
+        }
+
+        let generation = source.generation()?;
+        let names = Self::adapter_trees(&source)?;
+
+        // Anything left over from a copy which was interrupted
+        Self::drop_adapter_trees(&target)?;
+        if let Err(e) = Self::copy_trees(&source, &target, &names, generation) {
+            Self::drop_adapter_trees(&target)?;
+            return Err(e);
+        }
+        self.store.flush()
+    }
+
+    fn copy_trees(source: &Namespace<S>, target: &Namespace<S>, names: &[String], generation: u64) -> Result<(), Error> {
+        for name in names.iter() {
+            let source_tree = source.open_tree(name)?;
+            let target_tree = target.open_tree(name)?;
+
+            let mut entries = source_tree.iter().peekable();
+            while entries.peek().is_some() {
+                let chunk = entries.by_ref().take(COPY_CHUNK).collect::<Result<Vec<_>, Error>>()?;
+                // Checked after reading each chunk and before writing it, such that nothing read after a change is written
+                if source.generation()? != generation {
+                    return Err(Error::Conflict);
+                }
+                for (key, value) in chunk {
+                    target_tree.insert(key, value)?;
+                }
+            }
+        }
+        if Self::adapter_trees(source)? != names {
+            return Err(Error::Conflict);
+        }
+        Ok(())
+    }
+
+    /// The trees of the namespace written by a `KvAdapter`. Those by which `Namespace::exists` knows it go last,
+    /// so that a copy is not mistaken for a hypergraph until it is complete
+    fn adapter_trees(namespace: &Namespace<S>) -> Result<Vec<String>, Error> {
+        let mut names: Vec<String> = namespace
+            .tree_names()?
+            .into_iter()
+            .filter(|name| name.starts_with(ADAPTER_TREE_PREFIX))
+            .collect();
+        names.sort_by_key(|name| name == ENTITY_STORAGE_TREE || name == META_TREE);
+        Ok(names)
+    }
+
+    fn drop_adapter_trees(namespace: &Namespace<S>) -> Result<(), Error> {
+        for tree in Self::adapter_trees(namespace)? {
+            namespace.drop_tree(&tree)?;
+        }
+        Ok(())
+    }
+}
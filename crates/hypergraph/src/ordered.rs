@@ -0,0 +1,65 @@
+//! Byte encodings which sort in the same order as the values they encode.
+//!
+//! These are the building blocks for `TValue::sort_key`. Keys produced by combining them must be prefix-free,
+//! meaning no key may be a proper prefix of another, as the range index appends the `EntityIx` to each key.
+//! Fixed width encodings are trivially prefix-free, and `encode_bytes` is terminated for this purpose.
+
+pub fn encode_u32(v: u32) -> [u8; 4] {
+    v.to_be_bytes()
+}
+
+pub fn encode_u64(v: u64) -> [u8; 8] {
+    v.to_be_bytes()
+}
+
+/// Flip the sign bit, such that negative numbers sort before positive ones
+pub fn encode_i64(v: i64) -> [u8; 8] {
+    ((v as u64) ^ (1 << 63)).to_be_bytes()
+}
+
+/// Flip every bit of negative numbers, and only the sign bit of the rest, such that -0.0 sorts just before 0.0.
+/// NaNs sort after infinity, or before negative infinity if their sign bit is set
+pub fn encode_f64(v: f64) -> [u8; 8] {
+    let bits = v.to_bits();
+    if bits >> 63 == 1 {
+        (!bits).to_be_bytes()
+    } else {
+        (bits ^ (1 << 63)).to_be_bytes()
+    }
+}
+
+/// Escape each 0x00 as 0x00 0xFF, and terminate with 0x00 0x00
+pub fn encode_bytes(v: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(v.len() + 2);
+    for b in v {
+        out.push(*b);
+        if *b == 0 {
+            out.push(0xFF);
+        }
+    }
+    out.extend_from_slice(&[0, 0]);
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn order_preserved() {
+        let ints = [i64::MIN, -1000, -1, 0, 1, 1000, i64::MAX];
+        for pair in ints.windows(2) {
+            assert!(encode_i64(pair[0]) < encode_i64(pair[1]));
+        }
+
+        let floats = [f64::NEG_INFINITY, f64::MIN, -1.5, -f64::MIN_POSITIVE, -0.0, 0.0, f64::MIN_POSITIVE, 1.5, f64::MAX, f64::INFINITY];
+        for pair in floats.windows(2) {
+            assert!(encode_f64(pair[0]) < encode_f64(pair[1]));
+        }
+
+        let strings: [&[u8]; 6] = [b"", b"\0", b"\0\0", b"\0a", b"a", b"ab"];
+        for pair in strings.windows(2) {
+            assert!(encode_bytes(pair[0]) < encode_bytes(pair[1]));
+        }
+    }
+}
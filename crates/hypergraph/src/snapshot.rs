@@ -0,0 +1,205 @@
+//! Point-in-time copies of an entire store in a single file, from which a fresh store may be restored.
+//!
+//! A snapshot is the magic bytes, then a stream of bincode `Record`s: each tree's name followed by its entries in key order,
+//! and finally the `Manifest`. The last 8 bytes are the big-endian length of the manifest record,
+//! so that the manifest may be read without reading the rest of the file.
+
+use std::{
+    convert::TryInto,
+    fmt::Display,
+    io::{Read, Seek, SeekFrom, Write},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512Trunc256};
+
+use crate::{
+    kv::{Store, Tree},
+    Error,
+};
+
+pub const MAGIC: &[u8; 8] = b"MBSNAP\x00\x01";
+
+/// The tree whose entries are counted as `Manifest::entities`
+pub(crate) const ENTITY_STORAGE_TREE: &str = "hypergraph::entity_storage";
+
+/// Describes the contents of a snapshot, such that a restore may be verified against it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Manifest {
+    pub created: SystemTime,
+    /// The number of entities in the snapshot, not counting removed ones
+    pub entities: u64,
+    pub trees: Vec<TreeManifest>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TreeManifest {
+    pub name: String,
+    pub entries: u64,
+    /// Sha512Trunc256 over the length-prefixed key and value of each entry, in key order
+    pub checksum: [u8; 32],
+}
+
+impl Display for TreeManifest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use base64::STANDARD_NO_PAD;
+        write!(
+            f,
+            "{} entries={} checksum={}",
+            self.name,
+            self.entries,
+            base64::encode_config(self.checksum, STANDARD_NO_PAD)
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+enum Record {
+    Tree(String),
+    Entry(Vec<u8>, Vec<u8>),
+    Manifest(Manifest),
+}
+
+/// Accumulates the entry count and checksum of a tree as its entries are written or read
+struct TreeSummer {
+    name: String,
+    entries: u64,
+    hasher: Sha512Trunc256,
+}
+
+impl TreeSummer {
+    fn new(name: String) -> Self {
+        TreeSummer {
+            name,
+            entries: 0,
+            hasher: Sha512Trunc256::default(),
+        }
+    }
+    fn add(&mut self, key: &[u8], value: &[u8]) {
+        self.entries += 1;
+        self.hasher.update((key.len() as u64).to_be_bytes());
+        self.hasher.update(key);
+        self.hasher.update((value.len() as u64).to_be_bytes());
+        self.hasher.update(value);
+    }
+    fn finish(self) -> TreeManifest {
+        TreeManifest {
+            name: self.name,
+            entries: self.entries,
+            checksum: self.hasher.finalize().into(),
+        }
+    }
+}
+
+/// Write every tree of the store to `out`, as they were at one point in time. Writers are not held up here, so the caller
+/// should keep them out meanwhile. Any which are not, because the store applies a transaction or its trees change, stop
+/// this with `Error::Conflict` before the manifest is written, such that what was written cannot be restored
+pub(crate) fn write<S: Store, W: Write>(store: &S, mut out: W) -> Result<Manifest, Error> {
+    let generation = store.generation()?;
+    let names = store.tree_names()?;
+    out.write_all(MAGIC)?;
+
+    let mut manifest = Manifest {
+        created: SystemTime::now(),
+        entities: 0,
+        trees: Vec::new(),
+    };
+    for name in names.iter() {
+        let tree = store.open_tree(name)?;
+        bincode::serialize_into(&mut out, &Record::Tree(name.clone()))?;
+
+        let mut summer = TreeSummer::new(name.clone());
+        for rec in tree.iter() {
+            let (key, value) = rec?;
+            summer.add(&key, &value);
+            bincode::serialize_into(&mut out, &Record::Entry(key.to_vec(), value.to_vec()))?;
+        }
+        // Checked after each tree, so as to give up early
+        if store.generation()? != generation {
+            return Err(Error::Conflict);
+        }
+        let summary = summer.finish();
+        if summary.name == ENTITY_STORAGE_TREE {
+            manifest.entities = summary.entries;
+        }
+        manifest.trees.push(summary);
+    }
+    if store.tree_names()? != names {
+        return Err(Error::Conflict);
+    }
+
+    let record = bincode::serialize(&Record::Manifest(manifest.clone()))?;
+    out.write_all(&record)?;
+    out.write_all(&(record.len() as u64).to_be_bytes())?;
+    out.flush()?;
+
+    Ok(manifest)
+}
+
+/// Read the manifest of a snapshot without reading the rest of it
+pub fn read_manifest<R: Read + Seek>(mut input: R) -> Result<Manifest, Error> {
+    let mut magic = [0u8; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(Error::InvalidSnapshot);
+    }
+
+    let mut len = [0u8; 8];
+    input.seek(SeekFrom::End(-8))?;
+    input.read_exact(&mut len)?;
+    let len: i64 = u64::from_be_bytes(len).try_into().map_err(|_| Error::InvalidSnapshot)?;
+    input.seek(SeekFrom::End(-8 - len))?;
+
+    match bincode::deserialize_from(input)? {
+        Record::Manifest(manifest) => Ok(manifest),
+        _ => Err(Error::InvalidSnapshot),
+    }
+}
+
+/// Write the trees of a snapshot into the store, each of which must be empty, and verify them against its manifest.
+/// On error, the store is left partially restored, and should be discarded
+pub(crate) fn restore<S: Store, R: Read>(store: &S, mut input: R) -> Result<Manifest, Error> {
+    let mut magic = [0u8; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(Error::InvalidSnapshot);
+    }
+
+    let mut restored = Vec::new();
+    let mut current: Option<(S::Tree, TreeSummer)> = None;
+    let manifest = loop {
+        match bincode::deserialize_from(&mut input)? {
+            Record::Tree(name) => {
+                let tree = store.open_tree(&name)?;
+                if tree.iter().next().is_some() {
+                    return Err(Error::AlreadyExists);
+                }
+                if let Some((_, summer)) = current.replace((tree, TreeSummer::new(name))) {
+                    restored.push(summer.finish());
+                }
+            },
+            Record::Entry(key, value) => {
+                let (tree, summer) = current.as_mut().ok_or(Error::InvalidSnapshot)?;
+                summer.add(&key, &value);
+                tree.insert(key, value)?;
+            },
+            Record::Manifest(manifest) => break manifest,
+        }
+    };
+    if let Some((_, summer)) = current {
+        restored.push(summer.finish());
+    }
+
+    if manifest.trees.len() != restored.len() {
+        return Err(Error::InvalidSnapshot);
+    }
+    for (expected, found) in manifest.trees.iter().zip(restored.iter()) {
+        if expected != found {
+            return Err(Error::ChecksumMismatch(expected.name.clone()));
+        }
+    }
+    store.flush()?;
+
+    Ok(manifest)
+}
@@ -0,0 +1,280 @@
+//! Full-text search over the text of property values, as given by `TValue::text`.
+//!
+//! Text is split into words on Unicode word boundaries, and lowercased. Each word is indexed both as it is and as stemmed
+//! by the Snowball English stemmer, so that each query may choose whether "lamps" should find "lamp". The position of
+//! each word within its property value is kept, so that phrases may be matched. Results are ranked by BM25,
+//! treating all of the text of an entity as one document.
+//!
+//! Any value type takes part by implementing `TValue::text`, as `String`, `MBValue` and the `Artifact`s of MBQL do.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryInto,
+};
+
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    entity::{EntityIx, SymbolIx},
+    EntityId, Error,
+};
+
+/// Which entities to find, and how. Every word and phrase must match, unless `any` is set
+/// ```
+/// use mindbase_hypergraph::{entity::{vertex, Property}, text::TextQuery, Hypergraph};
+/// let graph = Hypergraph::<_, String, String>::memory();
+/// let prop = |key: &str, value: &str| Property { key: key.to_string(), value: value.to_string() };
+/// let (_, lamp) = graph.insert(vertex(vec![prop("name", "Living Room Lamps")])).unwrap();
+/// let (_, note) = graph.insert(vertex(vec![prop("note", "the lamp in the living room flickers")])).unwrap();
+///
+/// let hits = graph.search(&TextQuery::parse("\"living room\" lamp").stemmed()).unwrap();
+/// assert_eq!(hits.len(), 2);
+/// let hits = graph.search(&TextQuery::parse("\"living room lamps\"")).unwrap();
+/// assert_eq!(hits[0].entity_id, lamp);
+/// assert_eq!(hits.len(), 1);
+/// ```
+pub struct TextQuery<Sym> {
+    /// Each is a single word, or the words of a phrase
+    pub(crate) clauses: Vec<Vec<String>>,
+    pub(crate) any: bool,
+    pub(crate) stemmed: bool,
+    pub(crate) symbols: Option<Vec<Sym>>,
+    pub(crate) limit: Option<usize>,
+}
+
+impl<Sym> TextQuery<Sym> {
+    /// Words separated by whitespace, and phrases in double quotes. Words are split and lowercased as the indexed text is
+    pub fn parse(query: &str) -> Self {
+        let mut clauses = Vec::new();
+        for (i, part) in query.split('"').enumerate() {
+            // Odd parts are within quotes
+            if i % 2 == 1 {
+                let phrase = words(part);
+                if !phrase.is_empty() {
+                    clauses.push(phrase);
+                }
+            } else {
+                clauses.extend(words(part).into_iter().map(|word| vec![word]));
+            }
+        }
+
+        TextQuery {
+            clauses,
+            any: false,
+            stemmed: false,
+            symbols: None,
+            limit: None,
+        }
+    }
+    /// Match entities with any of the words and phrases, rather than all of them. Those with more still rank higher
+    pub fn any(mut self) -> Self {
+        self.any = true;
+        self
+    }
+    /// Match words by their stem, such that "lamp" finds "lamps"
+    pub fn stemmed(mut self) -> Self {
+        self.stemmed = true;
+        self
+    }
+    /// Only search the text of properties with these symbols
+    pub fn within(mut self, symbols: Vec<Sym>) -> Self {
+        self.symbols = Some(symbols);
+        self
+    }
+    /// Return no more than this many of the best ranked entities
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+/// An entity which matched, and how well. Higher scores are better
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    pub entity_id: EntityId,
+    pub score: f64,
+}
+
+/// Split text into lowercase words
+pub fn words(text: &str) -> Vec<String> {
+    text.unicode_words().map(str::to_lowercase).collect()
+}
+
+pub fn stem(word: &str) -> String {
+    Stemmer::create(Algorithm::English).stem(word).into_owned()
+}
+
+/// Words are indexed both as they are, and by their stem
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum TermKind {
+    Word = 0,
+    Stem = 1,
+}
+
+/// Term index keys are the `TermKind`, the term, a zero byte, and then the `EntityIx`.
+/// Words never contain a zero byte, so one term may never be mistaken for the prefix of another
+pub(crate) fn term_prefix(kind: TermKind, term: &str) -> Vec<u8> {
+    let mut key = vec![kind as u8];
+    key.extend_from_slice(term.as_bytes());
+    key.push(0);
+    key
+}
+
+fn term_key(kind: TermKind, term: &str, entity_ix: EntityIx) -> Vec<u8> {
+    let mut key = term_prefix(kind, term);
+    key.extend_from_slice(&entity_ix.to_be_bytes());
+    key
+}
+
+/// Where a term occurs in one property value of an entity: the property's place among the entity's properties,
+/// its symbol, and the position of each occurrence among the words of the value
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(crate) struct Occurrence {
+    pub property: u32,
+    pub symbol_ix: SymbolIx,
+    pub positions: Vec<u32>,
+}
+
+/// The term index entries for the text of one entity
+#[derive(Default)]
+pub(crate) struct Postings {
+    terms: BTreeMap<(TermKind, String), Vec<Occurrence>>,
+    /// The number of words in all of the entity's text
+    pub length: u32,
+}
+
+impl Postings {
+    pub(crate) fn add(&mut self, property: u32, symbol_ix: SymbolIx, text: &str) {
+        let stemmer = Stemmer::create(Algorithm::English);
+        for (position, word) in words(text).into_iter().enumerate() {
+            let position = position as u32;
+            let stemmed = stemmer.stem(&word).into_owned();
+            for term in [(TermKind::Word, word), (TermKind::Stem, stemmed)].iter().cloned() {
+                let occurrences = self.terms.entry(term).or_default();
+                match occurrences.last_mut() {
+                    Some(occurrence) if occurrence.property == property => occurrence.positions.push(position),
+                    _ => occurrences.push(Occurrence {
+                        property,
+                        symbol_ix,
+                        positions: vec![position],
+                    }),
+                }
+            }
+            self.length += 1;
+        }
+    }
+    /// The term index keys and values
+    pub(crate) fn entries(&self, entity_ix: EntityIx) -> impl Iterator<Item = (Vec<u8>, Vec<u8>)> + '_ {
+        self.terms.iter().map(move |((kind, term), occurrences)| {
+            (term_key(*kind, term, entity_ix), bincode::serialize(occurrences).unwrap())
+        })
+    }
+}
+
+/// The text length tree holds the totals for the whole index under the empty key
+pub(crate) const TOTALS_KEY: &[u8] = b"";
+
+/// The operand for `merge_totals` which adds or removes the text of one entity
+pub(crate) fn totals_operand(length: u32, added: bool) -> [u8; 16] {
+    let sign: i64 = if added { 1 } else { -1 };
+    let mut operand = [0u8; 16];
+    operand[..8].copy_from_slice(&sign.to_be_bytes());
+    operand[8..].copy_from_slice(&(sign * length as i64).to_be_bytes());
+    operand
+}
+
+/// Add to the number of entities with text, and the number of words they hold between them. Removed once both are zero
+pub(crate) fn merge_totals(_key: &[u8], last_bytes: Option<&[u8]>, op_bytes: &[u8]) -> Option<Vec<u8>> {
+    let (documents, words) = read_totals(last_bytes);
+    let documents = documents + read_i64(op_bytes);
+    let words = words + read_i64(&op_bytes[8..]);
+    if documents <= 0 && words <= 0 {
+        return None;
+    }
+
+    let mut out = documents.to_be_bytes().to_vec();
+    out.extend_from_slice(&words.to_be_bytes());
+    Some(out)
+}
+
+/// The number of entities with text, and the number of words they hold between them
+pub(crate) fn read_totals(bytes: Option<&[u8]>) -> (i64, i64) {
+    match bytes {
+        Some(totals) if totals.len() == 16 => (read_i64(totals), read_i64(&totals[8..])),
+        _ => (0, 0),
+    }
+}
+
+fn read_i64(bytes: &[u8]) -> i64 {
+    let mut n = [0u8; 8];
+    n.copy_from_slice(&bytes[..8]);
+    i64::from_be_bytes(n)
+}
+
+/// Decode the term index entries under a term prefix, keeping only occurrences in the given properties
+pub(crate) fn read_postings<I, V>(
+    entries: I, prefix_len: usize, symbols: Option<&BTreeSet<SymbolIx>>,
+) -> Result<BTreeMap<EntityIx, Vec<Occurrence>>, Error>
+where
+    I: Iterator<Item = Result<(V, V), Error>>,
+    V: AsRef<[u8]>,
+{
+    let mut postings = BTreeMap::new();
+    for rec in entries {
+        let (key, value) = rec?;
+        let ix_bytes = key.as_ref().get(prefix_len..).ok_or(Error::InvalidSlice)?;
+        let entity_ix = u64::from_be_bytes(ix_bytes.try_into().map_err(|_| Error::InvalidSlice)?);
+        let mut occurrences: Vec<Occurrence> = bincode::deserialize(value.as_ref())?;
+        if let Some(symbols) = symbols {
+            occurrences.retain(|o| symbols.contains(&o.symbol_ix));
+        }
+        if !occurrences.is_empty() {
+            postings.insert(entity_ix, occurrences);
+        }
+    }
+    Ok(postings)
+}
+
+/// How many times the words of the clause occur in sequence in each entity. `postings` holds those of each word in turn
+pub(crate) fn clause_frequencies(postings: &[BTreeMap<EntityIx, Vec<Occurrence>>]) -> BTreeMap<EntityIx, u32> {
+    let mut frequencies = BTreeMap::new();
+    let (first, rest) = match postings.split_first() {
+        Some(split) => split,
+        None => return frequencies,
+    };
+
+    for (entity_ix, occurrences) in first.iter() {
+        let mut frequency = 0;
+        for occurrence in occurrences.iter() {
+            for start in occurrence.positions.iter() {
+                let follows = rest.iter().enumerate().all(|(i, word)| {
+                    word.get(entity_ix).is_some_and(|occurrences| {
+                        occurrences.iter().any(|o| {
+                            o.property == occurrence.property && o.positions.binary_search(&(start + i as u32 + 1)).is_ok()
+                        })
+                    })
+                });
+                if follows {
+                    frequency += 1;
+                }
+            }
+        }
+        if frequency > 0 {
+            frequencies.insert(*entity_ix, frequency);
+        }
+    }
+    frequencies
+}
+
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// The BM25 score of one clause for one entity
+pub(crate) fn bm25(frequency: u32, length: f64, matching: usize, documents: f64, average_length: f64) -> f64 {
+    let matching = matching as f64;
+    let idf = (1.0 + (documents - matching + 0.5) / (matching + 0.5)).ln();
+    let frequency = frequency as f64;
+    idf * frequency * (K1 + 1.0) / (frequency + K1 * (1.0 - B + B * length / average_length.max(1.0)))
+}
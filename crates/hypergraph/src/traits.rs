@@ -1,7 +1,9 @@
 use serde::{de::DeserializeOwned, Serialize};
 
-use crate::{Entity, EntityId, Error};
+use crate::{ordered, Entity, EntityId, Error};
 
+/// A property value. Any serde type may be one: those which need none of the indexes beyond exact match may be declared
+/// with `plain_values!`, which stands in for the blanket impl this trait once had. Others implement `sort_key` and `text`
 pub trait TValue: Sized + Serialize + DeserializeOwned {
     fn serialize(&self) -> Vec<u8> {
         bincode::serialize(self).unwrap()
@@ -9,13 +11,71 @@ pub trait TValue: Sized + Serialize + DeserializeOwned {
     fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
         Ok(bincode::deserialize(bytes)?)
     }
+    /// An encoding of this value whose byte order matches the value order, for use in range queries.
+    /// Values which have no meaningful order should return None, and are left out of the range index.
+    /// See `crate::ordered` for the building blocks, and the constraints on the output
+    fn sort_key(&self) -> Option<Vec<u8>> {
+        None
+    }
+    /// The text of this value, for full-text search. Values which are not text should return None,
+    /// and are left out of the full-text index. See `crate::text`
+    fn text(&self) -> Option<&str> {
+        None
+    }
     // type Symbol;
     // fn compare<G, W>(&self, other: &Self, graph: &G) -> Result<f64, Error>
     // where
     //     G: GraphInterface<Self>;
 }
 
-impl<T> TValue for T where T: Sized + Serialize + DeserializeOwned {}
+/// Implement `TValue` for each of the given types using only the defaults, such that their values are stored and may be
+/// found by exact match, but are left out of the range and full-text indexes
+/// ```
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Reading(u8, u8);
+/// mindbase_hypergraph::plain_values!(Reading);
+/// ```
+#[macro_export]
+macro_rules! plain_values {
+    ($($value:ty),+ $(,)?) => {
+        $(impl $crate::traits::TValue for $value {})+
+    };
+}
+
+plain_values!(bool, char, i8, i16, i32, u8, u16, f32, f64);
+
+impl TValue for String {
+    fn sort_key(&self) -> Option<Vec<u8>> {
+        Some(ordered::encode_bytes(self.as_bytes()))
+    }
+    fn text(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+
+impl TValue for Vec<u8> {
+    fn sort_key(&self) -> Option<Vec<u8>> {
+        Some(ordered::encode_bytes(self))
+    }
+}
+
+impl TValue for u32 {
+    fn sort_key(&self) -> Option<Vec<u8>> {
+        Some(ordered::encode_u32(*self).to_vec())
+    }
+}
+
+impl TValue for u64 {
+    fn sort_key(&self) -> Option<Vec<u8>> {
+        Some(ordered::encode_u64(*self).to_vec())
+    }
+}
+
+impl TValue for i64 {
+    fn sort_key(&self) -> Option<Vec<u8>> {
+        Some(ordered::encode_i64(*self).to_vec())
+    }
+}
 
 pub trait TSymbol: Sized + Serialize + DeserializeOwned {
     fn serialize(&self) -> Vec<u8> {
@@ -24,11 +84,15 @@ pub trait TSymbol: Sized + Serialize + DeserializeOwned {
     fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
         Ok(bincode::deserialize(bytes)?)
     }
-    // Compare two symbols to determine a similarity score
-    // fn compare<G, W>(&self, other: &Self, graph: &G) -> Result<f64, Error>;
-    // where
-    // G: GraphInterface<W>
-    // W: Value<Symbol = Self>;
+    /// How alike two symbols are, from 0.0 for unrelated to 1.0 for the same, such that properties may be found by keys
+    /// which are similar rather than identical. By default, only identical symbols are alike
+    fn similarity(&self, other: &Self) -> f64 {
+        if TSymbol::serialize(self) == TSymbol::serialize(other) {
+            1.0
+        } else {
+            0.0
+        }
+    }
 }
 
 impl TSymbol for String {
@@ -40,7 +104,19 @@ impl TSymbol for String {
         Ok(Self::from_utf8_lossy(bytes).to_string())
     }
 }
-pub trait TProvenance {}
+/// A record of who asserted an entity or property, and perhaps how that may be verified
+pub trait TProvenance: Sized + Serialize + DeserializeOwned {
+    /// Whoever made the assertion, such that reads may be filtered down to those made by certain agents
+    type Agent: PartialEq;
+
+    fn agent(&self) -> &Self::Agent;
+    fn serialize(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+}
 
 pub trait GraphInterface<Sym, Val>
 where
@@ -55,4 +131,10 @@ where
         F: Fn(&Sym, &Val) -> Result<bool, Error>;
 }
 
-impl TProvenance for () {}
+impl TProvenance for () {
+    type Agent = ();
+
+    fn agent(&self) -> &() {
+        self
+    }
+}
@@ -0,0 +1,223 @@
+//! Walk the hypergraph outward from an entity, across the hyperedges which include it
+
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::{
+    adapter::StorageAdapter,
+    entity::{Member, MemberRole},
+    traits::{TProvenance, TSymbol, TValue},
+    EntityId, Error, Hypergraph,
+};
+
+/// Which way to cross directed hyperedges. Undirected hyperedges are crossed regardless
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// From the "from" side of a directed hyperedge to its "to" side
+    Forward,
+    /// From the "to" side of a directed hyperedge to its "from" side
+    Backward,
+    Both,
+}
+
+impl Direction {
+    /// Whether an entity playing this role in a hyperedge may step across it
+    fn departs(&self, role: MemberRole) -> bool {
+        matches!(
+            (self, role),
+            (_, MemberRole::Undirected)
+                | (Direction::Both, _)
+                | (Direction::Forward, MemberRole::From)
+                | (Direction::Backward, MemberRole::To)
+        )
+    }
+    /// Whether a member playing this role in a hyperedge may be arrived at
+    fn arrives(&self, role: MemberRole) -> bool {
+        matches!(
+            (self, role),
+            (_, MemberRole::Undirected)
+                | (Direction::Both, _)
+                | (Direction::Forward, MemberRole::To)
+                | (Direction::Backward, MemberRole::From)
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    BreadthFirst,
+    DepthFirst,
+}
+
+/// An entity reached by a traversal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Visit {
+    pub entity_id: EntityId,
+    /// The number of hyperedges crossed to get here. The start entity is at depth 0
+    pub depth: usize,
+    /// The hyperedge which was crossed to get here. None for the start entity
+    pub via: Option<EntityId>,
+}
+
+type StepFilter<'a, Sym, Val> = Box<dyn Fn(&Sym, &Val) -> Result<bool, Error> + 'a>;
+
+/// A lazy walk outward from a start entity. Each entity is visited at most once, so cycles are never followed.
+/// Created by `Hypergraph::traverse`
+/// ```
+/// use mindbase_hypergraph::{entity::{directed, vertex}, traverse::Direction, Hypergraph};
+/// let graph = Hypergraph::<_, String, String>::memory();
+/// let (_, a) = graph.insert(vertex(vec![])).unwrap();
+/// let (_, b) = graph.insert(vertex(vec![])).unwrap();
+/// graph.insert(directed(vec![], [a], [b])).unwrap();
+/// graph.insert(directed(vec![], [b], [a])).unwrap();
+///
+/// let visited: Vec<_> = graph.traverse(a).direction(Direction::Forward).map(|v| v.unwrap().entity_id).collect();
+/// assert_eq!(visited, vec![a, b]);
+/// ```
+pub struct Traverse<'a, Stor, Sym, Val, Prov>
+where
+    Sym: TSymbol,
+    Val: TValue,
+{
+    graph: &'a Hypergraph<Stor, Sym, Val, Prov>,
+    order: Order,
+    direction: Direction,
+    max_depth: Option<usize>,
+    filter: Option<StepFilter<'a, Sym, Val>>,
+    frontier: VecDeque<Visit>,
+    visited: BTreeSet<EntityId>,
+}
+
+impl<'a, Stor, Sym, Val, Prov> Traverse<'a, Stor, Sym, Val, Prov>
+where
+    Stor: StorageAdapter<Sym, Val, Prov>,
+    Sym: TSymbol,
+    Val: TValue,
+    Prov: TProvenance,
+{
+    pub(crate) fn new(graph: &'a Hypergraph<Stor, Sym, Val, Prov>, start: EntityId) -> Self {
+        let mut frontier = VecDeque::new();
+        frontier.push_back(Visit {
+            entity_id: start,
+            depth: 0,
+            via: None,
+        });
+
+        Self {
+            graph,
+            order: Order::BreadthFirst,
+            direction: Direction::Both,
+            max_depth: None,
+            filter: None,
+            frontier,
+            visited: BTreeSet::new(),
+        }
+    }
+
+    /// Breadth first is the default
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn depth_first(self) -> Self {
+        self.order(Order::DepthFirst)
+    }
+
+    /// Both directions is the default
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Cross no more than this many hyperedges away from the start entity
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Only cross hyperedges which have at least one property matching the filter
+    pub fn filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&Sym, &Val) -> Result<bool, Error> + 'a,
+    {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Every unvisited entity one step away from this one, in the order they should be visited
+    fn step(&self, visit: &Visit) -> Result<Vec<Visit>, Error> {
+        let mut next = Vec::new();
+
+        for adjacency in self.graph.get_memberships(&visit.entity_id)? {
+            if !self.direction.departs(adjacency.role) {
+                continue;
+            }
+            let hyperedge = self.graph.get(&adjacency.hyperedge)?;
+
+            if let Some(filter) = &self.filter {
+                let mut matched = false;
+                for prop in hyperedge.properties.iter() {
+                    if filter(&prop.key, &prop.value)? {
+                        matched = true;
+                        break;
+                    }
+                }
+                if !matched {
+                    continue;
+                }
+            }
+
+            for (Member { entity_id: member, .. }, role) in hyperedge.members() {
+                if !self.direction.arrives(role) || self.visited.contains(&member) || self.graph.is_removed(&member)? {
+                    continue;
+                }
+                next.push(Visit {
+                    entity_id: member,
+                    depth: visit.depth + 1,
+                    via: Some(adjacency.hyperedge),
+                });
+            }
+        }
+        Ok(next)
+    }
+}
+
+impl<'a, Stor, Sym, Val, Prov> Iterator for Traverse<'a, Stor, Sym, Val, Prov>
+where
+    Stor: StorageAdapter<Sym, Val, Prov>,
+    Sym: TSymbol,
+    Val: TValue,
+    Prov: TProvenance,
+{
+    type Item = Result<Visit, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let visit = match self.order {
+                Order::BreadthFirst => self.frontier.pop_front()?,
+                Order::DepthFirst => self.frontier.pop_back()?,
+            };
+            // Entities are marked as they are visited rather than as they are found, so that depth first
+            // really does go deep first. The same entity may be found along several paths before then
+            if !self.visited.insert(visit.entity_id) {
+                continue;
+            }
+
+            if !matches!(self.max_depth, Some(max) if visit.depth >= max) {
+                match self.step(&visit) {
+                    Ok(next) => match self.order {
+                        Order::BreadthFirst => self.frontier.extend(next),
+                        // Reversed, so that the first one found is the next one popped
+                        Order::DepthFirst => self.frontier.extend(next.into_iter().rev()),
+                    },
+                    Err(e) => {
+                        self.frontier.clear();
+                        return Some(Err(e));
+                    },
+                }
+            }
+
+            return Some(Ok(visit));
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use mindbase_hypergraph::{
+    adapter::sled::SledAdapter,
+    entity::{directed, undirected, vertex, Adjacency, MemberRole},
+    Hypergraph,
+};
+
+mod common;
+use common::prop;
+
+#[test]
+fn adjacencies() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    let graph: Hypergraph<_, String, String, ()> = Hypergraph::new(SledAdapter::open(tmpdir.path())?);
+
+    let (_, a) = graph.insert(vertex(vec![prop("name", "a")]))?;
+    let (_, b) = graph.insert(vertex(vec![prop("name", "b")]))?;
+    let (_, c) = graph.insert(vertex(vec![prop("name", "c")]))?;
+
+    let (_, x) = graph.insert(undirected(vec![prop("kind", "category")], [a, b]))?;
+    let (_, y) = graph.insert(directed(vec![prop("kind", "analogy")], [a], [b, c]))?;
+
+    // Crucially, hyperedges can also include other hyperedges
+    let (_, z) = graph.insert(directed(vec![prop("kind", "meta")], [x], [y]))?;
+
+    // Adjacencies are ordered by hyperedge id, and ULIDs minted in the same millisecond are not monotonic
+    let mut xy = vec![x, y];
+    xy.sort();
+
+    assert_eq!(graph.get_adjacencies(&a)?, xy);
+    assert_eq!(graph.get_adjacencies(&c)?, vec![y]);
+    assert_eq!(graph.get_adjacencies(&x)?, vec![z]);
+    assert_eq!(graph.get_adjacencies(&z)?, vec![]);
+
+    let mut memberships = graph.get_memberships(&b)?;
+    memberships.sort_by_key(|a| a.role);
+    assert_eq!(memberships, vec![
+        Adjacency {
+            hyperedge: x,
+            role: MemberRole::Undirected,
+            degree: 1.0,
+        },
+        Adjacency {
+            hyperedge: y,
+            role: MemberRole::To,
+            degree: 1.0,
+        },
+    ]);
+    assert_eq!(graph.get_memberships(&y)?, vec![Adjacency {
+        hyperedge: z,
+        role: MemberRole::To,
+        degree: 1.0,
+    }]);
+
+    let analogies = graph.get_adjacencies_matching(&a, |k, v| Ok(k == "kind" && v == "analogy"))?;
+    assert_eq!(analogies, vec![y]);
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use mindbase_hypergraph::{
+    adapter::{Cascade, ScanDirection, StorageAdapter},
+    entity::{vertex, Amendment, Property},
+    Error, Hypergraph,
+};
+
+#[macro_use]
+mod common;
+use common::prop;
+
+fn pairs(properties: Vec<Property<String, String>>) -> Vec<(String, String)> {
+    properties.into_iter().map(|p| (p.key, p.value)).collect()
+}
+
+fn amend<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String>,
+{
+    let s = |v: &str| v.to_string();
+
+    let (_, lamp) = graph.insert(vertex(vec![prop("name", "lamp"), prop("state", "off")]))?;
+    assert_eq!(graph.get_version(&lamp)?, 0);
+    assert_eq!(pairs(graph.get_properties(&lamp, Some(0))?), vec![
+        (s("name"), s("lamp")),
+        (s("state"), s("off"))
+    ]);
+
+    let version = graph.amend(&lamp, vec![
+        Amendment::Replace(prop("state", "on")),
+        Amendment::Append(prop("room", "kitchen")),
+    ])?;
+    assert_eq!(version, 1);
+
+    let version = graph.amend(&lamp, vec![Amendment::Retract(s("room"))])?;
+    assert_eq!(version, 2);
+    assert_eq!(graph.get_version(&lamp)?, 2);
+
+    // The current view
+    assert_eq!(pairs(graph.get_properties(&lamp, None)?), vec![
+        (s("name"), s("lamp")),
+        (s("state"), s("on"))
+    ]);
+
+    // And the history
+    assert_eq!(pairs(graph.get_properties(&lamp, Some(0))?), vec![
+        (s("name"), s("lamp")),
+        (s("state"), s("off"))
+    ]);
+    assert_eq!(pairs(graph.get_properties(&lamp, Some(1))?), vec![
+        (s("name"), s("lamp")),
+        (s("state"), s("on")),
+        (s("room"), s("kitchen"))
+    ]);
+    match graph.get_properties(&lamp, Some(3)) {
+        Err(Error::NotFound) => {}
+        other => panic!("expected Error::NotFound, got {:?}", other.map(pairs)),
+    }
+
+    // Only the current view is indexed
+    assert_eq!(graph.find_by_property(&s("state"), &s("off"))?, vec![]);
+    assert_eq!(graph.find_by_property(&s("state"), &s("on"))?, vec![lamp]);
+    assert_eq!(graph.find_by_property(&s("room"), &s("kitchen"))?, vec![]);
+    assert_eq!(graph.range_by_property(&s("state"), .., ScanDirection::Forward, None)?, vec![lamp]);
+    assert_eq!(graph.range_by_property(&s("room"), .., ScanDirection::Forward, None)?, vec![]);
+
+    graph.remove(&lamp, Cascade::Remove)?;
+    match graph.amend(&lamp, vec![Amendment::Retract(s("state"))]) {
+        Err(Error::Removed) => {}
+        other => panic!("expected Error::Removed, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+fn concurrent_amend<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String> + Sync,
+{
+    let s = |v: &str| v.to_string();
+    let (_, counter) = graph.insert(vertex(vec![prop("name", "counter")]))?;
+
+    // Every amendment gets a version of its own, however they interleave
+    let mut versions: Vec<u32> = std::thread::scope(|scope| {
+        let graph = &graph;
+        let handles: Vec<_> = (0..4)
+            .map(|thread| {
+                scope.spawn(move || {
+                    (0..10)
+                        .map(|i| graph.amend(&counter, vec![Amendment::Replace(prop("tick", &format!("{}.{}", thread, i)))]))
+                        .collect::<Result<Vec<_>, Error>>()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect::<Result<Vec<_>, Error>>()
+    })?
+    .into_iter()
+    .flatten()
+    .collect();
+    versions.sort_unstable();
+    assert_eq!(versions, (1..=40).collect::<Vec<_>>());
+    assert_eq!(graph.get_version(&counter)?, 40);
+
+    // Each version holds exactly one tick, and only the last is indexed
+    let mut ticks = Vec::new();
+    for version in 1..=40 {
+        let properties = graph.get_properties(&counter, Some(version))?;
+        assert_eq!(properties.len(), 2);
+        ticks.push(properties[1].value.clone());
+    }
+    ticks.sort();
+    ticks.dedup();
+    assert_eq!(ticks.len(), 40);
+    let last = graph.get_properties(&counter, None)?[1].value.clone();
+    assert_eq!(graph.find_by_property(&s("tick"), &last)?, vec![counter]);
+    assert_eq!(graph.range_by_property(&s("tick"), .., ScanDirection::Forward, None)?, vec![counter]);
+
+    Ok(())
+}
+
+memory_and_sled!(amend, concurrent_amend);
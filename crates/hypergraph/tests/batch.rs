@@ -0,0 +1,69 @@
+use mindbase_hypergraph::{
+    adapter::{sled::SledAdapter, Cascade, StorageAdapter},
+    entity::{directed, vertex, Property},
+    Error, Hypergraph,
+};
+
+fn name(value: &str) -> Vec<Property<String, String>> {
+    vec![Property {
+        key: "name".to_string(),
+        value: value.to_string(),
+    }]
+}
+
+#[test]
+fn batch() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    let graph: Hypergraph<_, String, String> = Hypergraph::new(SledAdapter::open(tmpdir.path())?);
+
+    let mut batch = graph.batch();
+    let doc = batch.insert(vertex(name("document")));
+    let root = batch.insert(vertex(name("root")));
+    let edge = batch.insert(directed(name("root element"), [doc], [root]));
+
+    // Nothing is visible until the batch is committed
+    assert_eq!(graph.find_by_property(&"name".to_string(), &"root".to_string())?, vec![]);
+
+    let committed = batch.commit()?;
+    assert_eq!(committed.iter().map(|(_, id)| *id).collect::<Vec<_>>(), vec![doc, root, edge]);
+
+    assert_eq!(graph.find_by_property(&"name".to_string(), &"root".to_string())?, vec![root]);
+    assert_eq!(graph.get_adjacencies(&doc)?, vec![edge]);
+    assert_eq!(graph.get_adjacencies(&root)?, vec![edge]);
+
+    Ok(())
+}
+
+fn duplicate_ids<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String>,
+{
+    let (_, lamp) = graph.insert(vertex(name("lamp")))?;
+    let (_, desk) = graph.insert(vertex(name("desk")))?;
+    graph.remove(&desk, Cascade::Remove)?;
+    let fresh = graph.batch().insert(vertex(name("unused")));
+
+    // An id repeated within the batch, or already live, or removed, fails the whole batch
+    for repeated in [vec![fresh, fresh], vec![fresh, lamp], vec![desk]].iter() {
+        let entities = repeated.iter().map(|id| (*id, vertex(name("chair")), None)).collect();
+        assert!(matches!(graph.adapter().insert_batch(entities), Err(Error::DuplicateId(_))));
+    }
+    assert_eq!(graph.find_by_property(&"name".to_string(), &"chair".to_string())?, vec![]);
+    assert_eq!(graph.find_by_property(&"name".to_string(), &"lamp".to_string())?, vec![lamp]);
+
+    graph.adapter().insert_batch(vec![(fresh, vertex(name("chair")), None)])?;
+    assert_eq!(graph.find_by_property(&"name".to_string(), &"chair".to_string())?, vec![fresh]);
+
+    Ok(())
+}
+
+#[test]
+fn memory() -> Result<(), std::io::Error> {
+    duplicate_ids(Hypergraph::memory())
+}
+
+#[test]
+fn sled() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    duplicate_ids(Hypergraph::new(SledAdapter::open(tmpdir.path())?))
+}
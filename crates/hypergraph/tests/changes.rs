@@ -0,0 +1,205 @@
+use std::time::UNIX_EPOCH;
+
+use futures::{executor::block_on, StreamExt};
+use mindbase_hypergraph::{
+    adapter::{kv::KvAdapter, sled::SledAdapter, Cascade},
+    changes::{ChangeId, ChangeKind, Filter, Subscription, INBOX_CAPACITY},
+    entity::{vertex, Amendment},
+    kv::Store,
+    EntityId, Error, Hypergraph,
+};
+
+#[macro_use]
+mod common;
+use common::prop;
+
+fn s(symbol: &str) -> String {
+    symbol.to_string()
+}
+
+/// The kind, entity and symbols of the next n changes
+fn take(subscription: &mut Subscription<String>, n: usize) -> Result<Vec<(ChangeKind, EntityId, Vec<String>)>, std::io::Error> {
+    let mut out = Vec::new();
+    for change in subscription.take(n) {
+        let change = change?;
+        out.push((change.kind, change.entity_id, change.symbols));
+    }
+    Ok(out)
+}
+
+fn changes<S>(graph: Hypergraph<KvAdapter<S, String, String>, String, String>) -> Result<(), std::io::Error>
+where
+    S: Store + Send + Sync + 'static,
+    S::Tree: Send + Sync,
+{
+    let mut everything = graph.subscribe(Filter::Any)?;
+    let mut temperatures = graph.subscribe(Filter::Symbols(vec![s("temperature")]))?;
+
+    let (_, kitchen) = graph.insert(vertex(vec![prop("name", "kitchen"), prop("temperature", "21")]))?;
+    let (_, hallway) = graph.insert(vertex(vec![prop("name", "hallway")]))?;
+    graph.amend(&hallway, vec![Amendment::Append(prop("temperature", "18"))])?;
+    graph.amend(&kitchen, vec![Amendment::Replace(prop("name", "galley"))])?;
+    graph.remove(&kitchen, Cascade::Remove)?;
+
+    assert_eq!(take(&mut everything, 5)?, vec![
+        (ChangeKind::Insert, kitchen, vec![s("name"), s("temperature")]),
+        (ChangeKind::Insert, hallway, vec![s("name")]),
+        (ChangeKind::Update, hallway, vec![s("temperature")]),
+        (ChangeKind::Update, kitchen, vec![s("name")]),
+        // Replacing a property moves it to the end
+        (ChangeKind::Remove, kitchen, vec![s("temperature"), s("name")]),
+    ]);
+    assert!(everything.try_recv().is_none());
+
+    // Only the changes which touch a temperature, and only the matching symbols
+    assert_eq!(take(&mut temperatures, 3)?, vec![
+        (ChangeKind::Insert, kitchen, vec![s("temperature")]),
+        (ChangeKind::Update, hallway, vec![s("temperature")]),
+        (ChangeKind::Remove, kitchen, vec![s("temperature")]),
+    ]);
+    assert!(temperatures.try_recv().is_none());
+
+    // Catch up from a given change, then carry on with new ones
+    let all: Vec<ChangeId> = graph
+        .subscribe_after(Filter::Any, ChangeId::at(UNIX_EPOCH))?
+        .take(5)
+        .map(|change| change.map(|c| c.id))
+        .collect::<Result<_, _>>()?;
+    assert!(all.windows(2).all(|w| w[0] < w[1]));
+
+    let mut resumed = graph.subscribe_after(Filter::Any, all[2])?;
+    let (_, porch) = graph.insert(vertex(vec![prop("name", "porch")]))?;
+    assert_eq!(take(&mut resumed, 3)?, vec![
+        (ChangeKind::Update, kitchen, vec![s("name")]),
+        (ChangeKind::Remove, kitchen, vec![s("temperature"), s("name")]),
+        (ChangeKind::Insert, porch, vec![s("name")]),
+    ]);
+
+    // As an async stream, fed from another thread
+    let graph = std::sync::Arc::new(graph);
+    let mut stream = graph.subscribe(Filter::Any)?.into_stream();
+    let writer = {
+        let graph = graph.clone();
+        std::thread::spawn(move || graph.insert(vertex(vec![prop("name", "attic")])).map(|(_, id)| id))
+    };
+    let change = block_on(stream.next()).unwrap()?;
+    assert_eq!(change.entity_id, writer.join().unwrap()?);
+
+    // Subscriptions end along with the hypergraph
+    let mut ending = graph.subscribe(Filter::Any)?;
+    drop(graph);
+    assert!(ending.next().is_none());
+    assert!(block_on(stream.next()).is_none());
+
+    Ok(())
+}
+
+fn retention<S>(graph: Hypergraph<KvAdapter<S, String, String>, String, String>) -> Result<(), std::io::Error>
+where
+    S: Store,
+{
+    // Subscribing to a symbol does not intern it, but it is matched once it is
+    let mut humidities = graph.subscribe(Filter::Symbols(vec![s("humidity")]))?;
+    assert_eq!(graph.get_symbol_ix(&s("humidity"))?, None);
+    let (_, cellar) = graph.insert(vertex(vec![prop("name", "cellar"), prop("humidity", "80")]))?;
+    assert_eq!(take(&mut humidities, 1)?, vec![(ChangeKind::Insert, cellar, vec![s("humidity")])]);
+
+    // A subscriber which falls too far behind is told how many changes it missed, and then carries on
+    let mut everything = graph.subscribe(Filter::Any)?;
+    let mut batch = graph.batch();
+    for _ in 0..INBOX_CAPACITY + 2 {
+        batch.insert(vertex(vec![prop("name", "room")]));
+    }
+    batch.commit()?;
+    let seen: Vec<ChangeId> = everything
+        .by_ref()
+        .take(INBOX_CAPACITY)
+        .map(|change| change.map(|c| c.id))
+        .collect::<Result<_, _>>()?;
+    assert!(matches!(everything.next(), Some(Err(Error::Lagged(2)))));
+    let (_, garage) = graph.insert(vertex(vec![prop("name", "garage")]))?;
+    assert_eq!(everything.next().unwrap()?.entity_id, garage);
+
+    // Resubscribing after the last change seen catches up on those missed
+    let resumed = graph.subscribe_after(Filter::Any, seen[INBOX_CAPACITY - 1])?;
+    assert_eq!(resumed.take(3).map(|change| change.map(|c| c.entity_id)).collect::<Result<Vec<_>, _>>()?[2], garage);
+
+    // Changes before the cut-off are trimmed, and may no longer be caught up on
+    assert_eq!(graph.trim_changes(seen[1])?, 2);
+    assert!(matches!(graph.subscribe_after(Filter::Any, ChangeId::at(UNIX_EPOCH)), Err(Error::Trimmed(id)) if id == seen[0]));
+    let mut trimmed = graph.subscribe_after(Filter::Any, seen[0])?;
+    assert_eq!(trimmed.next().unwrap()?.id, seen[1]);
+    assert_eq!(graph.trim_changes(seen[1])?, 0);
+
+    Ok(())
+}
+
+/// Subscribing part way through a stream of writes misses none of them, and yields none twice
+fn subscribe_while_writing<S>(graph: Hypergraph<KvAdapter<S, String, String>, String, String>) -> Result<(), std::io::Error>
+where
+    S: Store + Send + Sync + 'static,
+    S::Tree: Send + Sync,
+{
+    let graph = std::sync::Arc::new(graph);
+    let writers: Vec<_> = (0..4)
+        .map(|_| {
+            let graph = graph.clone();
+            std::thread::spawn(move || -> Result<(), Error> {
+                for i in 0..100 {
+                    graph.insert(vertex(vec![prop("n", &i.to_string())]))?;
+                }
+                Ok(())
+            })
+        })
+        .collect();
+
+    while graph.iter().count() < 50 {
+        std::thread::yield_now();
+    }
+    let subscription = graph.subscribe_after(Filter::Any, ChangeId::at(UNIX_EPOCH))?;
+    for writer in writers {
+        writer.join().unwrap()?;
+    }
+
+    // Every change has been delivered by the time its write returns
+    let mut ids = Vec::new();
+    while let Some(change) = subscription.try_recv() {
+        ids.push(change?.id);
+    }
+    assert_eq!(ids.len(), 400);
+    assert!(ids.windows(2).all(|w| w[0] < w[1]));
+
+    Ok(())
+}
+
+memory_and_sled!(retention, changes, subscribe_while_writing);
+
+#[test]
+fn restart() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    let db = sled::open(tmpdir.path().join("mindbase.sled"))?;
+
+    let (last_seen, missed) = {
+        let graph: Hypergraph<_, String, String> = Hypergraph::new(SledAdapter::from_store(db.clone())?);
+        let mut subscription = graph.subscribe(Filter::Any)?;
+        graph.insert(vertex(vec![prop("name", "seen")]))?;
+        let last_seen = subscription.next().unwrap()?.id;
+        // The consumer goes away, and misses this one
+        drop(subscription);
+        let (_, missed) = graph.insert(vertex(vec![prop("name", "missed")]))?;
+        (last_seen, missed)
+    };
+
+    let graph: Hypergraph<_, String, String> = Hypergraph::new(SledAdapter::from_store(db)?);
+    let mut subscription = graph.subscribe_after(Filter::Any, last_seen)?;
+    let (_, after) = graph.insert(vertex(vec![prop("name", "after")]))?;
+
+    let caught_up = subscription.next().unwrap()?;
+    assert_eq!(caught_up.entity_id, missed);
+    let next = subscription.next().unwrap()?;
+    assert_eq!(next.entity_id, after);
+    // Ids carry on in order after reopening
+    assert!(next.id > caught_up.id && caught_up.id > last_seen);
+
+    Ok(())
+}
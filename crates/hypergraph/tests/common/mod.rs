@@ -0,0 +1,32 @@
+//! Helpers shared by the integration tests. Each test crate uses only some of them
+#![allow(dead_code, unused_macros)]
+
+use mindbase_hypergraph::entity::Property;
+
+pub fn prop(key: &str, value: &str) -> Property<String, String> {
+    Property {
+        key: key.to_string(),
+        value: value.to_string(),
+    }
+}
+
+/// Define `memory` and `sled` tests, which run each of the given fns against a fresh hypergraph of their own
+macro_rules! memory_and_sled {
+    ($($test:ident),+ $(,)?) => {
+        #[test]
+        fn memory() -> Result<(), std::io::Error> {
+            $($test(mindbase_hypergraph::Hypergraph::memory())?;)+
+            Ok(())
+        }
+
+        #[test]
+        fn sled() -> Result<(), std::io::Error> {
+            $({
+                let tmpdir = tempfile::tempdir()?;
+                let adapter = mindbase_hypergraph::adapter::sled::SledAdapter::open(tmpdir.path())?;
+                $test(mindbase_hypergraph::Hypergraph::new(adapter))?;
+            })+
+            Ok(())
+        }
+    };
+}
@@ -0,0 +1,93 @@
+use mindbase_hypergraph::{
+    adapter::{Cascade, StorageAdapter},
+    entity::{directed, undirected, vertex},
+    export::Format,
+    Hypergraph,
+};
+
+#[macro_use]
+mod common;
+use common::prop;
+
+fn export<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String>,
+{
+    let (_, a) = graph.insert(vertex(vec![prop("name", "a"), prop("tag", "x"), prop("tag", "y")]))?;
+    // Ids are ordered by the millisecond they were made in, so that a's is the least, and its links are written first
+    std::thread::sleep(std::time::Duration::from_millis(2));
+    let (_, b) = graph.insert(vertex(vec![prop("name", "b")]))?;
+    let (_, c) = graph.insert(vertex(vec![prop("name", "<c & \"d\">")]))?;
+    let (_, ab) = graph.insert(directed(vec![prop("verb", "next")], [a], [b]))?;
+    // A hyperedge from a hyperedge
+    let (_, because) = graph.insert(directed(vec![prop("verb", "because")], [ab], [c]))?;
+    let (_, group) = graph.insert(undirected(vec![], [(a, 0.5), (c, 1.0)]))?;
+    let (_, gone) = graph.insert(vertex(vec![prop("name", "gone")]))?;
+    graph.remove(&gone, Cascade::Remove)?;
+
+    let mut dot = Vec::new();
+    graph.export(Format::Dot, &mut dot)?;
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.starts_with("digraph hypergraph {\n"));
+    assert!(dot.contains(&format!(
+        "    \"{}\" [\"name\"=\"a\", \"tag\"=\"x, y\", kind=\"vertex\", label=\"{}\\nname: a\\ntag: x, y\", shape=ellipse];\n",
+        a.full(),
+        a.short()
+    )));
+    assert!(dot.contains(r#""name"="<c & \"d\">""#));
+    assert!(dot.contains(&format!("    \"{}\" -> \"{}\" [role=\"from\", degree=1];\n", a.full(), ab.full())));
+    assert!(dot.contains(&format!("    \"{}\" -> \"{}\" [role=\"to\", degree=1];\n", ab.full(), b.full())));
+    assert!(dot.contains(&format!("    \"{}\" -> \"{}\" [role=\"from\", degree=1];\n", ab.full(), because.full())));
+    assert!(dot.contains(&format!(
+        "    \"{}\" -> \"{}\" [role=\"undirected\", degree=0.5, dir=none];\n",
+        group.full(),
+        a.full()
+    )));
+    assert!(!dot.contains(&gone.full()));
+    assert_eq!(dot.matches(" -> ").count(), 6);
+
+    let mut graphml = Vec::new();
+    graph.export(Format::GraphMl, &mut graphml)?;
+    let graphml = String::from_utf8(graphml).unwrap();
+    assert!(graphml.contains(r#"<key id="p0" for="node" attr.name="name" attr.type="string"/>"#));
+    assert!(graphml.contains(&format!("<node id=\"{}\">\n      <data key=\"kind\">directed</data>", because.full())));
+    assert!(graphml.contains("<data key=\"p0\">&lt;c &amp; &quot;d&quot;&gt;</data>"));
+    assert!(graphml.contains(&format!(
+        "source=\"{}\" target=\"{}\" directed=\"false\">\n      <data key=\"role\">undirected</data>\n      <data key=\"degree\">0.5</data>",
+        group.full(),
+        a.full()
+    )));
+    assert_eq!(graphml.matches("<node ").count(), 6);
+    assert_eq!(graphml.matches("<edge ").count(), 6);
+
+    let mut gexf = Vec::new();
+    graph.export(Format::Gexf, &mut gexf)?;
+    let gexf = String::from_utf8(gexf).unwrap();
+    assert!(gexf.contains(r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#));
+    assert!(gexf.contains(&format!("<node id=\"{}\" label=\"{}\">", b.full(), b.short())));
+    assert!(gexf.contains(r#"<attvalue for="p1" value="x, y"/>"#));
+    assert!(gexf.contains(&format!(
+        "<edge id=\"l0\" source=\"{}\" target=\"{}\" type=\"directed\" weight=\"1\">",
+        a.full(),
+        ab.full()
+    )));
+    assert_eq!(gexf.matches("<node ").count(), 6);
+    assert_eq!(gexf.matches("<edge ").count(), 6);
+
+    // Written the same way every time
+    let mut again = Vec::new();
+    graph.export(Format::Gexf, &mut again)?;
+    assert_eq!(String::from_utf8(again).unwrap(), gexf);
+
+    // Only the links between members of the subgraph
+    let mut dot = Vec::new();
+    graph.export_subgraph(vec![a, ab, b, a, gone], Format::Dot, &mut dot)?;
+    let dot = String::from_utf8(dot).unwrap();
+    assert_eq!(dot.matches(" [").count(), 5);
+    assert!(dot.contains(&format!("    \"{}\" -> \"{}\" [role=\"to\", degree=1];\n", ab.full(), b.full())));
+    assert!(!dot.contains(&c.full()) && !dot.contains(&gone.full()));
+
+    Ok(())
+}
+
+memory_and_sled!(export);
@@ -0,0 +1,152 @@
+use mindbase_hypergraph::{
+    adapter::{
+        kv::{fsck::Problem, KvAdapter},
+        sled::SledAdapter,
+        Cascade,
+    },
+    adapter::ScanDirection,
+    entity::{directed, vertex},
+    kv::{Pair, Store, Tree},
+    text::TextQuery,
+    EntityId, Error, Hypergraph,
+};
+
+#[macro_use]
+mod common;
+use common::prop;
+
+fn raw(entity_id: &EntityId) -> Vec<u8> {
+    base64::decode_config(entity_id.full(), base64::STANDARD_NO_PAD).unwrap()
+}
+
+fn fsck<S: Store>(graph: Hypergraph<KvAdapter<S, String, String>, String, String>) -> Result<(), std::io::Error> {
+    let (_, a) = graph.insert(vertex(vec![prop("name", "Alice")]))?;
+    let (_, b) = graph.insert(vertex(vec![prop("name", "Bob"), prop("bio", &"x".repeat(100))]))?;
+    let (_, c) = graph.insert(vertex(vec![prop("name", "Carol")]))?;
+    let (ab_ix, ab) = graph.insert(directed(vec![prop("kind", "knows")], [a], [b]))?;
+    let (_, ac) = graph.insert(directed(vec![prop("kind", "knows")], [a], [c]))?;
+    graph.remove(&c, Cascade::FlagDangling)?;
+
+    let report = graph.adapter().check()?;
+    assert!(report.is_clean(), "{:?}", report.problems);
+    assert_eq!(report.entities, 4);
+    assert_eq!(report.unreferenced_values, 0);
+
+    // Knock out and scramble some derived entries, behind the adapter's back
+    let store = graph.adapter().store();
+    store.open_tree("hypergraph::entity_id_to_ix")?.remove(raw(&b))?;
+    store.open_tree("hypergraph::hyperedge_by_entity_id")?.insert([7u8; 16], [0u8; 25])?;
+    store.open_tree("hypergraph::dangling_members")?.remove(raw(&ac))?;
+    store.open_tree("hypergraph::entity_by_property_value")?.insert(
+        store.open_tree("hypergraph::entity_by_property_value")?.iter().next().unwrap()?.0,
+        ab_ix.to_be_bytes(),
+    )?;
+    // and append a record which can't be decoded
+    store.open_tree("hypergraph::entity_storage")?.insert((ab_ix + 10).to_be_bytes(), b"garbage")?;
+
+    assert!(matches!(graph.get(&b), Err(Error::NotFound)));
+
+    let report = graph.adapter().check()?;
+    assert_eq!(report.entities, 4);
+    assert_eq!(report.next_entity_ix, ab_ix + 11);
+    assert!(report.problems.contains(&Problem::MissingEntry {
+        tree: "hypergraph::entity_id_to_ix",
+        key: raw(&b),
+    }));
+    assert!(report.problems.contains(&Problem::OrphanEntry {
+        tree: "hypergraph::hyperedge_by_entity_id",
+        key: vec![7u8; 16],
+    }));
+    assert!(report.problems.iter().any(|p| matches!(p, Problem::WrongEntry { tree: "hypergraph::entity_by_property_value", .. })));
+    assert!(report.problems.contains(&Problem::Undecodable {
+        tree: "hypergraph::entity_storage",
+        key: (ab_ix + 10).to_be_bytes().to_vec(),
+    }));
+    assert!(!report.repaired);
+
+    let report = graph.adapter().repair()?;
+    assert!(report.repaired);
+
+    // Only the undecodable record remains, as there is nothing to rebuild it from
+    let report = graph.adapter().check()?;
+    assert_eq!(report.problems, vec![Problem::Undecodable {
+        tree: "hypergraph::entity_storage",
+        key: (ab_ix + 10).to_be_bytes().to_vec(),
+    }]);
+    assert!(!report.problems[0].is_repairable());
+
+    assert_eq!(graph.get_properties(&b, None)?[0].value, "Bob");
+    assert_eq!(graph.get_adjacencies(&b)?, vec![ab]);
+    assert_eq!(graph.find_by_property(&"name".to_string(), &"Alice".to_string())?, vec![a]);
+    assert_eq!(graph.get_dangling_members(&ac)?, vec![c]);
+
+    // New entities are allocated past the bad record, rather than over it
+    let (d_ix, _) = graph.insert(vertex(vec![prop("name", "Dave")]))?;
+    assert!(d_ix > ab_ix + 10);
+
+    Ok(())
+}
+
+fn contents<T: Tree>(tree: &T) -> Result<Vec<Pair<Vec<u8>>>, Error> {
+    tree.iter().map(|rec| rec.map(|(key, value)| (key.to_vec(), value.to_vec()))).collect()
+}
+
+fn missing_references<S: Store>(graph: Hypergraph<KvAdapter<S, String, String>, String, String>) -> Result<(), std::io::Error> {
+    let bio = "tall ".repeat(20);
+    let (_, b) = graph.insert(vertex(vec![prop("name", "Bob"), prop("bio", &bio)]))?;
+    let store = graph.adapter().store();
+    let text_lengths = store.open_tree("hypergraph::text_lengths")?;
+    let lengths = contents(&text_lengths)?;
+
+    // Lose the bio, and the range entries of both properties
+    let values = store.open_tree("hypergraph::value_storage")?;
+    let (hash, _) = values.iter().next().unwrap()?;
+    values.remove(&hash)?;
+    let ranges = store.open_tree("hypergraph::entity_by_property_range")?;
+    for rec in ranges.iter() {
+        ranges.remove(rec?.0)?;
+    }
+
+    // The entries which can't be derived without the bio are left alone, and those which can be are still expected
+    let report = graph.adapter().check()?;
+    assert!(report.problems.iter().any(|p| matches!(p, Problem::MissingValue { entity_id, .. } if *entity_id == b)));
+    assert_eq!(
+        report.problems.iter().filter(|p| matches!(p, Problem::MissingEntry { tree: "hypergraph::entity_by_property_range", .. })).count(),
+        1
+    );
+    assert!(!report.problems.iter().any(|p| matches!(p, Problem::OrphanEntry { .. } | Problem::WrongEntry { .. })));
+
+    graph.adapter().repair()?;
+    let found = graph.range_by_property(&"name".to_string(), "A".to_string().."C".to_string(), ScanDirection::Forward, None)?;
+    assert_eq!(found, vec![b]);
+    let hits = graph.search(&TextQuery::parse("tall"))?;
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].entity_id, b);
+    assert_eq!(contents(&text_lengths)?, lengths);
+
+    Ok(())
+}
+
+memory_and_sled!(fsck, missing_references);
+
+#[test]
+fn reopen() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    let db = sled::open(tmpdir.path().join("mindbase.sled"))?;
+    let (a, b) = {
+        let graph: Hypergraph<_, String, String> = Hypergraph::new(SledAdapter::from_store(db.clone())?);
+        let (_, a) = graph.insert(vertex(vec![prop("name", "Alice")]))?;
+        let (_, b) = graph.insert(vertex(vec![prop("name", "Bob")]))?;
+        (a, b)
+    };
+
+    // Entities inserted after reopening must not land on top of those from before
+    let graph: Hypergraph<_, String, String> = Hypergraph::new(SledAdapter::from_store(db)?);
+    let (_, c) = graph.insert(vertex(vec![prop("name", "Carol")]))?;
+    for (entity_id, name) in [(a, "Alice"), (b, "Bob"), (c, "Carol")].iter() {
+        assert_eq!(graph.get_properties(entity_id, None)?[0].value, *name);
+    }
+    assert!(graph.adapter().check()?.is_clean());
+
+    Ok(())
+}
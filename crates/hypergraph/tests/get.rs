@@ -0,0 +1,78 @@
+use mindbase_hypergraph::{
+    adapter::{sled::SledAdapter, Cascade, StorageAdapter},
+    entity::{directed, vertex, MemberRole, Property},
+    traits::GraphInterface,
+    Error, Hypergraph,
+};
+
+mod common;
+use common::prop;
+
+fn pairs(properties: Vec<Property<String, String>>) -> Vec<(String, String)> {
+    properties.into_iter().map(|p| (p.key, p.value)).collect()
+}
+
+fn get<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String>,
+{
+    let s = |v: &str| v.to_string();
+
+    let (a_ix, a) = graph.insert(vertex(vec![prop("name", "a"), prop("color", "red")]))?;
+    let (_, b) = graph.insert(vertex(vec![prop("name", "b")]))?;
+    let (x_ix, x) = graph.insert(directed(vec![prop("kind", "link")], [a], [b]))?;
+
+    let entity = graph.get(&a)?;
+    assert!(entity.is_vertex());
+    assert_eq!(pairs(entity.properties), vec![(s("name"), s("a")), (s("color"), s("red"))]);
+    assert_eq!(pairs(graph.get_by_ix(&a_ix)?.properties), vec![(s("name"), s("a")), (s("color"), s("red"))]);
+
+    let edge = graph.get_by_ix(&x_ix)?;
+    assert!(!edge.is_vertex());
+    assert_eq!(edge.members(), vec![(a.into(), MemberRole::From), (b.into(), MemberRole::To)]);
+    assert_eq!(pairs(graph.get(&x)?.properties), vec![(s("kind"), s("link"))]);
+
+    graph.remove(&x, Cascade::Remove)?;
+    match graph.get(&x) {
+        Err(Error::Removed) => {}
+        other => panic!("expected Error::Removed, got {:?}", other),
+    }
+    match graph.get_by_ix(&x_ix) {
+        Err(Error::NotFound) => {}
+        other => panic!("expected Error::NotFound, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+/// Reads through `GraphInterface`, which the data adapters are written against
+fn via_interface<G>(graph: &G) -> Result<(), std::io::Error>
+where
+    G: GraphInterface<String, String>,
+{
+    let a = graph.insert(vertex(vec![prop("name", "a")]))?;
+    let b = graph.insert(vertex(vec![prop("name", "b")]))?;
+    let x = graph.insert(directed(vec![prop("kind", "link")], [a], [b]))?;
+
+    assert_eq!(graph.get(&b)?.properties[0].value, "b");
+    assert_eq!(graph.get_adjacencies(&a)?, vec![x]);
+    assert_eq!(graph.get_adjacencies_matching(&a, |k, v| Ok(k == "kind" && v == "link"))?, vec![x]);
+    assert_eq!(graph.get_adjacencies_matching(&a, |k, _| Ok(k == "name"))?, vec![]);
+
+    Ok(())
+}
+
+#[test]
+fn memory() -> Result<(), std::io::Error> {
+    get(Hypergraph::memory())?;
+    via_interface(&Hypergraph::<_, String, String>::memory())
+}
+
+#[test]
+fn sled() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    get(Hypergraph::new(SledAdapter::open(tmpdir.path())?))?;
+
+    let tmpdir = tempfile::tempdir()?;
+    via_interface(&Hypergraph::<_, String, String>::new(SledAdapter::open(tmpdir.path())?))
+}
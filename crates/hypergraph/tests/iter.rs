@@ -0,0 +1,91 @@
+use std::time::{Duration, SystemTime};
+
+use mindbase_hypergraph::{
+    adapter::{Cascade, StorageAdapter},
+    entity::{directed, undirected, vertex},
+    EntityId, Hypergraph,
+};
+
+#[macro_use]
+mod common;
+use common::prop;
+
+fn ids<I, E>(iter: I) -> Result<Vec<EntityId>, std::io::Error>
+where
+    I: Iterator<Item = Result<(EntityId, E), mindbase_hypergraph::Error>>,
+{
+    Ok(iter.map(|r| r.map(|(entity_id, _)| entity_id)).collect::<Result<_, _>>()?)
+}
+
+fn sorted(mut entity_ids: Vec<EntityId>) -> Vec<EntityId> {
+    entity_ids.sort();
+    entity_ids
+}
+
+fn iterate<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String>,
+{
+    let (_, a) = graph.insert(vertex(vec![prop("name", "a")]))?;
+    let (_, b) = graph.insert(vertex(vec![prop("name", "b")]))?;
+    let (_, c) = graph.insert(vertex(vec![prop("name", "c")]))?;
+    let (_, x) = graph.insert(undirected(vec![prop("kind", "group")], [a, b, c]))?;
+
+    // Ids embed their creation time at millisecond resolution, so step past the current millisecond
+    std::thread::sleep(Duration::from_millis(5));
+    let midpoint = SystemTime::now();
+    std::thread::sleep(Duration::from_millis(5));
+
+    let (_, d) = graph.insert(vertex(vec![prop("name", "d")]))?;
+    let (_, y) = graph.insert(directed(vec![prop("kind", "link")], [a], [d]))?;
+    graph.remove(&c, Cascade::FlagDangling)?;
+
+    // Everything but the removed entity, in id order
+    let all = ids(graph.iter())?;
+    assert_eq!(all, sorted(vec![a, b, d, x, y]));
+    assert_eq!(ids(graph.iter_vertices())?, sorted(vec![a, b, d]));
+    assert_eq!(ids(graph.iter_edges())?, sorted(vec![x, y]));
+
+    let recent = ids(graph.iter_created_between(midpoint, SystemTime::now() + Duration::from_secs(1)))?;
+    assert_eq!(recent, sorted(vec![d, y]));
+    let earlier = ids(graph.iter_created_between(SystemTime::now() - Duration::from_secs(60), midpoint))?;
+    assert_eq!(earlier, sorted(vec![a, b, x]));
+    assert_eq!(ids(graph.iter_created_between(midpoint, midpoint))?, vec![]);
+    assert!(d.created() >= midpoint - Duration::from_millis(1));
+
+    // Page through two at a time, resuming from the cursor with a fresh iterator each time
+    let mut paged = Vec::new();
+    let mut cursor = None;
+    loop {
+        let mut page = graph.iter().page_size(2);
+        if let Some(cursor) = cursor {
+            page = page.after(cursor);
+        }
+        let chunk: Vec<_> = page.by_ref().take(2).collect::<Result<_, _>>()?;
+        if chunk.is_empty() {
+            break;
+        }
+        paged.extend(chunk.into_iter().map(|(entity_id, _)| entity_id));
+        cursor = page.cursor();
+    }
+    assert_eq!(paged, all);
+
+    // Resuming narrows a time window, but never widens it
+    let resumed = ids(graph
+        .iter_created_between(midpoint, SystemTime::now() + Duration::from_secs(1))
+        .after(all[0]))?;
+    assert_eq!(resumed, sorted(vec![d, y]));
+
+    let mut dump = Vec::new();
+    graph.dump_entities(&mut dump)?;
+    let dump = String::from_utf8(dump).unwrap();
+    assert_eq!(dump.lines().count(), 5);
+    assert!(dump.contains(&format!("{} = Vertex \"name\": \"d\"", d)));
+
+    graph.remove(&x, Cascade::Remove)?;
+    assert_eq!(ids(graph.iter_edges())?, vec![y]);
+
+    Ok(())
+}
+
+memory_and_sled!(iterate);
@@ -0,0 +1,121 @@
+use mindbase_hypergraph::{
+    kv::{memory::MemoryStore, toboggan::TobogganStore, write_once, Store, Transaction, Tree},
+    Error,
+};
+
+fn append(_key: &[u8], last: Option<&[u8]>, operand: &[u8]) -> Option<Vec<u8>> {
+    let mut out = last.map(|l| l.to_vec()).unwrap_or_default();
+    out.extend_from_slice(operand);
+    Some(out)
+}
+
+fn remove_all(_key: &[u8], _last: Option<&[u8]>, _operand: &[u8]) -> Option<Vec<u8>> {
+    None
+}
+
+fn keys<T: Tree>(iter: T::Iter) -> Result<Vec<Vec<u8>>, std::io::Error> {
+    Ok(iter.map(|rec| rec.map(|(k, _)| k.to_vec())).collect::<Result<_, _>>()?)
+}
+
+fn kv<S: Store>(store: S) -> Result<(), std::io::Error> {
+    let letters = store.open_tree("letters")?;
+    let counts = store.open_tree("counts")?;
+
+    let mut tx = Transaction::new();
+    tx.insert(&letters, "b", "2");
+    tx.insert(&letters, "a", "1");
+    tx.insert(&letters, "ab", "12");
+    // Merges see the writes before them in the same transaction, including to other trees
+    tx.merge(&counts, "x", "1", append);
+    tx.merge(&counts, "x", "2", append);
+    tx.merge(&counts, "y", "1", append);
+    tx.merge(&counts, "y", "", remove_all);
+    store.apply(tx)?;
+
+    assert_eq!(letters.get("a")?.as_deref(), Some(&b"1"[..]));
+    assert_eq!(counts.get("x")?.as_deref(), Some(&b"12"[..]));
+    assert!(!counts.contains_key("y")?);
+
+    assert_eq!(keys::<S::Tree>(letters.iter())?, vec![b"a".to_vec(), b"ab".to_vec(), b"b".to_vec()]);
+    assert_eq!(keys::<S::Tree>(letters.scan_prefix("a"))?, vec![b"a".to_vec(), b"ab".to_vec()]);
+    assert_eq!(keys::<S::Tree>(letters.range::<&[u8], _>(&b"ab"[..]..))?, vec![b"ab".to_vec(), b"b".to_vec()]);
+    assert_eq!(keys::<S::Tree>(letters.range::<&[u8], _>(&b"b"[..]..&b"a"[..]))?, Vec::<Vec<u8>>::new());
+    assert_eq!(letters.iter().next_back().transpose()?.map(|(k, _)| k.to_vec()), Some(b"b".to_vec()));
+    assert_eq!(letters.last()?.map(|(k, _)| k.to_vec()), Some(b"b".to_vec()));
+
+    letters.insert([0xff, 0xff], "")?;
+    letters.insert([0xff], "")?;
+    let mut prefixed = letters.scan_prefix([0xff]);
+    assert_eq!(prefixed.next_back().transpose()?.map(|(k, _)| k.to_vec()), Some(vec![0xff, 0xff]));
+    assert_eq!(keys::<S::Tree>(prefixed)?, vec![vec![0xff]]);
+    letters.remove([0xff])?;
+    letters.remove([0xff, 0xff])?;
+
+    // Merging outside of a transaction returns the prior value
+    assert!(letters.merge("c", "3", write_once)?.is_none());
+    assert_eq!(letters.merge("c", "4", write_once)?.as_deref(), Some(&b"3"[..]));
+    assert_eq!(letters.get("c")?.as_deref(), Some(&b"3"[..]));
+
+    letters.remove("c")?;
+    assert!(letters.get("c")?.is_none());
+
+    // Expectations are checked before any of the writes, and if one is not met, none of the writes are applied
+    let mut tx = Transaction::new();
+    tx.insert(&letters, "c", "3");
+    tx.expect(&letters, "c", None);
+    tx.expect(&counts, "x", Some(&b"12"[..]));
+    store.apply(tx)?;
+    assert_eq!(letters.get("c")?.as_deref(), Some(&b"3"[..]));
+
+    let mut tx = Transaction::new();
+    tx.insert(&letters, "d", "4");
+    tx.expect(&letters, "c", None);
+    assert!(matches!(store.apply(tx), Err(Error::Conflict)));
+    assert!(letters.get("d")?.is_none());
+
+    let first = store.generate_id()?;
+    assert!(store.generate_id()? > first);
+
+    Ok(())
+}
+
+/// Iterators which read as they go see what is written ahead of them, from either end. Toboggan's in-memory trees are copied
+/// when iteration begins, so this holds only for the others
+fn lazy<S: Store>(store: S) -> Result<(), std::io::Error> {
+    let letters = store.open_tree("letters")?;
+    letters.insert("a", "1")?;
+    letters.insert("ab", "12")?;
+    letters.insert("b", "2")?;
+
+    let mut iter = letters.iter();
+    assert_eq!(iter.next().transpose()?.map(|(k, _)| k.to_vec()), Some(b"a".to_vec()));
+    letters.insert("aa", "11")?;
+    letters.insert("ba", "21")?;
+    assert_eq!(iter.next_back().transpose()?.map(|(k, _)| k.to_vec()), Some(b"ba".to_vec()));
+    assert_eq!(keys::<S::Tree>(iter)?, vec![b"aa".to_vec(), b"ab".to_vec(), b"b".to_vec()]);
+
+    Ok(())
+}
+
+#[test]
+fn memory() -> Result<(), std::io::Error> {
+    kv(MemoryStore::default())?;
+    lazy(MemoryStore::default())
+}
+
+#[test]
+fn sled() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    kv(sled::open(tmpdir.path())?)?;
+
+    let tmpdir = tempfile::tempdir()?;
+    lazy(sled::open(tmpdir.path())?)
+}
+
+#[test]
+fn toboggan() -> Result<(), std::io::Error> {
+    kv(TobogganStore::new(toboggan_kv::adapter::BTreeAdapter::new()))?;
+
+    let tmpdir = tempfile::tempdir()?;
+    kv(TobogganStore::new(toboggan_kv::adapter::SledAdapter::open(tmpdir.path())?))
+}
@@ -0,0 +1,59 @@
+use mindbase_hypergraph::{
+    adapter::{Cascade, StorageAdapter},
+    entity::{directed, undirected, vertex, Adjacency, Member, MemberRole},
+    Hypergraph,
+};
+
+#[macro_use]
+mod common;
+use common::prop;
+
+fn membership<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String>,
+{
+    let (_, reading) = graph.insert(vertex(vec![prop("name", "reading")]))?;
+    let (_, kitchen) = graph.insert(vertex(vec![prop("name", "kitchen")]))?;
+    let (_, hallway) = graph.insert(vertex(vec![prop("name", "hallway")]))?;
+
+    // This reading belongs to the kitchen with 0.8 confidence, and to the hallway with 0.2
+    let (_, x) = graph.insert(directed(vec![prop("kind", "located")], [reading], [(kitchen, 0.8), (hallway, 0.2)]))?;
+    // Bare ids are full members
+    let (_, y) = graph.insert(undirected(vec![prop("kind", "rooms")], [kitchen, hallway]))?;
+
+    assert_eq!(graph.get(&x)?.members(), vec![
+        (
+            Member {
+                entity_id: reading,
+                degree: 1.0
+            },
+            MemberRole::From
+        ),
+        ((kitchen, 0.8).into(), MemberRole::To),
+        ((hallway, 0.2).into(), MemberRole::To),
+    ]);
+
+    let mut memberships = graph.get_memberships(&hallway)?;
+    memberships.sort_by_key(|a| a.role);
+    assert_eq!(memberships, vec![
+        Adjacency {
+            hyperedge: y,
+            role: MemberRole::Undirected,
+            degree: 1.0,
+        },
+        Adjacency {
+            hyperedge: x,
+            role: MemberRole::To,
+            degree: 0.2,
+        },
+    ]);
+
+    // Index entries are removed along with the degree they were indexed with
+    graph.remove(&x, Cascade::Remove)?;
+    assert_eq!(graph.get_adjacencies(&kitchen)?, vec![y]);
+    assert_eq!(graph.get_adjacencies(&reading)?, vec![]);
+
+    Ok(())
+}
+
+memory_and_sled!(membership);
@@ -0,0 +1,125 @@
+use serde::Serialize;
+
+use mindbase_hypergraph::{
+    adapter::{kv::KvAdapter, memory::MemoryAdapter, sled::SledAdapter},
+    entity::{Member, MemberRole},
+    kv::{memory::MemoryStore, Store, Tree},
+    migrate::{self, FORMAT_VERSION, MIGRATIONS, UNVERSIONED},
+    traits::{TSymbol, TValue},
+    EntityId, Error, Hypergraph,
+};
+
+/// Entity records as they were written before the format was versioned
+#[derive(Serialize)]
+struct UnversionedEntity([u8; 16], Vec<(Vec<u8>, Vec<u8>)>, UnversionedInner);
+
+#[derive(Serialize)]
+#[allow(dead_code)]
+enum UnversionedInner {
+    Vertex,
+    Edge(Vec<[u8; 16]>),
+    DirectedEdge(Vec<[u8; 16]>, Vec<[u8; 16]>),
+}
+
+fn prop(key: &str, value: &str) -> (Vec<u8>, Vec<u8>) {
+    (TSymbol::serialize(&key.to_string()), TValue::serialize(&value.to_string()))
+}
+
+/// Write records directly into the store, in the layout which predates versioning
+fn write_unversioned<S: Store>(store: &S) -> Result<(), std::io::Error> {
+    let entity_storage = store.open_tree("hypergraph::entity_storage")?;
+    let entity_id_to_ix = store.open_tree("hypergraph::entity_id_to_ix")?;
+
+    let records = [
+        UnversionedEntity([1; 16], vec![prop("name", "hallway")], UnversionedInner::Vertex),
+        UnversionedEntity([2; 16], vec![prop("name", "kitchen"), prop("note", &"long ".repeat(20))], UnversionedInner::Vertex),
+        UnversionedEntity([3; 16], vec![prop("kind", "door")], UnversionedInner::DirectedEdge(vec![[1; 16]], vec![[2; 16]])),
+    ];
+    for (entity_ix, record) in records.iter().enumerate() {
+        entity_storage.insert((entity_ix as u64).to_be_bytes(), bincode::serialize(record).unwrap())?;
+        entity_id_to_ix.insert(record.0, (entity_ix as u64).to_be_bytes())?;
+    }
+    Ok(())
+}
+
+fn migrate<S: Store>(store: S) -> Result<(), std::io::Error> {
+    write_unversioned(&store)?;
+    assert_eq!(migrate::format_version(&store)?, Some(UNVERSIONED));
+
+    // Refused until migrated
+    match KvAdapter::<S, String, String>::from_store(store.clone()) {
+        Err(Error::UnsupportedFormat(UNVERSIONED)) => {},
+        Err(e) => panic!("expected UnsupportedFormat, got {:?}", e),
+        Ok(_) => panic!("expected UnsupportedFormat"),
+    }
+
+    let (adapter, applied) = KvAdapter::<S, String, String>::migrate_store(store.clone())?;
+    assert_eq!(applied, MIGRATIONS.iter().collect::<Vec<_>>());
+    assert_eq!(migrate::format_version(&store)?, Some(FORMAT_VERSION));
+
+    // Ids are preserved, and everything is interned and indexed as though it had been inserted
+    let graph = Hypergraph::new(adapter);
+    let hallway = EntityId::from_slice(&[1; 16])?;
+    let kitchen = EntityId::from_slice(&[2; 16])?;
+    let door = EntityId::from_slice(&[3; 16])?;
+
+    let properties = graph.get_properties(&kitchen, None)?;
+    assert_eq!(properties[1].value, "long ".repeat(20));
+    assert_eq!(graph.find_by_property(&"name".to_string(), &"hallway".to_string())?, vec![hallway]);
+    assert_eq!(graph.get_adjacencies(&kitchen)?, vec![door]);
+    assert_eq!(graph.get(&door)?.members(), vec![
+        (Member::from(hallway), MemberRole::From),
+        (Member::from(kitchen), MemberRole::To)
+    ]);
+    assert_eq!(graph.iter().count(), 3);
+
+    // Nothing more to do, and it may be opened as usual
+    drop(graph);
+    let (_, applied) = KvAdapter::<S, String, String>::migrate_store(store.clone())?;
+    assert!(applied.is_empty());
+    let graph = Hypergraph::new(KvAdapter::<S, String, String>::from_store(store)?);
+    graph.get(&hallway)?;
+
+    Ok(())
+}
+
+#[test]
+fn memory() -> Result<(), std::io::Error> {
+    migrate(MemoryStore::default())
+}
+
+#[test]
+fn sled() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    migrate(sled::open(tmpdir.path().join("mindbase.sled"))?)?;
+
+    // and by path, where there is nothing to migrate in a new database
+    let tmpdir = tempfile::tempdir()?;
+    let (_, applied) = SledAdapter::<String, String>::migrate(tmpdir.path())?;
+    assert!(applied.is_empty());
+    Ok(())
+}
+
+#[test]
+fn versions() -> Result<(), std::io::Error> {
+    // New stores are stamped with the current version
+    let store = MemoryStore::default();
+    assert_eq!(migrate::format_version(&store)?, None);
+    MemoryAdapter::<String, String>::from_store(store.clone())?;
+    assert_eq!(migrate::format_version(&store)?, Some(FORMAT_VERSION));
+
+    // Stores from the future can be neither opened nor migrated
+    let future = FORMAT_VERSION + 1;
+    store.open_tree("hypergraph::meta")?.insert("format_version", future.to_be_bytes())?;
+    assert!(matches!(MemoryAdapter::<String, String>::from_store(store.clone()), Err(Error::UnsupportedFormat(v)) if v == future));
+    assert!(matches!(MemoryAdapter::<String, String>::migrate_store(store), Err(Error::UnsupportedFormat(v)) if v == future));
+
+    // Every earlier version is brought all the way up to date, and the current one needs nothing
+    for migration in MIGRATIONS {
+        assert_eq!(migrate::migrations_from(migration.from)?.last().map(|m| m.to), Some(FORMAT_VERSION));
+    }
+    assert!(migrate::migrations_from(FORMAT_VERSION)?.is_empty());
+    assert!(matches!(migrate::migrations_from(future), Err(Error::UnsupportedFormat(v)) if v == future));
+
+    Ok(())
+}
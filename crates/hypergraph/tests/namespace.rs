@@ -0,0 +1,155 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use mindbase_hypergraph::{
+    adapter::{kv::KvAdapter, sled::SledAdapter},
+    entity::vertex,
+    kv::{memory::MemoryStore, Store},
+    namespace::{Namespaces, COPY_CHUNK, DEFAULT_NAMESPACE},
+    text::TextQuery,
+    Error, Hypergraph,
+};
+
+mod common;
+use common::prop;
+
+fn namespaces<S: Store>(namespaces: Namespaces<S>) -> Result<(), std::io::Error> {
+    assert!(namespaces.list()?.is_empty());
+
+    let house: Hypergraph<_, String, String> = Hypergraph::new(namespaces.create("house")?);
+    let garden: Hypergraph<_, String, String> = Hypergraph::new(namespaces.create("garden")?);
+    let (_, lamp) = house.insert(vertex(vec![prop("name", "Lamp")]))?;
+    let (_, hose) = garden.insert(vertex(vec![prop("name", "Hose")]))?;
+
+    // Separate entities, indexes and symbols
+    assert!(matches!(garden.get(&lamp), Err(Error::NotFound)));
+    assert!(matches!(house.get(&hose), Err(Error::NotFound)));
+    assert_eq!(house.find_by_property(&"name".to_string(), &"Hose".to_string())?, vec![]);
+    assert_eq!(garden.search(&TextQuery::parse("hose"))?.len(), 1);
+    assert_eq!(house.get_symbol_ix(&"name".to_string())?, Some(0));
+    assert_eq!(garden.get_symbol_ix(&"name".to_string())?, Some(0));
+    assert_eq!(namespaces.list()?, vec!["garden", "house"]);
+
+    assert!(matches!(namespaces.create::<String, String, ()>("house"), Err(Error::AlreadyExists)));
+    assert!(matches!(namespaces.open::<String, String, ()>("shed"), Err(Error::NotFound)));
+    assert!(matches!(namespaces.create::<String, String, ()>("a:b"), Err(Error::InvalidNamespace(_))));
+    assert!(matches!(namespaces.create::<String, String, ()>(""), Err(Error::InvalidNamespace(_))));
+    // Failing to open a namespace does not create it
+    assert_eq!(namespaces.list()?, vec!["garden", "house"]);
+
+    // The default namespace is the store as a plain adapter would see it
+    let default: Hypergraph<_, String, String> = Hypergraph::new(KvAdapter::from_store(namespaces.store().clone())?);
+    let (_, sofa) = default.insert(vertex(vec![prop("name", "Sofa")]))?;
+    assert_eq!(namespaces.list()?, vec![DEFAULT_NAMESPACE, "garden", "house"]);
+    let reopened: Hypergraph<_, String, String> = Hypergraph::new(namespaces.open(DEFAULT_NAMESPACE)?);
+    assert_eq!(reopened.get_properties(&sofa, None)?[0].value, "Sofa");
+    assert!(matches!(house.get(&sofa), Err(Error::NotFound)));
+
+    // Copies are independent of the original
+    namespaces.copy("house", "cabin")?;
+    assert!(matches!(namespaces.copy("house", "garden"), Err(Error::AlreadyExists)));
+    assert!(matches!(namespaces.copy("shed", "barn"), Err(Error::NotFound)));
+    let cabin: Hypergraph<_, String, String> = Hypergraph::new(namespaces.open("cabin")?);
+    assert_eq!(cabin.get_properties(&lamp, None)?[0].value, "Lamp");
+    let (_, stove) = cabin.insert(vertex(vec![prop("name", "Stove")]))?;
+    assert!(matches!(house.get(&stove), Err(Error::NotFound)));
+    assert!(cabin.adapter().check()?.is_clean());
+
+    // Snapshots of a namespace hold only its own trees
+    let mut snapshot = Vec::new();
+    let manifest = cabin.adapter().snapshot(&mut snapshot)?;
+    assert_eq!(manifest.entities, 2);
+    assert!(manifest.trees.iter().all(|tree| tree.name.starts_with("hypergraph::")));
+
+    assert!(namespaces.drop("house")?);
+    assert!(!namespaces.drop("house")?);
+    assert_eq!(namespaces.list()?, vec!["cabin", DEFAULT_NAMESPACE, "garden"]);
+    assert!(matches!(namespaces.open::<String, String, ()>("house"), Err(Error::NotFound)));
+    assert_eq!(cabin.get_properties(&lamp, None)?[0].value, "Lamp");
+    assert_eq!(garden.get_properties(&hose, None)?[0].value, "Hose");
+
+    Ok(())
+}
+
+#[test]
+fn memory() -> Result<(), std::io::Error> {
+    namespaces(Namespaces::new(MemoryStore::default()))
+}
+
+#[test]
+fn sled() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    namespaces(Namespaces::sled(tmpdir.path())?)
+}
+
+#[test]
+fn reopen() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    let db = sled::open(tmpdir.path().join("mindbase.sled"))?;
+    let lamp = {
+        let namespaces = Namespaces::new(db.clone());
+        let house: Hypergraph<_, String, String> = Hypergraph::new(namespaces.create("house")?);
+        house.insert(vertex(vec![prop("name", "Lamp")]))?.1
+    };
+
+    let namespaces = Namespaces::new(db.clone());
+    assert_eq!(namespaces.list()?, vec!["house"]);
+    let house: Hypergraph<_, String, String> = Hypergraph::new(namespaces.open("house")?);
+    assert_eq!(house.get_properties(&lamp, None)?[0].value, "Lamp");
+
+    // None of it is visible to a plain adapter on the same database
+    let default: Hypergraph<_, String, String> = Hypergraph::new(SledAdapter::from_store(db)?);
+    assert!(matches!(default.get(&lamp), Err(Error::NotFound)));
+    assert!(default.adapter().check()?.is_clean());
+
+    Ok(())
+}
+
+#[test]
+fn copy_while_writing() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    let namespaces = Namespaces::sled(tmpdir.path())?;
+    let house: Hypergraph<_, String, String> = Hypergraph::new(namespaces.create("house")?);
+    // Enough for several chunks of entity storage
+    for i in 0..2 * COPY_CHUNK {
+        house.insert(vertex(vec![prop("n", &i.to_string())]))?;
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let stop = stop.clone();
+        std::thread::spawn(move || -> Result<(), Error> {
+            while !stop.load(Ordering::SeqCst) {
+                house.insert(vertex(vec![prop("name", "Lamp")]))?;
+            }
+            Ok(())
+        })
+    };
+
+    // Each copy either stops with a conflict, leaving nothing behind, or is of the house at one point in time
+    let copied = namespaces.copy("house", "cabin");
+    stop.store(true, Ordering::SeqCst);
+    writer.join().unwrap()?;
+    match copied {
+        Ok(()) => {
+            let cabin: Hypergraph<_, String, String> = Hypergraph::new(namespaces.open("cabin")?);
+            assert!(cabin.adapter().check()?.is_clean());
+            assert!(namespaces.drop("cabin")?);
+        },
+        Err(Error::Conflict) => {
+            assert!(namespaces.namespace("cabin")?.tree_names()?.is_empty());
+        },
+        Err(e) => return Err(e.into()),
+    }
+
+    // Without writers, it always gets through
+    namespaces.copy("house", "cabin")?;
+    let house: Hypergraph<_, String, String> = Hypergraph::new(namespaces.open("house")?);
+    let cabin: Hypergraph<_, String, String> = Hypergraph::new(namespaces.open("cabin")?);
+    assert_eq!(cabin.iter().count(), house.iter().count());
+    assert!(cabin.adapter().check()?.is_clean());
+
+    Ok(())
+}
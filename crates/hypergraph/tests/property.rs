@@ -0,0 +1,101 @@
+use mindbase_hypergraph::{
+    adapter::{ScanDirection, StorageAdapter},
+    entity::{directed, vertex, Property},
+    plain_values, Hypergraph,
+};
+use serde::{Deserialize, Serialize};
+
+#[macro_use]
+mod common;
+use common::prop;
+
+/// A value type of the caller's own, which has no order
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+struct Colour(u8, u8, u8);
+
+plain_values!(Colour);
+
+fn find_by_property<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String>,
+{
+    let (_, lamp) = graph.insert(vertex(vec![prop("name", "lamp"), prop("room", "kitchen")]))?;
+    let (_, fan) = graph.insert(vertex(vec![prop("name", "fan"), prop("room", "kitchen")]))?;
+    let (_, on) = graph.insert(directed(vec![prop("name", "switched on")], [lamp], [fan]))?;
+
+    let s = |v: &str| v.to_string();
+
+    assert_eq!(graph.find_by_property(&s("name"), &s("lamp"))?, vec![lamp]);
+    assert_eq!(graph.find_by_property(&s("name"), &s("switched on"))?, vec![on]);
+    assert_eq!(graph.find_by_property(&s("name"), &s("kitchen"))?, vec![]);
+    assert_eq!(graph.find_by_property(&s("nam"), &s("elamp"))?, vec![]);
+
+    let mut kitchen = graph.find_by_property(&s("room"), &s("kitchen"))?;
+    kitchen.sort();
+    let mut expected = vec![lamp, fan];
+    expected.sort();
+    assert_eq!(kitchen, expected);
+
+    Ok(())
+}
+
+fn range_by_property<Stor>(graph: Hypergraph<Stor, String, u32>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, u32>,
+{
+    let reading = |v: u32| {
+        vec![Property {
+            key: "reading".to_string(),
+            value: v,
+        }]
+    };
+
+    let mut ids = Vec::new();
+    for v in [300, 2, 70000, 2, 0, 16].iter() {
+        ids.push(graph.insert(vertex(reading(*v)))?.1);
+    }
+    let (v300, v2a, v70000, v2b, v0, v16) = (ids[0], ids[1], ids[2], ids[3], ids[4], ids[5]);
+
+    let key = "reading".to_string();
+    let other = "other".to_string();
+
+    assert_eq!(graph.range_by_property(&key, .., ScanDirection::Forward, None)?, vec![
+        v0, v2a, v2b, v16, v300, v70000
+    ]);
+    assert_eq!(graph.range_by_property(&key, 2..300, ScanDirection::Forward, None)?, vec![v2a, v2b, v16]);
+    assert_eq!(graph.range_by_property(&key, 2..=300, ScanDirection::Reverse, None)?, vec![
+        v300, v16, v2b, v2a
+    ]);
+    assert_eq!(graph.range_by_property(&key, 1.., ScanDirection::Reverse, Some(2))?, vec![v70000, v300]);
+    assert_eq!(graph.range_by_property(&key, ..=2, ScanDirection::Forward, Some(2))?, vec![v0, v2a]);
+    assert_eq!(graph.range_by_property(&key, 17..300, ScanDirection::Forward, None)?, vec![]);
+    let (lo, hi) = (2, 300);
+    assert_eq!(graph.range_by_property(&key, hi..lo, ScanDirection::Forward, None)?, vec![]);
+    assert_eq!(graph.range_by_property(&other, .., ScanDirection::Forward, None)?, vec![]);
+
+    Ok(())
+}
+
+fn plain_values<Stor>(graph: Hypergraph<Stor, String, Colour>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, Colour>,
+{
+    let colour = |v: Colour| {
+        vec![Property {
+            key: "colour".to_string(),
+            value: v,
+        }]
+    };
+    let (_, red) = graph.insert(vertex(colour(Colour(255, 0, 0))))?;
+    graph.insert(vertex(colour(Colour(0, 0, 255))))?;
+
+    let key = "colour".to_string();
+    assert_eq!(graph.find_by_property(&key, &Colour(255, 0, 0))?, vec![red]);
+    assert_eq!(graph.get_properties(&red, None)?[0].value, Colour(255, 0, 0));
+    // Left out of the range index
+    assert_eq!(graph.range_by_property(&key, .., ScanDirection::Forward, None)?, vec![]);
+
+    Ok(())
+}
+
+memory_and_sled!(find_by_property, range_by_property, plain_values);
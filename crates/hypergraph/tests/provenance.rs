@@ -0,0 +1,108 @@
+use mindbase_hypergraph::{
+    adapter::{memory::MemoryAdapter, sled::SledAdapter, StorageAdapter},
+    entity::{vertex, Amendment, Property},
+    traits::TProvenance,
+    Error, Hypergraph,
+};
+use serde::{Deserialize, Serialize};
+
+#[macro_use]
+mod common;
+use common::prop;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Asserted {
+    agent: String,
+    signature: Vec<u8>,
+}
+
+impl TProvenance for Asserted {
+    type Agent = String;
+
+    fn agent(&self) -> &String {
+        &self.agent
+    }
+}
+
+fn by(agent: &str) -> Asserted {
+    Asserted {
+        agent: agent.to_string(),
+        signature: agent.bytes().rev().collect(),
+    }
+}
+
+fn pairs(properties: Vec<Property<String, String>>) -> Vec<(String, String)> {
+    properties.into_iter().map(|p| (p.key, p.value)).collect()
+}
+
+fn agents(provenance: &[Option<Asserted>]) -> Vec<Option<&str>> {
+    provenance.iter().map(|p| p.as_ref().map(|p| p.agent.as_str())).collect()
+}
+
+fn provenance<Stor>(graph: Hypergraph<Stor, String, String, Asserted>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String, Asserted>,
+{
+    let s = |v: &str| v.to_string();
+
+    let (_, lamp) = graph.insert_as(vertex(vec![prop("name", "lamp"), prop("state", "off")]), &by("ingest-bot"))?;
+    let (entity, attribution) = graph.get_attributed(&lamp)?;
+    assert_eq!(entity.properties.len(), 2);
+    assert_eq!(attribution.entity, Some(by("ingest-bot")));
+    assert_eq!(agents(&attribution.properties), vec![Some("ingest-bot"), Some("ingest-bot")]);
+
+    // Amended properties are attributed to whoever amended them, and the rest keep their original provenance
+    graph.amend_as(&lamp, vec![Amendment::Replace(prop("state", "on"))], &by("alice"))?;
+    graph.amend(&lamp, vec![Amendment::Append(prop("room", "kitchen"))])?;
+
+    let (_, attribution) = graph.get_attributed(&lamp)?;
+    assert_eq!(attribution.entity, Some(by("ingest-bot")));
+    assert_eq!(agents(&attribution.properties), vec![Some("ingest-bot"), Some("alice"), None]);
+
+    let asserting = [s("ingest-bot"), s("alice")];
+    assert_eq!(pairs(graph.get_asserted_by(&lamp, &asserting)?.properties), vec![
+        (s("name"), s("lamp")),
+        (s("state"), s("on"))
+    ]);
+    assert_eq!(pairs(graph.get_asserted_by(&lamp, &asserting[..1])?.properties), vec![(s("name"), s("lamp"))]);
+    match graph.get_asserted_by(&lamp, &[s("alice")]) {
+        Err(Error::NotAsserted) => {}
+        other => panic!("expected Error::NotAsserted, got {:?}", other),
+    }
+
+    // Entities inserted without provenance are asserted by nobody
+    let (_, anonymous) = graph.insert(vertex(vec![prop("name", "anonymous")]))?;
+    assert!(graph.get_attributed(&anonymous)?.1.entity.is_none());
+    match graph.get_asserted_by(&anonymous, &asserting) {
+        Err(Error::NotAsserted) => {}
+        other => panic!("expected Error::NotAsserted, got {:?}", other),
+    }
+
+    let mut batch = graph.batch();
+    let fan = batch.insert_as(vertex(vec![prop("name", "fan")]), by("bob"));
+    batch.commit()?;
+    assert_eq!(graph.get_attributed(&fan)?.1.entity, Some(by("bob")));
+
+    Ok(())
+}
+
+memory_and_sled!(provenance);
+
+/// Provenance is carried along when copying between adapters
+#[test]
+fn snapshot() -> Result<(), std::io::Error> {
+    let memory = MemoryAdapter::<String, String, Asserted>::new();
+    let (_, lamp) = memory.insert(vertex(vec![prop("name", "lamp")]), Some(&by("ingest-bot")))?;
+    memory.amend(&lamp, vec![Amendment::Append(prop("state", "on"))], Some(&by("alice")))?;
+
+    let tmpdir = tempfile::tempdir()?;
+    let sled = SledAdapter::open(tmpdir.path())?;
+    memory.save_to_sled(&sled)?;
+
+    let restored = Hypergraph::new(MemoryAdapter::from_sled(&sled)?);
+    let (_, attribution) = restored.get_attributed(&lamp)?;
+    assert_eq!(attribution.entity, Some(by("ingest-bot")));
+    assert_eq!(agents(&attribution.properties), vec![Some("ingest-bot"), Some("alice")]);
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use mindbase_hypergraph::{
+    adapter::{kv::KvAdapter, Cascade, ScanDirection, StorageAdapter},
+    entity::{directed, undirected, vertex},
+    kv::Store,
+    text::TextQuery,
+    Error, Hypergraph,
+};
+
+#[macro_use]
+mod common;
+use common::prop;
+
+fn cascade_remove<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String>,
+{
+    let (_, a) = graph.insert(vertex(vec![prop("name", "a")]))?;
+    let (_, b) = graph.insert(vertex(vec![prop("name", "b")]))?;
+    let (_, x) = graph.insert(undirected(vec![prop("kind", "pair")], [a, b]))?;
+    let (_, y) = graph.insert(directed(vec![prop("kind", "meta")], [x], [b]))?;
+
+    let s = |v: &str| v.to_string();
+
+    let mut removed = graph.remove(&a, Cascade::Remove)?;
+    removed.sort();
+    let mut expected = vec![a, x, y];
+    expected.sort();
+    assert_eq!(removed, expected);
+
+    assert!(graph.is_removed(&a)?);
+    assert!(graph.is_removed(&y)?);
+    assert!(!graph.is_removed(&b)?);
+
+    // Every index entry for the removed entities is gone
+    assert_eq!(graph.find_by_property(&s("name"), &s("a"))?, vec![]);
+    assert_eq!(graph.find_by_property(&s("kind"), &s("pair"))?, vec![]);
+    assert_eq!(graph.find_by_property(&s("name"), &s("b"))?, vec![b]);
+    assert_eq!(graph.range_by_property(&s("name"), .., ScanDirection::Forward, None)?, vec![b]);
+    assert_eq!(graph.range_by_property(&s("kind"), .., ScanDirection::Forward, None)?, vec![]);
+    assert_eq!(graph.get_adjacencies(&b)?, vec![]);
+
+    match graph.remove(&a, Cascade::Remove) {
+        Err(Error::Removed) => {}
+        other => panic!("expected Error::Removed, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+fn flag_dangling<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String>,
+{
+    let (_, a) = graph.insert(vertex(vec![prop("name", "a")]))?;
+    let (_, b) = graph.insert(vertex(vec![prop("name", "b")]))?;
+    let (_, x) = graph.insert(undirected(vec![prop("kind", "pair")], [a, b]))?;
+
+    let s = |v: &str| v.to_string();
+
+    assert_eq!(graph.remove(&a, Cascade::FlagDangling)?, vec![a]);
+    assert!(!graph.is_removed(&x)?);
+    assert_eq!(graph.get_dangling_members(&x)?, vec![a]);
+    assert_eq!(graph.get_dangling_members(&b)?, vec![]);
+    assert_eq!(graph.find_by_property(&s("kind"), &s("pair"))?, vec![x]);
+    assert_eq!(graph.get_adjacencies(&b)?, vec![x]);
+
+    // Removing the hyperedge clears its dangling flags, and its adjacencies
+    assert_eq!(graph.remove(&x, Cascade::FlagDangling)?, vec![x]);
+    assert_eq!(graph.get_dangling_members(&x)?, vec![]);
+    assert_eq!(graph.get_adjacencies(&b)?, vec![]);
+
+    Ok(())
+}
+
+fn concurrent_remove<S>(graph: Hypergraph<KvAdapter<S, String, String>, String, String>) -> Result<(), std::io::Error>
+where
+    S: Store + Sync,
+    S::Tree: Sync,
+{
+    let s = |v: &str| v.to_string();
+
+    for _ in 0..20 {
+        let (_, a) = graph.insert(vertex(vec![prop("name", "anvil")]))?;
+        let (_, b) = graph.insert(vertex(vec![prop("name", "bellows")]))?;
+        let (_, x) = graph.insert(undirected(vec![prop("kind", "forge")], [a, b]))?;
+
+        // Whichever removal comes first, nothing is removed twice
+        let results: Vec<Result<Vec<_>, Error>> = std::thread::scope(|scope| {
+            let graph = &graph;
+            let handles: Vec<_> = vec![a, a, x, x]
+                .into_iter()
+                .map(|id| scope.spawn(move || graph.remove(&id, Cascade::Remove)))
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        let mut removed: Vec<_> = results.iter().filter_map(|result| result.as_ref().ok()).flatten().copied().collect();
+        removed.sort();
+        let mut expected = vec![a, x];
+        expected.sort();
+        assert_eq!(removed, expected);
+        assert!(results.iter().all(|result| matches!(result, Ok(_) | Err(Error::Removed))));
+
+        assert_eq!(graph.get_adjacencies(&b)?, vec![]);
+        graph.remove(&b, Cascade::Remove)?;
+    }
+
+    assert_eq!(graph.find_by_property(&s("kind"), &s("forge"))?, vec![]);
+    assert!(graph.search(&TextQuery::parse("anvil"))?.is_empty());
+    assert!(graph.adapter().check()?.is_clean());
+
+    Ok(())
+}
+
+memory_and_sled!(cascade_remove, flag_dangling, concurrent_remove);
@@ -0,0 +1,84 @@
+use mindbase_hypergraph::{
+    adapter::{kv::KvAdapter, Cascade, StorageAdapter},
+    entity::vertex,
+    kv::{memory::MemoryStore, Store, Tree},
+    migrate::{self, FORMAT_VERSION},
+    EntityId, Error, Hypergraph,
+};
+
+#[macro_use]
+mod common;
+use common::prop;
+
+fn resolve<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String>,
+{
+    let (_, a) = graph.insert(vertex(vec![prop("name", "a")]))?;
+    let (_, b) = graph.insert(vertex(vec![prop("name", "b")]))?;
+    let (_, c) = graph.insert(vertex(vec![prop("name", "c")]))?;
+
+    // The forms which are printed, and the start of either
+    assert_eq!(graph.resolve_id(&a.full())?, a);
+    assert_eq!(graph.resolve_id(&format!("{}", b))?, b);
+    assert_eq!(graph.resolve_id(&c.short()[..5])?, c);
+    for len in 1..=6 {
+        assert!(graph.find_by_id_prefix(&c.short()[..len], 3)?.contains(&c));
+        assert!(graph.find_by_id_prefix(&c.full()[..len], 3)?.contains(&c));
+    }
+    assert_eq!(graph.resolve_id(&c.full()[..16])?, c);
+
+    // Ids begin with their creation time, so these were all created within the span of a short prefix
+    match graph.resolve_id(&a.full()[..4]) {
+        Err(Error::Ambiguous(mut candidates)) => {
+            candidates.sort();
+            let mut all = vec![a, b, c];
+            all.sort();
+            assert_eq!(candidates, all);
+        },
+        other => panic!("expected ambiguity, got {:?}", other),
+    }
+    assert_eq!(graph.find_by_id_prefix(&a.full()[..4], 2)?.len(), 2);
+
+    graph.remove(&b, Cascade::Remove)?;
+    assert!(matches!(graph.resolve_id(&b.full()), Err(Error::NotFound)));
+    assert!(matches!(graph.resolve_id(&b.short()), Err(Error::NotFound)));
+
+    for invalid in ["", "a-b_c", "AAAAAAAAAAAAAAAAAAAAAAA"].iter() {
+        assert!(matches!(graph.resolve_id(invalid), Err(Error::InvalidId)));
+    }
+
+    // Parsing a full id needs no lookup, so removed entities parse too
+    assert_eq!(EntityId::from_full(&b.full())?, b);
+    assert!(matches!(EntityId::from_full(&b.short()), Err(Error::InvalidId)));
+    assert!(matches!(EntityId::from_full("a-b_cAAAAAAAAAAAAAAAAA"), Err(Error::InvalidId)));
+
+    Ok(())
+}
+
+memory_and_sled!(resolve);
+
+#[test]
+fn migrate() -> Result<(), std::io::Error> {
+    let store = MemoryStore::default();
+    let graph: Hypergraph<_, String, String> = Hypergraph::new(KvAdapter::from_store(store.clone())?);
+    let (_, a) = graph.insert(vertex(vec![prop("name", "a")]))?;
+    drop(graph);
+
+    // As written by format version 2, which had no short id index
+    let short_ids = store.open_tree("hypergraph::entity_by_short_id")?;
+    for rec in short_ids.iter() {
+        short_ids.remove(rec?.0)?;
+    }
+    store.open_tree("hypergraph::meta")?.insert("format_version", 2u32.to_be_bytes())?;
+
+    let (adapter, applied) = KvAdapter::<_, String, String>::migrate_store(store.clone())?;
+    assert_eq!(applied.iter().map(|m| (m.from, m.to)).collect::<Vec<_>>(), vec![(2, 3)]);
+    assert_eq!(migrate::format_version(&store)?, Some(FORMAT_VERSION));
+
+    let graph = Hypergraph::new(adapter);
+    assert_eq!(graph.resolve_id(&a.short())?, a);
+    assert!(graph.adapter().check()?.is_clean());
+
+    Ok(())
+}
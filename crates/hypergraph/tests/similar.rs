@@ -0,0 +1,112 @@
+use std::collections::BTreeSet;
+
+use mindbase_hypergraph::{
+    adapter::StorageAdapter,
+    entity::{vertex, Property},
+    traits::TSymbol,
+    Hypergraph,
+};
+use serde::{Deserialize, Serialize};
+
+#[macro_use]
+mod common;
+
+/// A property key which is alike to others in proportion to the words they share, however those words are joined
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Key(String);
+
+impl Key {
+    fn words(&self) -> BTreeSet<String> {
+        let mut words = BTreeSet::new();
+        let mut word = String::new();
+        for c in self.0.chars() {
+            if (c == '_' || c.is_uppercase()) && !word.is_empty() {
+                words.insert(std::mem::take(&mut word));
+            }
+            if c != '_' {
+                word.extend(c.to_lowercase());
+            }
+        }
+        if !word.is_empty() {
+            words.insert(word);
+        }
+        words
+    }
+}
+
+impl TSymbol for Key {
+    fn similarity(&self, other: &Self) -> f64 {
+        let (mine, theirs) = (self.words(), other.words());
+        let union = mine.union(&theirs).count();
+        if union == 0 {
+            return 0.0;
+        }
+        mine.intersection(&theirs).count() as f64 / union as f64
+    }
+}
+
+fn prop(key: &str, value: &str) -> Property<Key, String> {
+    Property {
+        key: Key(key.to_string()),
+        value: value.to_string(),
+    }
+}
+
+fn similar<Stor>(graph: Hypergraph<Stor, Key, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<Key, String>,
+{
+    let ada = "Ada Lovelace".to_string();
+    let (_, snake) = graph.insert(vertex(vec![prop("full_name", &ada)]))?;
+    let (_, camel) = graph.insert(vertex(vec![prop("fullName", &ada)]))?;
+    let (_, short) = graph.insert(vertex(vec![prop("name", &ada)]))?;
+    graph.insert(vertex(vec![prop("full_name", "Charles Babbage")]))?;
+    graph.insert(vertex(vec![prop("nickname", &ada)]))?;
+
+    // Exact lookups only find the key as it was written
+    assert_eq!(graph.find_by_property(&Key("full_name".to_string()), &ada)?, vec![snake]);
+
+    let mut found = graph.find_by_similar_property(&Key("full_name".to_string()), &ada, 0.9)?;
+    found.sort();
+    let mut expected = vec![snake, camel];
+    expected.sort();
+    assert_eq!(found, expected);
+
+    // The most similar keys come first
+    let found = graph.find_by_similar_property(&Key("FullName".to_string()), &ada, 0.5)?;
+    assert_eq!(found.len(), 3);
+    assert_eq!(found[2], short);
+
+    let similar = graph.find_similar_symbols(&Key("full_name".to_string()), 0.5)?;
+    let keys = similar
+        .iter()
+        .map(|(symbol_ix, similarity)| Ok((graph.get_symbol(*symbol_ix)?.0, *similarity)))
+        .collect::<Result<Vec<_>, std::io::Error>>()?;
+    assert_eq!(&keys[2..], &[("name".to_string(), 0.5)]);
+    assert!(keys[..2].iter().all(|(_, similarity)| *similarity == 1.0));
+
+    assert!(graph.find_by_similar_property(&Key("surname".to_string()), &ada, 0.5)?.is_empty());
+    assert!(graph.find_by_similar_property(&Key("full_name".to_string()), &"Ada".to_string(), 0.5)?.is_empty());
+
+    Ok(())
+}
+
+memory_and_sled!(similar);
+
+#[test]
+fn exact_by_default() -> Result<(), std::io::Error> {
+    let graph: Hypergraph<_, String, String> = Hypergraph::memory();
+    let (_, a) = graph.insert(vertex(vec![Property {
+        key: "full_name".to_string(),
+        value: "Ada".to_string(),
+    }]))?;
+    graph.insert(vertex(vec![Property {
+        key: "fullName".to_string(),
+        value: "Ada".to_string(),
+    }]))?;
+
+    assert_eq!(graph.find_by_similar_property(&"full_name".to_string(), &"Ada".to_string(), 0.1)?, vec![a]);
+    assert_eq!(graph.find_similar_symbols(&"full_name".to_string(), 0.1)?.len(), 1);
+
+    Ok(())
+}
@@ -0,0 +1,221 @@
+use std::{
+    io::{Cursor, Seek, SeekFrom, Write},
+    sync::Arc,
+};
+
+use mindbase_hypergraph::{
+    adapter::{kv::KvAdapter, memory::MemoryAdapter, sled::SledAdapter},
+    entity::{directed, vertex},
+    kv::{memory::MemoryStore, Store, Transaction},
+    snapshot, Error, Hypergraph,
+};
+
+#[macro_use]
+mod common;
+use common::prop;
+
+fn populate<S: Store>(graph: &Hypergraph<KvAdapter<S, String, String>, String, String>) -> Result<(), std::io::Error> {
+    let (_, a) = graph.insert(vertex(vec![prop("name", "Alice")]))?;
+    let (_, b) = graph.insert(vertex(vec![prop("name", "Bob"), prop("bio", &"x".repeat(200))]))?;
+    graph.insert(directed(vec![prop("kind", "knows")], [a], [b]))?;
+    Ok(())
+}
+
+fn snapshot_restore<S: Store>(graph: Hypergraph<KvAdapter<S, String, String>, String, String>) -> Result<(), std::io::Error> {
+    populate(&graph)?;
+
+    let mut file = tempfile::tempfile()?;
+    let manifest = graph.adapter().snapshot(&mut file)?;
+    assert_eq!(manifest.entities, 3);
+
+    // The manifest can be read on its own, from the end of the file
+    file.seek(SeekFrom::Start(0))?;
+    assert_eq!(snapshot::read_manifest(&mut file)?, manifest);
+
+    // Restore into memory
+    file.seek(SeekFrom::Start(0))?;
+    let (restored, restored_manifest) = MemoryAdapter::<String, String>::restore_into(MemoryStore::default(), &mut file)?;
+    assert_eq!(restored_manifest, manifest);
+    let restored = Hypergraph::new(restored);
+    for rec in graph.iter() {
+        let (entity_id, entity) = rec?;
+        assert_eq!(format!("{:?}", restored.get(&entity_id)?), format!("{:?}", entity));
+    }
+    // and allocates new entities as usual
+    restored.insert(vertex(vec![prop("name", "Carol")]))?;
+
+    // Restore into a fresh directory
+    let tmpdir = tempfile::tempdir()?;
+    let path = tmpdir.path().join("mindbase.snapshot");
+    let mut named = std::fs::File::create(&path)?;
+    graph.adapter().snapshot(&mut named)?;
+    named.flush()?;
+
+    let (restored, _) = SledAdapter::<String, String>::restore(&path, tmpdir.path())?;
+    let restored: Hypergraph<_, String, String> = Hypergraph::new(restored);
+    assert_eq!(restored.iter().count(), 3);
+    let alice = restored.find_by_property(&"name".to_string(), &"Alice".to_string())?;
+    assert_eq!(restored.get_adjacencies(&alice[0])?.len(), 1);
+
+    // Never over the top of an existing database
+    drop(restored);
+    match SledAdapter::<String, String>::restore(&path, tmpdir.path()) {
+        Err(Error::AlreadyExists) => {},
+        other => panic!("expected AlreadyExists, got {:?}", other.map(|(_, m)| m)),
+    }
+
+    Ok(())
+}
+
+memory_and_sled!(snapshot_restore);
+
+#[test]
+fn corrupted() -> Result<(), std::io::Error> {
+    let graph: Hypergraph<MemoryAdapter<String, String>, String, String> = Hypergraph::memory();
+    populate(&graph)?;
+
+    let mut bytes = Vec::new();
+    graph.adapter().snapshot(&mut bytes)?;
+
+    // Flip a byte of the bio, which is stored in value_storage
+    let at = bytes.windows(3).rposition(|w| w == b"xxx").unwrap();
+    bytes[at] = b'y';
+    match MemoryAdapter::<String, String>::restore_into(MemoryStore::default(), Cursor::new(&bytes)) {
+        Err(Error::ChecksumMismatch(tree)) => assert_eq!(tree, "hypergraph::value_storage"),
+        other => panic!("expected ChecksumMismatch, got {:?}", other.map(|(_, m)| m)),
+    }
+
+    match MemoryAdapter::<String, String>::restore_into(MemoryStore::default(), Cursor::new(&bytes[1..])) {
+        Err(Error::InvalidSnapshot) => {},
+        other => panic!("expected InvalidSnapshot, got {:?}", other.map(|(_, m)| m)),
+    }
+
+    // A failed restore leaves no database behind, so it may be retried
+    let tmpdir = tempfile::tempdir()?;
+    let path = tmpdir.path().join("mindbase.snapshot");
+    std::fs::write(&path, &bytes)?;
+    match SledAdapter::<String, String>::restore(&path, tmpdir.path()) {
+        Err(Error::ChecksumMismatch(_)) => {},
+        other => panic!("expected ChecksumMismatch, got {:?}", other.map(|(_, m)| m)),
+    }
+    assert!(!tmpdir.path().join("mindbase.sled").exists());
+    let mut good = Vec::new();
+    graph.adapter().snapshot(&mut good)?;
+    std::fs::write(&path, &good)?;
+    let (restored, _) = SledAdapter::<String, String>::restore(&path, tmpdir.path())?;
+    assert_eq!(Hypergraph::<_, String, String>::new(restored).iter().count(), 3);
+
+    // Restoring requires empty trees
+    let store = MemoryStore::default();
+    let _ = MemoryAdapter::<String, String>::from_store(store.clone())?;
+    Hypergraph::new(MemoryAdapter::<String, String>::from_store(store.clone())?).insert(vertex(vec![prop("name", "Dave")]))?;
+    let mut clean = Vec::new();
+    graph.adapter().snapshot(&mut clean)?;
+    match MemoryAdapter::<String, String>::restore_into(store, Cursor::new(&clean)) {
+        Err(Error::AlreadyExists) => {},
+        other => panic!("expected AlreadyExists, got {:?}", other.map(|(_, m)| m)),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn concurrent_writes() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    let graph: Arc<Hypergraph<SledAdapter<String, String>, String, String>> = Arc::new(Hypergraph::new(SledAdapter::open(tmpdir.path())?));
+    populate(&graph)?;
+
+    // Keep writing edges between fresh vertices while the snapshot is taken
+    let writer = {
+        let graph = graph.clone();
+        std::thread::spawn(move || -> Result<(), Error> {
+            for i in 0..200 {
+                let (_, a) = graph.insert(vertex(vec![prop("n", &i.to_string())]))?;
+                let (_, b) = graph.insert(vertex(vec![prop("n", &i.to_string())]))?;
+                graph.insert(directed(vec![prop("kind", "next")], [a], [b]))?;
+            }
+            Ok(())
+        })
+    };
+
+    // Writes wait for the snapshot, rather than spoiling it
+    let mut bytes = Vec::new();
+    let manifest = graph.adapter().snapshot(&mut bytes)?;
+    writer.join().unwrap()?;
+    assert_eq!(graph.iter().count(), 603);
+
+    let (restored, _) = MemoryAdapter::<String, String>::restore_into(MemoryStore::default(), Cursor::new(&bytes))?;
+    let restored: Hypergraph<_, String, String> = Hypergraph::new(restored);
+    assert_eq!(restored.iter().count() as u64, manifest.entities);
+
+    // Every edge in the snapshot was written along with its members
+    for rec in restored.iter_edges() {
+        let (_, edge) = rec?;
+        for (member, _) in edge.members() {
+            restored.get(&member.entity_id)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes to the store partway through the snapshot, as something other than the adapter might
+struct Interloper<'a, S: Store> {
+    store: &'a S,
+    written: Vec<u8>,
+}
+
+impl<'a, S: Store> Write for Interloper<'a, S> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.written.len() < 100 && self.written.len() + buf.len() >= 100 {
+            let tree = self.store.open_tree("elsewhere")?;
+            let mut tx = Transaction::new();
+            tx.insert(&tree, b"mallory", b"was here");
+            self.store.apply(tx)?;
+        }
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn interrupted<S: Store>(graph: Hypergraph<KvAdapter<S, String, String>, String, String>) -> Result<(), std::io::Error> {
+    populate(&graph)?;
+
+    // A write from outside the adapter is not held up by the snapshot, but spoils it
+    let mut interloper = Interloper {
+        store: graph.adapter().store(),
+        written: Vec::new(),
+    };
+    match graph.adapter().snapshot(&mut interloper) {
+        Err(Error::Conflict) => {},
+        other => panic!("expected Conflict, got {:?}", other),
+    }
+
+    // What was written cannot be mistaken for a snapshot
+    match MemoryAdapter::<String, String>::restore_into(MemoryStore::default(), Cursor::new(&interloper.written)) {
+        Err(Error::Bincode(_)) | Err(Error::Io(_)) | Err(Error::InvalidSnapshot) => {},
+        other => panic!("expected an incomplete snapshot, got {:?}", other.map(|(_, m)| m)),
+    }
+
+    // Taken over, it is complete
+    let mut bytes = Vec::new();
+    let manifest = graph.adapter().snapshot(&mut bytes)?;
+    assert_eq!(manifest.entities, 3);
+    assert!(manifest.trees.iter().any(|tree| tree.name == "elsewhere"));
+
+    Ok(())
+}
+
+#[test]
+fn memory_interrupted() -> Result<(), std::io::Error> {
+    interrupted(Hypergraph::memory())
+}
+
+#[test]
+fn sled_interrupted() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    interrupted(Hypergraph::new(SledAdapter::open(tmpdir.path())?))
+}
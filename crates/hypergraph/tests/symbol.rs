@@ -0,0 +1,68 @@
+use mindbase_hypergraph::{
+    adapter::{sled::SledAdapter, StorageAdapter},
+    entity::vertex,
+    Error, Hypergraph,
+};
+
+mod common;
+use common::prop;
+
+fn interning<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String>,
+{
+    let s = |v: &str| v.to_string();
+
+    assert_eq!(graph.get_symbol_ix(&s("name"))?, None);
+
+    graph.insert(vertex(vec![prop("name", "lamp"), prop("room", "kitchen")]))?;
+    graph.insert(vertex(vec![prop("name", "fan"), prop("room", "kitchen")]))?;
+
+    let name = graph.get_symbol_ix(&s("name"))?.expect("interned on insert");
+    let room = graph.get_symbol_ix(&s("room"))?.expect("interned on insert");
+    assert_ne!(name, room);
+    assert_eq!(graph.put_symbol(&s("name"))?, name);
+    assert_eq!(graph.get_symbol(name)?, "name");
+    assert_eq!(graph.get_symbol(room)?, "room");
+
+    let colour = graph.put_symbol(&s("colour"))?;
+    assert!(colour != name && colour != room);
+    assert_eq!(graph.get_symbol_ix(&s("colour"))?, Some(colour));
+
+    Ok(())
+}
+
+/// sled releases its file lock from a background thread, so it may still be held just after the database is dropped
+fn reopen(path: &std::path::Path) -> Result<SledAdapter<String, String>, Error> {
+    let mut attempts = 0;
+    loop {
+        match SledAdapter::open(path) {
+            Err(Error::Sled(_)) if attempts < 100 => {
+                attempts += 1;
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            },
+            result => return result,
+        }
+    }
+}
+
+#[test]
+fn memory() -> Result<(), std::io::Error> {
+    interning(Hypergraph::memory())
+}
+
+#[test]
+fn sled() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    interning(Hypergraph::new(SledAdapter::open(tmpdir.path())?))?;
+
+    // Symbol ids allocated after reopening must not collide with those allocated before
+    let graph: Hypergraph<_, String, String> = Hypergraph::new(reopen(tmpdir.path())?);
+    let name = graph.get_symbol_ix(&"name".to_string())?.expect("persisted");
+    let shape = graph.put_symbol(&"shape".to_string())?;
+    assert_ne!(shape, name);
+    assert_eq!(graph.get_symbol(name)?, "name");
+    assert_eq!(graph.get_symbol(shape)?, "shape");
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use mindbase_hypergraph::{
+    adapter::{kv::KvAdapter, Cascade},
+    entity::{vertex, Amendment},
+    kv::{memory::MemoryStore, Store, Tree},
+    migrate::{self, FORMAT_VERSION},
+    text::TextQuery,
+    EntityId, Hypergraph,
+};
+
+#[macro_use]
+mod common;
+use common::prop;
+
+fn ids<S: Store>(graph: &Hypergraph<KvAdapter<S, String, String>, String, String>, query: TextQuery<String>) -> Result<Vec<EntityId>, std::io::Error> {
+    Ok(graph.search(&query)?.into_iter().map(|hit| hit.entity_id).collect())
+}
+
+fn sorted(mut ids: Vec<EntityId>) -> Vec<EntityId> {
+    ids.sort();
+    ids
+}
+
+fn search<S: Store>(graph: Hypergraph<KvAdapter<S, String, String>, String, String>) -> Result<(), std::io::Error> {
+    let (_, lamp) = graph.insert(vertex(vec![prop("name", "Living Room Lamp"), prop("note", "Dims to 40%")]))?;
+    let (_, lamps) = graph.insert(vertex(vec![prop("name", "Porch lamps"), prop("note", "On a timer, lamp by lamp")]))?;
+    let (_, heater) = graph.insert(vertex(vec![prop("name", "Heater"), prop("note", "In the living room, beside the lamp")]))?;
+
+    // Case folded, whole words only
+    assert_eq!(sorted(ids(&graph, TextQuery::parse("LAMP"))?), sorted(vec![lamp, lamps, heater]));
+    assert!(ids(&graph, TextQuery::parse("lam"))?.is_empty());
+
+    // Stemming is up to the query
+    assert_eq!(ids(&graph, TextQuery::parse("lamps"))?, vec![lamps]);
+    assert_eq!(sorted(ids(&graph, TextQuery::parse("lamps").stemmed())?), sorted(vec![lamp, lamps, heater]));
+
+    // Phrases must be in sequence within a single value
+    assert_eq!(sorted(ids(&graph, TextQuery::parse("\"living room\""))?), sorted(vec![lamp, heater]));
+    assert_eq!(ids(&graph, TextQuery::parse("\"living room lamp\""))?, vec![lamp]);
+    assert!(ids(&graph, TextQuery::parse("\"room living\""))?.is_empty());
+    assert!(ids(&graph, TextQuery::parse("\"lamp dims\""))?.is_empty());
+
+    // Every word must match, unless any will do
+    assert_eq!(ids(&graph, TextQuery::parse("lamp timer"))?, vec![lamps]);
+    assert_eq!(sorted(ids(&graph, TextQuery::parse("heater timer").any())?), sorted(vec![lamps, heater]));
+
+    // Only the text of the given properties
+    assert_eq!(sorted(ids(&graph, TextQuery::parse("lamp").within(vec!["name".to_string()]))?), sorted(vec![lamp]));
+    assert!(ids(&graph, TextQuery::parse("lamp").within(vec!["nothing".to_string()]))?.is_empty());
+
+    // Ranked by how often the words occur, in how little text
+    assert_eq!(ids(&graph, TextQuery::parse("lamp").limit(1))?, vec![lamps]);
+    let hits = graph.search(&TextQuery::parse("living lamp").any())?;
+    assert_eq!(hits.len(), 3);
+    assert!(hits.windows(2).all(|w| w[0].score >= w[1].score));
+    assert!(graph.search(&TextQuery::parse(""))?.is_empty());
+
+    // Kept up to date as entities are amended and removed
+    graph.amend(&heater, vec![Amendment::Replace(prop("note", "In the hallway"))])?;
+    assert!(!ids(&graph, TextQuery::parse("living"))?.contains(&heater));
+    assert_eq!(ids(&graph, TextQuery::parse("hallway"))?, vec![heater]);
+    graph.remove(&lamps, Cascade::Remove)?;
+    assert_eq!(ids(&graph, TextQuery::parse("lamp"))?, vec![lamp]);
+
+    let report = graph.adapter().check()?;
+    assert!(report.is_clean(), "{:?}", report.problems);
+
+    Ok(())
+}
+
+memory_and_sled!(search);
+
+#[test]
+fn migrate() -> Result<(), std::io::Error> {
+    let store = MemoryStore::default();
+    let graph: Hypergraph<_, String, String> = Hypergraph::new(KvAdapter::from_store(store.clone())?);
+    let (_, lamp) = graph.insert(vertex(vec![prop("name", "Living Room Lamp")]))?;
+    drop(graph);
+
+    // As written by format version 1, which had no full-text index
+    for tree in ["hypergraph::entity_by_term", "hypergraph::text_lengths"].iter() {
+        let tree = store.open_tree(tree)?;
+        for rec in tree.iter() {
+            tree.remove(rec?.0)?;
+        }
+    }
+    store.open_tree("hypergraph::meta")?.insert("format_version", 1u32.to_be_bytes())?;
+
+    let (adapter, applied) = KvAdapter::<_, String, String>::migrate_store(store.clone())?;
+    assert_eq!(applied.iter().map(|m| (m.from, m.to)).collect::<Vec<_>>(), vec![(1, 2), (2, 3)]);
+    assert_eq!(migrate::format_version(&store)?, Some(FORMAT_VERSION));
+
+    let graph = Hypergraph::new(adapter);
+    assert_eq!(ids(&graph, TextQuery::parse("lamp"))?, vec![lamp]);
+    assert!(graph.adapter().check()?.is_clean());
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use mindbase_hypergraph::{
+    adapter::{toboggan::TobogganAdapter, Cascade, ScanDirection},
+    entity::{undirected, vertex, Amendment},
+    kv::{toboggan::TobogganStore, Store, Tree as KvTree},
+    text::TextQuery,
+    Error, Hypergraph,
+};
+use toboggan_kv::{
+    adapter::{BTreeAdapter, SledAdapter},
+    Toboggan, Tree,
+};
+
+mod common;
+use common::prop;
+
+/// The hypergraph keeps to its own trees, alongside any others in the same toboggan store
+fn shared<T: Toboggan>(toboggan: T) -> Result<(), std::io::Error> {
+    let allegations = toboggan.open_tree("core::allegations")?;
+    allegations.insert("allegation", "by somebody")?;
+
+    let adapter: TobogganAdapter<T, String, String> = TobogganAdapter::from_store(TobogganStore::new(toboggan.clone()))?;
+    let graph = Hypergraph::new(adapter);
+    let s = |v: &str| v.to_string();
+
+    let (_, lamp) = graph.insert(vertex(vec![prop("name", "kitchen lamp"), prop("room", "kitchen")]))?;
+    let (_, fan) = graph.insert(vertex(vec![prop("name", "ceiling fan"), prop("room", "hall")]))?;
+    let (_, pair) = graph.insert(undirected(vec![prop("kind", "appliances")], [lamp, fan]))?;
+
+    assert_eq!(graph.find_by_property(&s("room"), &s("kitchen"))?, vec![lamp]);
+    assert_eq!(graph.range_by_property(&s("room"), .., ScanDirection::Reverse, None)?, vec![lamp, fan]);
+    assert_eq!(graph.search(&TextQuery::parse("lamp"))?[0].entity_id, lamp);
+    assert_eq!(graph.get_adjacencies(&fan)?, vec![pair]);
+
+    assert_eq!(graph.amend(&lamp, vec![Amendment::Replace(prop("room", "hall"))])?, 1);
+    let mut hall = graph.find_by_property(&s("room"), &s("hall"))?;
+    hall.sort();
+    let mut expected = vec![lamp, fan];
+    expected.sort();
+    assert_eq!(hall, expected);
+
+    assert_eq!(graph.remove(&fan, Cascade::Remove)?.len(), 2);
+    assert_eq!(graph.get_adjacencies(&lamp)?, vec![]);
+    assert!(graph.adapter().check()?.is_clean());
+
+    assert_eq!(allegations.get("allegation")?.as_deref(), Some(&b"by somebody"[..]));
+    assert!(graph.adapter().store().tree_names()?.iter().all(|name| name.starts_with("hypergraph::")));
+
+    Ok(())
+}
+
+#[test]
+fn memory() -> Result<(), std::io::Error> {
+    shared(BTreeAdapter::new())
+}
+
+#[test]
+fn sled() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    shared(SledAdapter::open(tmpdir.path())?)
+}
+
+/// The first byte of each key
+fn keys(iter: impl Iterator<Item = Result<(Vec<u8>, Vec<u8>), Error>>) -> Result<Vec<u8>, Error> {
+    iter.map(|rec| Ok(rec?.0[0])).collect()
+}
+
+/// Ranges seek to their start, and run from either end, over keys written before the store was opened as well as after
+fn ranges<T: Toboggan>(toboggan: T) -> Result<(), std::io::Error> {
+    let raw = toboggan.open_tree("numbers")?;
+    for i in (0u8..100).step_by(2) {
+        raw.insert([i], [i])?;
+    }
+
+    let store = TobogganStore::new(toboggan);
+    let tree = store.open_tree("numbers")?;
+    tree.insert([51u8], [51u8])?;
+    tree.remove([52u8])?;
+
+    assert_eq!(keys(tree.range([48u8]..[56u8]))?, vec![48, 50, 51, 54]);
+    assert_eq!(keys(tree.range([48u8]..=[56u8]).rev())?, vec![56, 54, 51, 50, 48]);
+    assert_eq!(keys(tree.range([97u8]..))?, vec![98]);
+    assert_eq!(keys(tree.range([60u8]..[50u8]))?, vec![]);
+    assert_eq!(tree.last()?, Some((vec![98], vec![98])));
+
+    // Meeting in the middle
+    let mut iter = tree.range([0u8]..[6u8]);
+    assert_eq!(iter.next().transpose()?.map(|(k, _)| k), Some(vec![0]));
+    assert_eq!(iter.next_back().transpose()?.map(|(k, _)| k), Some(vec![4]));
+    assert_eq!(iter.next().transpose()?.map(|(k, _)| k), Some(vec![2]));
+    assert!(iter.next_back().is_none());
+
+    // Written part way through, ahead of the iterator
+    let mut iter = tree.range([10u8]..[20u8]);
+    assert_eq!(iter.next().transpose()?.map(|(k, _)| k), Some(vec![10]));
+    tree.insert([11u8], [11u8])?;
+    tree.remove([12u8])?;
+    assert_eq!(keys(iter)?, vec![11, 14, 16, 18]);
+
+    assert!(store.drop_tree("numbers")?);
+    assert_eq!(tree.iter().count(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn memory_ranges() -> Result<(), std::io::Error> {
+    ranges(BTreeAdapter::new())
+}
+
+#[test]
+fn sled_ranges() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    ranges(SledAdapter::open(tmpdir.path())?)
+}
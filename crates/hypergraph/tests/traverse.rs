@@ -0,0 +1,111 @@
+use mindbase_hypergraph::{
+    adapter::{Cascade, StorageAdapter},
+    entity::{directed, undirected, vertex},
+    traverse::{Direction, Visit},
+    EntityId, Hypergraph,
+};
+
+#[macro_use]
+mod common;
+use common::prop;
+
+/// Each visited entity and its depth, sorted so that order within a level does not matter
+fn levels<I>(visits: I) -> Result<Vec<(usize, EntityId)>, std::io::Error>
+where
+    I: Iterator<Item = Result<Visit, mindbase_hypergraph::Error>>,
+{
+    let mut levels: Vec<(usize, EntityId)> = visits
+        .map(|v| v.map(|v| (v.depth, v.entity_id)))
+        .collect::<Result<_, _>>()?;
+    levels.sort();
+    Ok(levels)
+}
+
+fn sorted(mut levels: Vec<(usize, EntityId)>) -> Vec<(usize, EntityId)> {
+    levels.sort();
+    levels
+}
+
+fn in_order<I>(visits: I) -> Result<Vec<EntityId>, std::io::Error>
+where
+    I: Iterator<Item = Result<Visit, mindbase_hypergraph::Error>>,
+{
+    Ok(visits.map(|v| v.map(|v| v.entity_id)).collect::<Result<_, _>>()?)
+}
+
+fn traverse<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String>,
+{
+    let (_, a) = graph.insert(vertex(vec![prop("name", "a")]))?;
+    let (_, b) = graph.insert(vertex(vec![prop("name", "b")]))?;
+    let (_, c) = graph.insert(vertex(vec![prop("name", "c")]))?;
+    let (_, d) = graph.insert(vertex(vec![prop("name", "d")]))?;
+    let (_, e) = graph.insert(vertex(vec![prop("name", "e")]))?;
+
+    let (_, ab) = graph.insert(directed(vec![prop("kind", "next")], [a], [b]))?;
+    graph.insert(directed(vec![prop("kind", "next")], [b], [c]))?;
+    // Closes a cycle
+    graph.insert(directed(vec![prop("kind", "back")], [c], [a]))?;
+    graph.insert(undirected(vec![prop("kind", "sibling")], [b, d]))?;
+    graph.insert(directed(vec![prop("kind", "next")], [d], [e]))?;
+
+    let forward = levels(graph.traverse(a).direction(Direction::Forward))?;
+    assert_eq!(forward, sorted(vec![(0, a), (1, b), (2, c), (2, d), (3, e)]));
+
+    let mut visits = graph.traverse(a).direction(Direction::Forward);
+    assert_eq!(visits.next().transpose()?, Some(Visit {
+        entity_id: a,
+        depth: 0,
+        via: None
+    }));
+    assert_eq!(visits.next().transpose()?, Some(Visit {
+        entity_id: b,
+        depth: 1,
+        via: Some(ab)
+    }));
+
+    // Undirected hyperedges are crossed in either direction
+    let backward = levels(graph.traverse(a).direction(Direction::Backward))?;
+    assert_eq!(backward, sorted(vec![(0, a), (1, c), (2, b), (3, d)]));
+
+    let both = levels(graph.traverse(a).max_depth(1))?;
+    assert_eq!(both, sorted(vec![(0, a), (1, b), (1, c)]));
+
+    let depth_limited = levels(graph.traverse(a).direction(Direction::Forward).max_depth(2))?;
+    assert_eq!(depth_limited, sorted(vec![(0, a), (1, b), (2, c), (2, d)]));
+
+    let next_only = levels(
+        graph
+            .traverse(a)
+            .direction(Direction::Forward)
+            .filter(|k, v| Ok(k == "kind" && v == "next")),
+    )?;
+    assert_eq!(next_only, sorted(vec![(0, a), (1, b), (2, c)]));
+
+    Ok(())
+}
+
+fn order<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String>,
+{
+    let (_, a) = graph.insert(vertex(vec![prop("name", "a")]))?;
+    let (_, b) = graph.insert(vertex(vec![prop("name", "b")]))?;
+    let (_, c) = graph.insert(vertex(vec![prop("name", "c")]))?;
+    let (_, d) = graph.insert(vertex(vec![prop("name", "d")]))?;
+    let (_, z) = graph.insert(vertex(vec![prop("name", "z")]))?;
+
+    graph.insert(directed(vec![], [a], [b, c, z]))?;
+    graph.insert(directed(vec![], [b], [d]))?;
+
+    // Removed members of a hyperedge are not visited
+    graph.remove(&z, Cascade::FlagDangling)?;
+
+    assert_eq!(in_order(graph.traverse(a).direction(Direction::Forward))?, vec![a, b, c, d]);
+    assert_eq!(in_order(graph.traverse(a).direction(Direction::Forward).depth_first())?, vec![a, b, d, c]);
+
+    Ok(())
+}
+
+memory_and_sled!(traverse, order);
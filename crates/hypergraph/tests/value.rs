@@ -0,0 +1,52 @@
+use mindbase_hypergraph::{
+    adapter::{Cascade, ScanDirection, StorageAdapter},
+    entity::{vertex, Amendment, ValueHash},
+    Error, Hypergraph,
+};
+
+#[macro_use]
+mod common;
+use common::prop;
+
+fn content_addressed<Stor>(graph: Hypergraph<Stor, String, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<String, String>,
+{
+    let s = |v: &str| v.to_string();
+    let payload = r#"{"device": "kitchen lamp", "attributes": {"switch": "on", "level": 80}}"#;
+    let other = r#"{"device": "hallway fan", "attributes": {"switch": "off", "speed": "low"}}"#;
+
+    let (_, a) = graph.insert(vertex(vec![prop("json", payload), prop("name", "a")]))?;
+    let (_, b) = graph.insert(vertex(vec![prop("json", payload), prop("name", "b")]))?;
+    let (_, c) = graph.insert(vertex(vec![prop("json", other), prop("name", "c")]))?;
+
+    // Large values are stored once, and may be fetched by hash
+    assert_eq!(graph.get_value(&ValueHash::of(&s(payload)))?, payload);
+    assert_eq!(graph.get_value(&ValueHash::of(&s(other)))?, other);
+
+    // Small values are inline
+    match graph.get_value(&ValueHash::of(&s("a"))) {
+        Err(Error::NotFound) => {}
+        other => panic!("expected Error::NotFound, got {:?}", other),
+    }
+
+    // Referenced values are indexed and resolved just as inline ones are
+    let mut ab = graph.find_by_property(&s("json"), &s(payload))?;
+    ab.sort();
+    let mut expected = vec![a, b];
+    expected.sort();
+    assert_eq!(ab, expected);
+
+    let in_order = graph.range_by_property(&s("json"), .., ScanDirection::Forward, None)?;
+    assert_eq!(in_order[0], c);
+    assert_eq!(graph.get_properties(&c, None)?[0].value, other);
+
+    graph.amend(&a, vec![Amendment::Retract(s("json"))])?;
+    graph.remove(&b, Cascade::Remove)?;
+    assert_eq!(graph.find_by_property(&s("json"), &s(payload))?, vec![]);
+    assert_eq!(graph.get_properties(&a, Some(0))?[0].value, payload);
+
+    Ok(())
+}
+
+memory_and_sled!(content_addressed);
@@ -842,11 +842,11 @@ impl Artifact {
     pub fn apply(&self, query: &Query) -> Result<ArtifactId, Error> {
         let artifact_id = match self {
             Artifact::Agent(agent) => query.mb.put_artifact(agent.get_agent_id(query.mb)?)?,
-            Artifact::Url(url) => query.mb.put_artifact(crate::artifact::Url { url: url.url.clone() })?,
-            Artifact::Text(text) => query.mb.put_artifact(crate::artifact::Text::new(&text.text))?,
+            Artifact::Url(url) => query.mb.put_artifact(mindbase_types::artifact::Url { url: url.url.clone() })?,
+            Artifact::Text(text) => query.mb.put_artifact(mindbase_types::artifact::Text::new(&text.text))?,
             Artifact::DataNode(node) => {
                 let data_type = node.data_type.apply(query)?;
-                query.mb.put_artifact(crate::artifact::DataNode {
+                query.mb.put_artifact(mindbase_types::artifact::DataNode {
                     data_type,
                     data: node.data.clone(),
                 })?
@@ -5,7 +5,7 @@ use super::{
 };
 use crate::search::SearchContext;
 
-use mindbase_artifact::Artifact;
+use mindbase_types::artifact::Artifact;
 use mindbase_claim::Claim;
 use mindbase_hypergraph::Hypergraph;
 use mindbase_symbol as sy;
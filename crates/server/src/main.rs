@@ -1,15 +1,18 @@
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
 
-use chrono::{TimeZone, Utc};
+use chrono::{Duration, TimeZone, Utc};
 use mindbase_hypergraph::adapter::sled::SledAdapter;
 use mindbase_hypergraph::adapter::StorageAdapter;
 use mindbase_hypergraph::entity::{vertex, Property};
-use mindbase_types::MBValue;
+use mindbase_hypergraph::EntityId;
+use mindbase_types::{Decimal, MBValue};
 use tonic::{transport::Server, Request, Response, Status};
 
+use proto::admin_server::{Admin, AdminServer};
 use proto::entities_server::{Entities, EntitiesServer};
-use proto::{PutEntityReply, PutEntityRequest};
+use proto::{PutEntityReply, PutEntityRequest, SnapshotReply, SnapshotRequest};
 use tokio;
 
 pub mod proto {
@@ -26,6 +29,18 @@ struct Opt {
     #[structopt(short, long, parse(from_os_str))]
     mindbase: Option<PathBuf>,
 
+    /// Restore a snapshot into the MindBase storage path before serving. The path must not already contain a database
+    #[structopt(long, parse(from_os_str))]
+    restore: Option<PathBuf>,
+
+    /// Upgrade the MindBase storage to the current on-disk format before serving
+    #[structopt(long)]
+    migrate: bool,
+
+    /// Directory in which to write snapshots requested over the admin API. Defaults to `backups` under the storage path
+    #[structopt(long, parse(from_os_str))]
+    backups: Option<PathBuf>,
+
     // /// Verbose mode (-v, -vv, -vvv, etc.)
     // #[structopt(short, long, parse(from_occurrences))]
     // verbose: u8,
@@ -38,7 +53,25 @@ use mindbase_hypergraph::Hypergraph;
 
 pub struct MyService {
     // hg: Hypergraph<SledStore, String, Artifact<String>>,
-    hg: SledAdapter<String, MBValue, ()>,
+    hg: Arc<SledAdapter<String, MBValue, ()>>,
+}
+
+pub struct AdminService {
+    hg: Arc<SledAdapter<String, MBValue, ()>>,
+    /// Snapshots are only ever written here
+    backups: PathBuf,
+}
+
+impl AdminService {
+    /// The path of the named file within the backup directory. Only a plain file name is accepted,
+    /// so that nothing may be written outside of it
+    fn backup_path(&self, name: &str) -> Result<PathBuf, Status> {
+        let mut components = Path::new(name).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(file_name)), None) => Ok(self.backups.join(file_name)),
+            _ => Err(Status::invalid_argument(format!("{:?} is not a plain file name", name))),
+        }
+    }
 }
 
 #[tonic::async_trait]
@@ -58,9 +91,23 @@ impl Entities for MyService {
                     PV::String(s) => MBValue::String(s.into()),
                     PV::Date(ts) => MBValue::DateTime(Utc.timestamp(ts.seconds, ts.nanos as u32)),
                     PV::Uint32(v) => MBValue::Uint32(*v),
-                    PV::Struct(s) => unimplemented!(),
+                    PV::Float64(v) => MBValue::Float64(*v),
+                    PV::Int64(v) => MBValue::Int64(*v),
+                    PV::Bool(v) => MBValue::Bool(*v),
+                    PV::Null(_) => MBValue::Null,
+                    PV::Decimal(d) => MBValue::Decimal(
+                        d.parse::<Decimal>()
+                            .map_err(|e| Status::invalid_argument(format!("Invalid decimal {:?}: {}", d, e)))?,
+                    ),
+                    PV::Duration(d) => MBValue::Duration(Duration::seconds(d.seconds) + Duration::nanoseconds(d.nanos as i64)),
+                    PV::EntityRef(id) => MBValue::EntityRef(
+                        EntityId::from_full(id).map_err(|_| Status::invalid_argument(format!("Invalid entity id {:?}", id)))?,
+                    ),
+                    PV::Struct(_) => {
+                        return Err(Status::invalid_argument(format!("Struct values are not supported, for property {:?}", key)))
+                    },
                     PV::Json(j) => MBValue::Json(j.to_owned()),
-                    PV::Bytes(b) => unimplemented!(),
+                    PV::Bytes(b) => MBValue::Bytes(b.to_owned()),
                 };
 
                 properties.push(Property {
@@ -70,7 +117,7 @@ impl Entities for MyService {
             }
         }
 
-        let (ix, id) = self.hg.insert(vertex(properties)).unwrap();
+        let (ix, id) = self.hg.insert(vertex(properties), None).unwrap();
         // TODO add storage engine insertion guts here
         let reply = PutEntityReply { id: id.full() };
 
@@ -78,6 +125,50 @@ impl Entities for MyService {
     }
 }
 
+#[tonic::async_trait]
+impl Admin for AdminService {
+    async fn snapshot(&self, request: Request<SnapshotRequest>) -> Result<Response<SnapshotReply>, Status> {
+        let path = self.backup_path(&request.into_inner().name)?;
+        let file = match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                return Err(Status::already_exists(format!("{} already exists", path.display())))
+            },
+            Err(e) => return Err(Status::internal(e.to_string())),
+        };
+        let hg = self.hg.clone();
+
+        // The snapshot takes a while, and there is no need to hold up the executor meanwhile
+        let manifest = tokio::task::spawn_blocking(move || {
+            let written = hg.snapshot(std::io::BufWriter::new(&file));
+            if written.is_err() {
+                // Leave no partial snapshot behind to be mistaken for a good one
+                let _ = std::fs::remove_file(&path);
+            }
+            written
+        })
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?
+        .map_err(|e| Status::internal(format!("{:?}", e)))?;
+
+        println!("Wrote snapshot of {} entities", manifest.entities);
+        let reply = SnapshotReply {
+            entities: manifest.entities,
+            trees: manifest
+                .trees
+                .into_iter()
+                .map(|tree| proto::TreeManifest {
+                    name: tree.name,
+                    entries: tree.entries,
+                    checksum: tree.checksum.to_vec(),
+                })
+                .collect(),
+        };
+
+        Ok(Response::new(reply))
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let opt = Opt::from_args();
@@ -90,11 +181,44 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("Loading database in {}", path.display());
 
-    let hg = SledAdapter::open(path).unwrap();
-    let service = MyService { hg };
+    if let Some(snapshot) = &opt.restore {
+        println!("Restoring {}", snapshot.display());
+        let (_, manifest) = SledAdapter::<String, MBValue, ()>::restore(snapshot, path).unwrap();
+        for tree in manifest.trees.iter() {
+            println!("  {}", tree);
+        }
+        println!("Restored {} entities", manifest.entities);
+    }
+
+    let hg = if opt.migrate {
+        let (hg, applied) = SledAdapter::migrate(path).unwrap();
+        for migration in applied {
+            println!("Migrated format {} to {}: {}", migration.from, migration.to, migration.description);
+        }
+        hg
+    } else {
+        match SledAdapter::open(path) {
+            Ok(hg) => hg,
+            Err(mindbase_hypergraph::Error::UnsupportedFormat(version)) => {
+                eprintln!("The database is in format version {}, and must be upgraded with --migrate", version);
+                std::process::exit(1);
+            },
+            Err(e) => panic!("{:?}", e),
+        }
+    };
+    let backups = match opt.backups {
+        Some(backups) => backups,
+        None => path.join("backups"),
+    };
+    std::fs::create_dir_all(&backups)?;
+
+    let hg = Arc::new(hg);
+    let service = MyService { hg: hg.clone() };
+    let admin = AdminService { hg, backups };
 
     Server::builder()
         .add_service(EntitiesServer::new(service))
+        .add_service(AdminServer::new(admin))
         .serve(opt.bind_to)
         .await?;
 
@@ -1,13 +1,3 @@
-use crate::{
-    analogy::associative::{AssociativeAnalogyMember, Side},
-    fuzzyset as fs,
-    traits::Entity,
-};
-use crate::{
-    fuzzyset::FuzzySet,
-    symbol::{Symbol, SymbolMember},
-};
-
 #[macro_export]
 #[warn(unused_macros)]
 macro_rules! sym {
@@ -43,49 +33,51 @@ macro_rules! sym {
 //     }
 // }
 
-impl<E> From<(Symbol<E>, Symbol<E>)> for FuzzySet<AssociativeAnalogyMember<E>>
-where
-    E: Entity,
-{
-    fn from(tuple: (Symbol<E>, Symbol<E>)) -> Self {
-        let mut set = FuzzySet::new();
-
-        for i in tuple.0.into_iter() {
-            set.insert(fs::Item {
-                degree: i.degree,
-                member: AssociativeAnalogyMember {
-                    entity: i.member.entity,
-                    side: Side::Left,
-                },
-            });
-        }
-
-        for i in tuple.1.into_iter() {
-            set.insert(fs::Item {
-                degree: i.degree,
-                member: AssociativeAnalogyMember {
-                    entity: i.member.entity,
-                    side: Side::Right,
-                },
-            });
-        }
-
-        set
-    }
-}
+// TODO 2 - restore along with the analogy module
+// impl<E> From<(Symbol<E>, Symbol<E>)> for FuzzySet<AssociativeAnalogyMember<E>>
+// where
+//     E: Entity,
+// {
+//     fn from(tuple: (Symbol<E>, Symbol<E>)) -> Self {
+//         let mut set = FuzzySet::new();
+//
+//         for i in tuple.0.into_iter() {
+//             set.insert(fs::Item {
+//                 degree: i.degree,
+//                 member: AssociativeAnalogyMember {
+//                     entity: i.member.entity,
+//                     side: Side::Left,
+//                 },
+//             });
+//         }
+//
+//         for i in tuple.1.into_iter() {
+//             set.insert(fs::Item {
+//                 degree: i.degree,
+//                 member: AssociativeAnalogyMember {
+//                     entity: i.member.entity,
+//                     side: Side::Right,
+//                 },
+//             });
+//         }
+//
+//         set
+//     }
+// }
 
-impl<E, T> From<&(T, f32)> for fs::Item<SymbolMember<E>>
-where
-    T: Into<E>,
-    T: Clone,
-    E: Entity,
-{
-    fn from(item: &(T, f32)) -> Self {
-        fs::Item {
-            member: SymbolMember {
-                entity: item.0.clone().into(),
-            },
-            degree: item.1,
-        }
-    }
-}
+// TODO 2 - Item now lives in mindbase_fuzzyset, so this must move there, or become a constructor of SymbolMember
+// impl<E, T> From<&(T, f32)> for fs::Item<SymbolMember<E>>
+// where
+//     T: Into<E>,
+//     T: Clone,
+//     E: Entity,
+// {
+//     fn from(item: &(T, f32)) -> Self {
+//         fs::Item {
+//             member: SymbolMember {
+//                 entity: item.0.clone().into(),
+//             },
+//             degree: item.1,
+//         }
+//     }
+// }
@@ -1,9 +1,12 @@
 use crate::traits::Entity;
-use mindbase_fuzzyset::{self as fs, FuzzySet};
+use mindbase_fuzzyset::{self as fs, fuzzyset::FuzzySet, traits as fst};
+use mindbase_hypergraph::traits::TSymbol;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use std::cmp::Ordering;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "E: Serialize + DeserializeOwned")]
 pub struct Symbol<E>
 where
     E: Entity,
@@ -12,7 +15,7 @@ where
     pub set: FuzzySet<SymbolMember<E>>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SymbolMember<E> {
     pub entity: E,
 }
@@ -29,22 +32,22 @@ where
     pub fn new<L, T>(list: L) -> Self
     where
         L: IntoIterator<Item = T>,
-        T: Into<fs::Item<SymbolMember<E>>>,
+        T: Into<fs::fuzzyset::Item<SymbolMember<E>>>,
     {
         let mut set = FuzzySet::from_list(list);
 
         Symbol { set }
     }
 
-    pub fn iter<'a>(&'a self) -> std::slice::Iter<'a, fs::Item<SymbolMember<E>>> {
+    pub fn iter<'a>(&'a self) -> std::slice::Iter<'a, fs::fuzzyset::Item<SymbolMember<E>>> {
         self.set.iter()
     }
 
-    pub fn into_iter(self) -> std::vec::IntoIter<fs::Item<SymbolMember<E>>> {
+    pub fn into_iter(self) -> std::vec::IntoIter<fs::fuzzyset::Item<SymbolMember<E>>> {
         self.set.into_iter()
     }
 
-    pub fn drain<'a, T>(&'a mut self, range: T) -> std::vec::Drain<'a, fs::Item<SymbolMember<E>>>
+    pub fn drain<'a, T>(&'a mut self, range: T) -> std::vec::Drain<'a, fs::fuzzyset::Item<SymbolMember<E>>>
     where
         T: std::ops::RangeBounds<usize>,
     {
@@ -56,7 +59,16 @@ where
     }
 }
 
-impl<E> fs::Member for SymbolMember<E>
+impl<E> std::fmt::Display for SymbolMember<E>
+where
+    E: Entity,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.entity)
+    }
+}
+
+impl<E> fst::Member for SymbolMember<E>
 where
     E: Entity,
 {
@@ -64,11 +76,22 @@ where
         self.entity.cmp(&other.entity)
     }
 
-    fn display_fmt(&self, item: &fs::Item<Self>, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn display_fmt(&self, item: &fs::fuzzyset::Item<Self>, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "({},{:0.2})", self.entity, item.degree)
     }
 }
 
+/// Symbols may be used as property keys, such that properties are found by keys which are similar rather than identical.
+/// Members are kept in order, so the same symbol is always interned as the same bytes
+impl<E> TSymbol for Symbol<E>
+where
+    E: Entity + Serialize + DeserializeOwned,
+{
+    fn similarity(&self, other: &Self) -> f64 {
+        self.set.similarity(&other.set)
+    }
+}
+
 impl<E> std::fmt::Display for Symbol<E>
 where
     E: Entity,
@@ -80,11 +103,11 @@ where
 
 impl<E> IntoIterator for Symbol<E>
 where
-    E: Entity, // IntoItem: Into<fs::Item<AssociativeAnalogyMember<E>>>,
+    E: Entity, // IntoItem: Into<fs::fuzzyset::Item<AssociativeAnalogyMember<E>>>,
 {
-    type Item = fs::Item<SymbolMember<E>>;
+    type Item = fs::fuzzyset::Item<SymbolMember<E>>;
 
-    type IntoIter = std::vec::IntoIter<fs::Item<SymbolMember<E>>>;
+    type IntoIter = std::vec::IntoIter<fs::fuzzyset::Item<SymbolMember<E>>>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.into_iter()
@@ -1 +1,4 @@
+/// Anything which a `Symbol` may be made up of
+pub trait Entity: Clone + Ord + std::fmt::Display + std::fmt::Debug {}
 
+impl<T> Entity for T where T: Clone + Ord + std::fmt::Display + std::fmt::Debug {}
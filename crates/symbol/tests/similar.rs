@@ -0,0 +1,64 @@
+use mindbase_fuzzyset::fuzzyset::Item;
+use mindbase_hypergraph::{
+    adapter::{sled::SledAdapter, StorageAdapter},
+    entity::{vertex, Property},
+    Hypergraph,
+};
+use mindbase_symbol::symbol::{Symbol, SymbolMember};
+
+/// A symbol of the given words, each to the given degree
+fn symbol(members: &[(&str, f64)]) -> Symbol<String> {
+    Symbol::new(members.iter().map(|(word, degree)| Item {
+        degree: *degree,
+        member: SymbolMember { entity: word.to_string() },
+    }))
+}
+
+fn prop(key: Symbol<String>, value: &str) -> Property<Symbol<String>, String> {
+    Property {
+        key,
+        value: value.to_string(),
+    }
+}
+
+fn similar<Stor>(graph: Hypergraph<Stor, Symbol<String>, String>) -> Result<(), std::io::Error>
+where
+    Stor: StorageAdapter<Symbol<String>, String>,
+{
+    let ada = "Ada Lovelace".to_string();
+    let full_name = symbol(&[("full", 1.0), ("name", 1.0)]);
+    let (_, snake) = graph.insert(vertex(vec![prop(full_name.clone(), &ada)]))?;
+    // The same words in another order are the same symbol
+    let (_, reordered) = graph.insert(vertex(vec![prop(symbol(&[("name", 1.0), ("full", 1.0)]), &ada)]))?;
+    let (_, camel) = graph.insert(vertex(vec![prop(symbol(&[("full", 1.0), ("name", 0.9)]), &ada)]))?;
+    let (_, short) = graph.insert(vertex(vec![prop(symbol(&[("name", 1.0)]), &ada)]))?;
+    graph.insert(vertex(vec![prop(symbol(&[("nick", 1.0), ("name", 1.0)]), "Enchantress of Numbers")]))?;
+
+    let similar = graph.find_similar_symbols(&full_name, 0.5)?;
+    let degrees: Vec<f64> = similar.iter().map(|(_, similarity)| *similarity).collect();
+    assert_eq!(degrees, vec![1.0, 0.95, 0.5]);
+    assert_eq!(graph.get_symbol(similar[2].0)?.to_string(), symbol(&[("name", 1.0)]).to_string());
+
+    let mut found = graph.find_by_similar_property(&full_name, &ada, 0.9)?;
+    found.sort();
+    let mut expected = vec![snake, reordered, camel];
+    expected.sort();
+    assert_eq!(found, expected);
+
+    let found = graph.find_by_similar_property(&full_name, &ada, 0.5)?;
+    assert_eq!(found.len(), 4);
+    assert_eq!(found[3], short);
+
+    Ok(())
+}
+
+#[test]
+fn memory() -> Result<(), std::io::Error> {
+    similar(Hypergraph::memory())
+}
+
+#[test]
+fn sled() -> Result<(), std::io::Error> {
+    let tmpdir = tempfile::tempdir()?;
+    similar(Hypergraph::new(SledAdapter::open(tmpdir.path())?))
+}
@@ -0,0 +1,80 @@
+//! The values which MBQL writes to the hypergraph for its artifacts.
+//!
+//! Only `FlatText` takes part in full-text search. The others are found by exact match, as they are compared as a whole
+//! ```
+//! use mindbase_hypergraph::{entity::{vertex, Property}, text::TextQuery, Hypergraph};
+//! use mindbase_types::artifact::{Artifact, Text};
+//!
+//! let graph = Hypergraph::<_, String, Artifact>::memory();
+//! let prop = Property { key: "artifact".to_string(), value: Text::new("Saturday night").into() };
+//! let (_, saturday) = graph.insert(vertex(vec![prop])).unwrap();
+//! assert_eq!(graph.search(&TextQuery::parse("night")).unwrap()[0].entity_id, saturday);
+//! ```
+
+use mindbase_hypergraph::{traits::TValue, EntityId};
+use serde::{Deserialize, Serialize};
+
+// Variants are stored by their position, so new ones must only ever be added at the end
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum Artifact {
+    Agent(keyplace::AgentId),
+    Url(Url),
+    FlatText(Text),
+    DataNode(DataNode),
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Url {
+    pub url: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Text {
+    pub text: String,
+}
+
+impl Text {
+    pub fn new(text: &str) -> Self {
+        Text { text: text.to_string() }
+    }
+}
+
+/// A node of data, whose type is given by another entity
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct DataNode {
+    pub data_type: EntityId,
+    pub data: Option<Vec<u8>>,
+}
+
+impl From<keyplace::AgentId> for Artifact {
+    fn from(agent_id: keyplace::AgentId) -> Self {
+        Artifact::Agent(agent_id)
+    }
+}
+
+impl From<Url> for Artifact {
+    fn from(url: Url) -> Self {
+        Artifact::Url(url)
+    }
+}
+
+impl From<Text> for Artifact {
+    fn from(text: Text) -> Self {
+        Artifact::FlatText(text)
+    }
+}
+
+impl From<DataNode> for Artifact {
+    fn from(node: DataNode) -> Self {
+        Artifact::DataNode(node)
+    }
+}
+
+impl TValue for Artifact {
+    fn text(&self) -> Option<&str> {
+        match self {
+            Artifact::FlatText(text) => Some(&text.text),
+            _ => None,
+        }
+    }
+}
@@ -1,7 +1,7 @@
-use core::fmt;
-use mindbase_util::Error;
 use serde::{Deserialize, Serialize};
 
+/// The hash of a content-addressed value, serialized as base64. Convertible to and from the hypergraph's own `ValueHash`,
+/// by which it stores and shares large values
 #[derive(Clone, Serialize, Deserialize, PartialEq, PartialOrd, Eq, Ord)]
 pub struct ValueHash(
     #[serde(
@@ -11,6 +11,21 @@ pub struct ValueHash(
     pub(crate) [u8; 32],
 );
 
+impl From<mindbase_hypergraph::entity::ValueHash> for ValueHash {
+    fn from(hash: mindbase_hypergraph::entity::ValueHash) -> Self {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(hash.as_bytes());
+        ValueHash(bytes)
+    }
+}
+
+impl From<ValueHash> for mindbase_hypergraph::entity::ValueHash {
+    fn from(hash: ValueHash) -> Self {
+        // Both are 32 bytes
+        mindbase_hypergraph::entity::ValueHash::from_slice(&hash.0).unwrap()
+    }
+}
+
 // impl Artifact
 // where
 //     T: ArtifactNodeType,
@@ -0,0 +1,35 @@
+//! How those `MBValue`s whose types have no serde support of their own, or none which bincode can read, are stored
+
+/// Whole seconds and the remaining nanoseconds, as `google.protobuf.Duration` has them.
+/// Both truncate toward zero, so a negative duration has no positive part
+pub(crate) mod duration {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn subsec_nanos(duration: &Duration) -> i32 {
+        (*duration - Duration::seconds(duration.num_seconds())).num_nanoseconds().unwrap_or(0) as i32
+    }
+
+    pub(crate) fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        (duration.num_seconds(), subsec_nanos(duration)).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let (seconds, nanos) = <(i64, i32)>::deserialize(deserializer)?;
+        Ok(Duration::seconds(seconds) + Duration::nanoseconds(nanos as i64))
+    }
+}
+
+/// The 16 bytes of `Decimal::serialize`, which keep the scale, so that "12.50" is stored as "12.50" rather than "12.5"
+pub(crate) mod decimal {
+    use rust_decimal::Decimal;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(decimal: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+        decimal.serialize().serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+        Ok(Decimal::deserialize(<[u8; 16]>::deserialize(deserializer)?))
+    }
+}
@@ -1,7 +1,13 @@
+pub mod artifact;
 pub mod cas;
+mod encoding;
+pub mod provenance;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use encoding::{decimal, duration};
+use mindbase_hypergraph::{ordered, traits::TValue, EntityId};
 pub use mindbase_util::Error;
+pub use rust_decimal::Decimal;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sha2::{Digest, Sha512Trunc256};
 
@@ -19,17 +25,86 @@ pub enum MBValue {
     // Struct()
     Json(Vec<u8>),
     Bytes(Vec<u8>),
+    // Variants are stored by their position, so new ones must only ever be added at the end
+    Float64(f64),
+    Int64(i64),
+    Bool(bool),
+    Null,
+    Decimal(#[serde(with = "decimal")] Decimal),
+    /// May be negative, as for offsets
+    Duration(#[serde(with = "duration")] Duration),
+    /// Refers to another entity, which need not exist
+    EntityRef(EntityId),
 }
 
+/// Each ordered variant gets a distinct tag byte, so that the variants never interleave in a range index
+impl TValue for MBValue {
+    fn sort_key(&self) -> Option<Vec<u8>> {
+        let mut key = Vec::new();
+        match self {
+            MBValue::String(s) => {
+                key.push(1);
+                key.extend_from_slice(&ordered::encode_bytes(s.as_bytes()));
+            },
+            MBValue::DateTime(d) => {
+                key.push(2);
+                key.extend_from_slice(&ordered::encode_i64(d.timestamp()));
+                key.extend_from_slice(&ordered::encode_u32(d.timestamp_subsec_nanos()));
+            },
+            MBValue::Uint32(v) => {
+                key.push(3);
+                key.extend_from_slice(&ordered::encode_u32(*v));
+            },
+            MBValue::Float64(v) => {
+                key.push(4);
+                key.extend_from_slice(&ordered::encode_f64(*v));
+            },
+            MBValue::Int64(v) => {
+                key.push(5);
+                key.extend_from_slice(&ordered::encode_i64(*v));
+            },
+            MBValue::Bool(v) => {
+                key.push(6);
+                key.push(*v as u8);
+            },
+            MBValue::Duration(d) => {
+                // Whole seconds and the remaining nanoseconds both truncate toward zero, so they order as the duration does
+                let seconds = d.num_seconds();
+                key.push(7);
+                key.extend_from_slice(&ordered::encode_i64(seconds));
+                key.extend_from_slice(&ordered::encode_i64(duration::subsec_nanos(d) as i64));
+            },
+            MBValue::Agent(_) | MBValue::Json(_) | MBValue::Bytes(_) | MBValue::Null | MBValue::Decimal(_) | MBValue::EntityRef(_) => {
+                return None
+            },
+        }
+        Some(key)
+    }
+    fn text(&self) -> Option<&str> {
+        match self {
+            MBValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// Agents and bytes are written in base64, and JSON as it is
 impl std::fmt::Display for MBValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            MBValue::Agent(a) => todo!(),
-            MBValue::String(s) => todo!(),
-            MBValue::DateTime(d) => todo!(),
-            MBValue::Uint32(v) => todo!(),
-            MBValue::Json(j) => todo!(),
-            MBValue::Bytes(b) => todo!(),
+            MBValue::Agent(agent_id) => write!(f, "{}", base64::encode_config(agent_id.pubkey, base64::STANDARD_NO_PAD)),
+            MBValue::String(s) => write!(f, "{}", s),
+            MBValue::DateTime(d) => write!(f, "{}", d.to_rfc3339()),
+            MBValue::Uint32(v) => write!(f, "{}", v),
+            MBValue::Json(j) => write!(f, "{}", String::from_utf8_lossy(j)),
+            MBValue::Bytes(b) => write!(f, "{}", base64::encode_config(b, base64::STANDARD_NO_PAD)),
+            MBValue::Float64(v) => write!(f, "{}", v),
+            MBValue::Int64(v) => write!(f, "{}", v),
+            MBValue::Bool(v) => write!(f, "{}", v),
+            MBValue::Null => write!(f, "null"),
+            MBValue::Decimal(v) => write!(f, "{}", v),
+            MBValue::Duration(d) => write!(f, "{}", d),
+            MBValue::EntityRef(entity_id) => write!(f, "{}", entity_id.full()),
         }
     }
 }
@@ -0,0 +1,20 @@
+use keyplace::{AgentId, Signature};
+use mindbase_hypergraph::traits::TProvenance;
+use serde::{Deserialize, Serialize};
+
+/// The agent who asserted an entity or property, and their signature over it.
+/// Signatures are stored as given, and are not verified by the hypergraph. Reads by agent, such as
+/// `Hypergraph::get_asserted_by`, go by the agent alone, so verifying the signature is up to the reader
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Provenance {
+    pub agent: AgentId,
+    pub signature: Option<Signature>,
+}
+
+impl TProvenance for Provenance {
+    type Agent = AgentId;
+
+    fn agent(&self) -> &AgentId {
+        &self.agent
+    }
+}
@@ -0,0 +1,46 @@
+use mindbase_hypergraph::{
+    entity::{vertex, Property},
+    text::TextQuery,
+    Hypergraph,
+};
+use mindbase_types::artifact::{Artifact, DataNode, Text, Url};
+
+fn prop(value: Artifact) -> Property<String, Artifact> {
+    Property {
+        key: "artifact".to_string(),
+        value,
+    }
+}
+
+#[test]
+fn text_artifacts() -> Result<(), std::io::Error> {
+    let graph: Hypergraph<_, String, Artifact> = Hypergraph::memory();
+    let (_, saturday) = graph.insert(vertex(vec![prop(Text::new("Saturday night's alright for fighting").into())]))?;
+    let (_, weekday) = graph.insert(vertex(vec![prop(Text::new("Abstract day of the week").into())]))?;
+    let url = Artifact::from(Url {
+        url: "https://example.com/saturday".to_string(),
+    });
+    let (_, link) = graph.insert(vertex(vec![prop(url.clone())]))?;
+    graph.insert(vertex(vec![prop(
+        DataNode {
+            data_type: weekday,
+            data: Some(b"saturday".to_vec()),
+        }
+        .into(),
+    )]))?;
+
+    // Only text is searched, and not urls or data
+    let hits = graph.search(&TextQuery::parse("saturday"))?;
+    assert_eq!(hits.iter().map(|hit| hit.entity_id).collect::<Vec<_>>(), vec![saturday]);
+    assert_eq!(graph.search(&TextQuery::parse("\"day of the week\""))?[0].entity_id, weekday);
+    assert!(graph.search(&TextQuery::parse("\"week day\""))?.is_empty());
+
+    // All are found by exact match
+    assert_eq!(graph.find_by_property(&"artifact".to_string(), &url)?, vec![link]);
+    assert_eq!(
+        graph.find_by_property(&"artifact".to_string(), &Text::new("Abstract day of the week").into())?,
+        vec![weekday]
+    );
+
+    Ok(())
+}
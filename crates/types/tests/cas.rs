@@ -0,0 +1,18 @@
+use mindbase_types::cas::ValueHash;
+
+#[test]
+fn base64() -> Result<(), std::io::Error> {
+    let hash = mindbase_hypergraph::entity::ValueHash::of(&"a value long enough to be stored by hash".to_string());
+    let ours = ValueHash::from(hash);
+
+    // Serialized as base64, not as raw bytes
+    let bytes = bincode::serialize(&ours).map_err(std::io::Error::other)?;
+    let encoded = hash.to_string();
+    assert_eq!(&bytes[8..], encoded.as_bytes());
+
+    let decoded: ValueHash = bincode::deserialize(&bytes).map_err(std::io::Error::other)?;
+    assert!(decoded == ours);
+    assert_eq!(mindbase_hypergraph::entity::ValueHash::from(decoded), hash);
+
+    Ok(())
+}
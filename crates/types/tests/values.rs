@@ -0,0 +1,115 @@
+use chrono::{DateTime, Duration, Utc};
+use mindbase_hypergraph::{
+    adapter::ScanDirection,
+    entity::{vertex, Property},
+    export::Format,
+    EntityId, Hypergraph,
+};
+use mindbase_types::{Decimal, MBValue};
+
+fn prop(key: &str, value: MBValue) -> Property<String, MBValue> {
+    Property {
+        key: key.to_string(),
+        value,
+    }
+}
+
+fn values(other: EntityId) -> Vec<MBValue> {
+    vec![
+        MBValue::Float64(-21.5),
+        MBValue::Int64(-3),
+        MBValue::Bool(false),
+        MBValue::Null,
+        MBValue::Decimal("-12.50".parse::<Decimal>().unwrap()),
+        MBValue::Duration(Duration::milliseconds(-1500)),
+        MBValue::EntityRef(other),
+    ]
+}
+
+#[test]
+fn round_trip() -> Result<(), std::io::Error> {
+    let graph: Hypergraph<_, String, MBValue> = Hypergraph::memory();
+    let (_, other) = graph.insert(vertex(vec![]))?;
+    let values = values(other);
+
+    let (_, e) = graph.insert(vertex(self::values(other).into_iter().map(|v| prop("v", v)).collect::<Vec<_>>()))?;
+    let stored: Vec<MBValue> = graph.get_properties(&e, None)?.into_iter().map(|p| p.value).collect();
+    assert_eq!(stored, values);
+    for value in values.iter() {
+        assert_eq!(graph.find_by_property(&"v".to_string(), value)?, vec![e]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn ranges() -> Result<(), std::io::Error> {
+    let graph: Hypergraph<_, String, MBValue> = Hypergraph::memory();
+    let mut inserted = Vec::new();
+    for (float, int, millis) in [(-2.5, -25, -2500), (-1.2, -12, -1200), (-0.5, -5, -500), (0.0, 0, 0), (0.7, 7, 700), (3.0, 30, 3000)].iter() {
+        let (_, e) = graph.insert(vertex(vec![
+            prop("float", MBValue::Float64(*float)),
+            prop("int", MBValue::Int64(*int)),
+            prop("offset", MBValue::Duration(Duration::milliseconds(*millis))),
+        ]))?;
+        inserted.push(e);
+    }
+
+    let found = graph.range_by_property(&"float".to_string(), MBValue::Float64(-2.0)..MBValue::Float64(1.0), ScanDirection::Forward, None)?;
+    assert_eq!(found, inserted[1..5]);
+    let found = graph.range_by_property(&"int".to_string(), MBValue::Int64(-12)..=MBValue::Int64(0), ScanDirection::Forward, None)?;
+    assert_eq!(found, inserted[1..4]);
+    let found = graph.range_by_property(
+        &"offset".to_string(),
+        MBValue::Duration(Duration::milliseconds(-1200))..MBValue::Duration(Duration::seconds(1)),
+        ScanDirection::Reverse,
+        None,
+    )?;
+    let mut expected = inserted[1..5].to_vec();
+    expected.reverse();
+    assert_eq!(found, expected);
+
+    // Variants never interleave
+    let floats = MBValue::Float64(f64::NEG_INFINITY)..=MBValue::Float64(f64::INFINITY);
+    assert!(graph.range_by_property(&"int".to_string(), floats, ScanDirection::Forward, None)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn export() -> Result<(), std::io::Error> {
+    let graph: Hypergraph<_, String, MBValue> = Hypergraph::memory();
+    let (_, other) = graph.insert(vertex(vec![]))?;
+    let mut values = values(other);
+    values.extend(vec![
+        MBValue::Agent(keyplace::AgentId { pubkey: [7; 32] }),
+        MBValue::String("Lamp".to_string()),
+        MBValue::DateTime("2021-03-04T05:06:07Z".parse::<DateTime<Utc>>().unwrap()),
+        MBValue::Uint32(42),
+        MBValue::Json(br#"{"watts":60}"#.to_vec()),
+        MBValue::Bytes(vec![0, 1, 2]),
+    ]);
+    graph.insert(vertex(values.into_iter().map(|v| prop("v", v)).collect::<Vec<_>>()))?;
+
+    let mut dot = Vec::new();
+    graph.export(Format::Dot, &mut dot)?;
+    let dot = String::from_utf8(dot).unwrap();
+    let rendered = [
+        "-21.5".to_string(),
+        "-3".to_string(),
+        "false".to_string(),
+        "null".to_string(),
+        "-12.50".to_string(),
+        "-PT1.5S".to_string(),
+        other.full(),
+        "BwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc".to_string(),
+        "Lamp".to_string(),
+        "2021-03-04T05:06:07+00:00".to_string(),
+        "42".to_string(),
+        r#"{\"watts\":60}"#.to_string(),
+        "AAEC".to_string(),
+    ];
+    assert!(dot.contains(&format!("\"v\"=\"{}\"", rendered.join(", "))));
+
+    Ok(())
+}